@@ -0,0 +1,178 @@
+use chrono::{DateTime, Local};
+use tracing::error;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::ProcessType;
+
+/// Returns the name of the OS user running the app.
+pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Something worth keeping a record of on a shared lab machine.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// The config file differs from the last time the app loaded it, or was
+    /// changed from the GUI.
+    ConfigEdited { path: PathBuf },
+    /// A secret was set or replaced in the OS keyring (the value is never logged).
+    SecretUpdated { name: String },
+    /// A secret was removed from the OS keyring.
+    SecretDeleted { name: String },
+    /// A running process was stopped, e.g. with its Stop button or because
+    /// its recording session ended.
+    Stopped { process_type: ProcessType, pid: u32, reason: String },
+    /// A running process was stopped by an emergency stop.
+    EmergencyStop {
+        process_type: ProcessType,
+        pid: u32,
+        #[serde(default)]
+        reason: String,
+    },
+    /// The last episode of a dataset was deleted from the GUI.
+    EpisodeDeleted { repo_id: String, episode: u64, frames: u64 },
+}
+
+/// One line of the audit log.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Local>,
+    pub user: String,
+    /// The operator selected in the GUI at the time, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+/// Append-only log of security relevant actions, stored as JSON lines.
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Default location of the audit log.
+    pub fn default_path() -> PathBuf {
+        crate::paths::data_dir().join("audit.log")
+    }
+
+    /// Appends `event`, attributed to `user` and the selected `operator`.
+    /// Failures are logged but never block the action being audited.
+    pub fn record(&self, user: &str, operator: Option<&str>, event: AuditEvent) {
+        let entry = AuditEntry {
+            timestamp: Local::now(),
+            user: user.to_string(),
+            operator: operator.map(str::to_string),
+            event,
+        };
+        if let Err(e) = self.append(&entry) {
+            error!("Failed to write audit log '{}': {}", self.path.display(), e);
+        }
+    }
+
+    fn append(&self, entry: &AuditEntry) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e| e.to_string())
+    }
+
+    /// Records a `ConfigEdited` event if `contents` differs from what was loaded
+    /// from `config_path` last time. The fingerprints are kept next to the log.
+    pub fn check_config(&self, user: &str, config_path: &Path, contents: &str) {
+        if let Some(path) = self.update_fingerprint(config_path, contents) {
+            self.record(user, None, AuditEvent::ConfigEdited { path });
+        }
+    }
+
+    /// Records a `ConfigEdited` event for a change the app made itself, and
+    /// remembers the new contents so the next load does not report it again.
+    pub fn config_saved(&self, user: &str, operator: Option<&str>, config_path: &Path, contents: &str) {
+        self.update_fingerprint(config_path, contents);
+        let path = fs::canonicalize(config_path).unwrap_or_else(|_| config_path.to_path_buf());
+        self.record(user, operator, AuditEvent::ConfigEdited { path });
+    }
+
+    /// Stores the fingerprint of `contents` for `config_path`. Returns the
+    /// canonical path if a different fingerprint was stored before.
+    fn update_fingerprint(&self, config_path: &Path, contents: &str) -> Option<PathBuf> {
+        let fingerprints_path = self.path.with_file_name("config-fingerprints.json");
+        let mut fingerprints: HashMap<PathBuf, String> = fs::read_to_string(&fingerprints_path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+
+        let key = fs::canonicalize(config_path).unwrap_or_else(|_| config_path.to_path_buf());
+        // A SHA-256 rather than `DefaultHasher`, whose output may change
+        // between Rust releases and would make every config look edited.
+        let fingerprint: String = Sha256::digest(contents).iter().map(|b| format!("{:02x}", b)).collect();
+
+        let changed = match fingerprints.insert(key.clone(), fingerprint.clone()) {
+            Some(previous) if previous == fingerprint => return None,
+            // The first time we see a file there is nothing to compare against.
+            None => None,
+            Some(_) => Some(key),
+        };
+        if let Some(parent) = fingerprints_path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let result = serde_json::to_string_pretty(&fingerprints)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(&fingerprints_path, json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            error!("Failed to save '{}': {}", fingerprints_path.display(), e);
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprints_are_stable_digests() {
+        let dir = std::env::temp_dir().join(format!("audit-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("config.toml");
+        fs::write(&config_path, "").unwrap();
+        let log = AuditLog::new(dir.join("audit.log"));
+
+        assert_eq!(log.update_fingerprint(&config_path, "a = 1"), None);
+        assert_eq!(log.update_fingerprint(&config_path, "a = 1"), None);
+        let saved = fs::read_to_string(dir.join("config-fingerprints.json")).unwrap();
+        // sha256sum of "a = 1", which no toolchain upgrade can change.
+        assert!(saved.contains("\"b5bc1ffd90912fb18bef6e7d80909192c7a6492896320156d67fbaf104c6544a\""), "{}", saved);
+        assert!(log.update_fingerprint(&config_path, "a = 2").is_some());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn emergency_stops_logged_before_they_had_a_reason_still_parse() {
+        let line = concat!(
+            r#"{"timestamp":"2026-10-01T10:00:00+02:00","user":"lab","#,
+            r#""event":"emergency_stop","process_type":"Record","pid":7}"#
+        );
+        let entry: AuditEntry = serde_json::from_str(line).unwrap();
+        assert!(matches!(entry.event, AuditEvent::EmergencyStop { pid: 7, ref reason, .. } if reason.is_empty()));
+    }
+
+}
@@ -0,0 +1,92 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::config::Config;
+use crate::paths;
+
+/// The `[checkpoints]` config table: where trained policies are looked for to
+/// fill the `{checkpoint}` placeholder.
+#[derive(Deserialize, Clone, Debug)]
+pub struct CheckpointsConfig {
+    /// Directory searched for checkpoints, relative to `commands.working_directory`.
+    #[serde(default = "default_directory")]
+    pub directory: String,
+    /// Policies on the Hugging Face Hub, offered after the local checkpoints.
+    #[serde(default)]
+    pub hub_repos: Vec<String>,
+}
+
+fn default_directory() -> String {
+    "outputs/train".to_string()
+}
+
+impl Default for CheckpointsConfig {
+    fn default() -> Self {
+        Self { directory: default_directory(), hub_repos: Vec::new() }
+    }
+}
+
+/// A trained policy that can be evaluated or replayed.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    /// Shown in the dropdown, e.g. `act_demo/checkpoints/020000`.
+    pub label: String,
+    /// What `{checkpoint}` is replaced with: the `pretrained_model` folder or the Hub repo id.
+    pub value: String,
+    /// When the checkpoint was saved; `None` for Hub repos.
+    pub modified: Option<SystemTime>,
+}
+
+/// How deep below the directory checkpoints are looked for. LeRobot saves
+/// them as `<run>/checkpoints/<step>/pretrained_model`.
+const MAX_DEPTH: usize = 5;
+
+/// The directory searched for checkpoints, with the working directory applied.
+pub fn directory(config: &Config) -> PathBuf {
+    let directory = PathBuf::from(paths::expand(&config.checkpoints.directory));
+    if directory.is_relative() {
+        Path::new(&paths::expand(&config.commands.working_directory)).join(directory)
+    } else {
+        directory
+    }
+}
+
+/// Finds the saved checkpoints, newest first, followed by the configured Hub repos.
+pub fn scan(config: &Config) -> Vec<Checkpoint> {
+    let root = directory(config);
+    let mut found = Vec::new();
+    find(&root, &root, 0, &mut found);
+    found.sort_by_key(|c| std::cmp::Reverse(c.modified));
+    found.extend(config.checkpoints.hub_repos.iter().map(|repo| Checkpoint {
+        label: format!("{} (Hub)", repo),
+        value: repo.clone(),
+        modified: None,
+    }));
+    found
+}
+
+fn find(root: &Path, dir: &Path, depth: usize, found: &mut Vec<Checkpoint>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        // Skips LeRobot's `last` link, which duplicates the newest checkpoint.
+        let Ok(file_type) = entry.file_type() else { continue };
+        if !file_type.is_dir() {
+            continue;
+        }
+        let path = entry.path();
+        if entry.file_name() == "pretrained_model" && path.join("config.json").is_file() {
+            let label = path
+                .parent()
+                .and_then(|p| p.strip_prefix(root).ok())
+                .map(|p| p.display().to_string())
+                .filter(|label| !label.is_empty())
+                .unwrap_or_else(|| path.display().to_string());
+            let modified = fs::metadata(path.join("config.json")).and_then(|m| m.modified()).ok();
+            found.push(Checkpoint { label, value: path.display().to_string(), modified });
+        } else if depth < MAX_DEPTH {
+            find(root, &path, depth + 1, found);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use tracing::{debug, warn};
+use serde::Deserialize;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The `[clock_check]` config table: before recording, the clocks of the
+/// robot host and the listed machines are compared with this PC's, as
+/// timestamps from clocks that disagree quietly misalign a dataset.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ClockCheckConfig {
+    /// Largest tolerated difference between two clocks, in milliseconds.
+    #[serde(default = "default_max_skew_ms")]
+    pub max_skew_ms: f64,
+    /// Other machines taking part in the recording, reached with ssh.
+    #[serde(default)]
+    pub hosts: Vec<String>,
+}
+
+fn default_max_skew_ms() -> f64 {
+    20.0
+}
+
+/// How often the clocks are compared at most; a session records many episodes.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// How far the clock of a host is ahead of this PC's.
+#[derive(Debug, Clone)]
+pub struct Skew {
+    pub offset_ms: f64,
+    /// Half the round trip: the offset is known to within this much.
+    pub uncertainty_ms: f64,
+}
+
+fn now() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs_f64()).unwrap_or(0.0)
+}
+
+/// Measures the clock of `host` over ssh. Its time is read once the
+/// connection is up, so the handshake does not count towards the round trip.
+pub fn measure(host: &str) -> Result<Skew, String> {
+    let mut child = Command::new("ssh")
+        .args(["-o", "BatchMode=yes", "-o", "ConnectTimeout=5", host])
+        .arg("date +%s.%N; read line; date +%s.%N")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run ssh: {}", e))?;
+    let mut stdin = child.stdin.take().ok_or_else(|| "No input to ssh".to_string())?;
+    let mut lines = BufReader::new(child.stdout.take().ok_or_else(|| "No output from ssh".to_string())?).lines();
+    let mut exchange = || -> Option<Skew> {
+        lines.next()?.ok()?;
+        let sent = now();
+        writeln!(stdin).ok()?;
+        let remote: f64 = lines.next()?.ok()?.trim().parse().ok()?;
+        let received = now();
+        Some(Skew {
+            offset_ms: (remote - (sent + received) / 2.0) * 1000.0,
+            uncertainty_ms: (received - sent) / 2.0 * 1000.0,
+        })
+    };
+    let skew = exchange();
+    drop(stdin);
+    let output = child.wait_with_output().map_err(|e| format!("ssh {}: {}", host, e))?;
+    skew.ok_or_else(|| {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        format!("Could not read the clock of {}: {}", host, stderr.trim())
+    })
+}
+
+/// Whether this PC's clock is synchronized over NTP, as far as systemd knows.
+fn ntp_synchronized() -> Option<bool> {
+    let output = Command::new("timedatectl").args(["show", "--property=NTPSynchronized", "--value"]).output().ok()?;
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Compares the clocks of `hosts` with this PC's. Returns a description of
+/// every problem found, empty if the clocks agree.
+pub fn check(config: &ClockCheckConfig, hosts: &[String]) -> Vec<String> {
+    let mut problems = Vec::new();
+    if ntp_synchronized() == Some(false) {
+        problems.push("This PC's clock is not synchronized with NTP.".to_string());
+    }
+    for host in hosts {
+        match measure(host) {
+            // Only a skew that is certainly too large is reported.
+            Ok(skew) if skew.offset_ms.abs() - skew.uncertainty_ms > config.max_skew_ms => {
+                problems.push(format!(
+                    "The clock of {} is {:+.0} ms (±{:.0} ms) off, more than {} ms.",
+                    host, skew.offset_ms, skew.uncertainty_ms, config.max_skew_ms
+                ));
+            }
+            Ok(skew) => debug!("Clock of {} is {:+.1} ms (±{:.1} ms) off", host, skew.offset_ms, skew.uncertainty_ms),
+            Err(e) => problems.push(e),
+        }
+    }
+    for problem in &problems {
+        warn!("{}", problem);
+    }
+    problems
+}
@@ -0,0 +1,544 @@
+use std::collections::HashMap;
+use tracing::debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{Config, Variant};
+use crate::{paths, placeholders};
+
+/// Builds the shell command that runs `command` (usually the command of
+/// `variant`, possibly edited): it sources conda, changes into the working
+/// directory and runs the command with its placeholders filled from `values`.
+/// Placeholders without a value are kept. The working directory and conda
+/// environment are those of `variant`.
+pub fn resolve(config: &Config, variant: &Variant, command: &str, values: &HashMap<String, String>) -> String {
+    let specific_command = placeholders::substitute(command, values);
+
+    // Combine the prefix and the specific command. The directory is expanded
+    // here and quoted so paths with spaces survive.
+    let working_directory = paths::expand(variant.working_directory(&config.commands));
+    let full_command = if !working_directory.is_empty() {
+        format!("cd {} && {}", shell_quote(&working_directory), specific_command)
+    } else {
+        specific_command
+    };
+
+    format!("{}{}", conda_init(config, variant.conda_env(&config.commands)), full_command)
+}
+
+/// The shell prefix that makes `conda` available and activates `env`, ending
+/// in `&& ` (or empty if conda is not configured).
+fn conda_init(config: &Config, env: Option<&str>) -> String {
+    // Construct a shell-script that first sources conda, then runs the command.
+    // This is the most reliable way to ensure the 'conda' command is available.
+    let mut init = String::new();
+    if let Some(conda_path) = config.app.conda_path.as_deref().filter(|p| !p.is_empty()) {
+        // Expanded here, as quoting keeps the shell from doing it.
+        let script = PathBuf::from(paths::expand(conda_path)).join("etc/profile.d/conda.sh");
+        init.push_str(&format!("source {} && ", shell_quote(&script.display().to_string())));
+    }
+    if let Some(env) = env {
+        init.push_str(&format!("conda activate {} && ", shell_quote(env)));
+    }
+    init
+}
+
+/// Terminals used on each desktop (as named in `$XDG_CURRENT_DESKTOP`) when
+/// xdg-terminal-exec is not installed.
+const DESKTOP_TERMINALS: &[(&str, &str)] = &[
+    ("GNOME", "gnome-terminal"),
+    ("Unity", "gnome-terminal"),
+    ("X-Cinnamon", "gnome-terminal"),
+    ("KDE", "konsole"),
+    ("XFCE", "xfce4-terminal"),
+    ("MATE", "mate-terminal"),
+    ("LXQt", "qterminal"),
+];
+
+/// The terminal emulator to launch commands in: the user's default terminal
+/// as chosen through xdg-terminal-exec if it is installed, else the configured
+/// one, else (with `terminal` unset or `"auto"`) the usual one of their
+/// desktop, else konsole.
+pub fn terminal(config: &Config) -> String {
+    // For other OSes:
+    // - macOS: "osascript", "-e", &format!("tell app \"Terminal\" to do script \"{}\"", command_str)
+    // - Windows: "cmd", "/C", &format!("start {}", command_str)
+    let terminal = if in_path("xdg-terminal-exec") {
+        "xdg-terminal-exec".to_string()
+    } else {
+        match config.app.terminal.as_deref().filter(|t| !t.is_empty() && *t != "auto") {
+            Some(terminal) => terminal.to_string(),
+            None => desktop_terminal(),
+        }
+    };
+    debug!("Using terminal: '{}'", terminal);
+    terminal
+}
+
+/// The usual terminal of the user's desktop, else konsole.
+fn desktop_terminal() -> String {
+    let desktops = std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+    desktops
+        .split(':')
+        .filter_map(|desktop| DESKTOP_TERMINALS.iter().find(|(d, _)| d.eq_ignore_ascii_case(desktop)))
+        .map(|(_, terminal)| *terminal)
+        .find(|terminal| in_path(terminal))
+        .unwrap_or("konsole")
+        .to_string()
+}
+
+/// A `Command` that opens the terminal running `shell_command`.
+///
+/// Most terminals take the command after `-e`. xdg-terminal-exec takes it as
+/// separate arguments, and gnome-terminal has deprecated `-e` and only stays
+/// in the foreground (so the app can tell when the command ends) with `--wait`.
+pub fn in_terminal(config: &Config, shell_command: &str) -> Command {
+    let terminal = terminal(config);
+    let mut command = Command::new(&terminal);
+    match Path::new(&terminal).file_name().and_then(|n| n.to_str()) {
+        Some("xdg-terminal-exec") => command.args(["bash", "-c", shell_command]),
+        Some("gnome-terminal") => command.args(["--wait", "--", "bash", "-c", shell_command]),
+        _ => command.arg("-e").arg(shell_command),
+    };
+    command
+}
+
+/// Opens an interactive shell in the working directory of `variant` with its
+/// conda environment activated, for ad-hoc debugging. The shell is not
+/// supervised.
+pub fn open_shell(config: &Config, variant: &Variant) -> Result<(), String> {
+    // An rc file keeps the user's own .bashrc while activating conda after it,
+    // so `conda init`'s auto-activation of `base` does not win.
+    let mut rc = String::from("[ -f ~/.bashrc ] && source ~/.bashrc\n");
+    let init = conda_init(config, variant.conda_env(&config.commands));
+    if !init.is_empty() {
+        rc.push_str(init.trim_end_matches("&& "));
+        rc.push('\n');
+    }
+    let working_directory = paths::expand(variant.working_directory(&config.commands));
+    if !working_directory.is_empty() {
+        rc.push_str(&format!("cd {}\n", shell_quote(&working_directory)));
+    }
+    let rc_path = crate::paths::data_dir().join("shell-rc.sh");
+    fs::create_dir_all(crate::paths::data_dir())
+        .and_then(|()| fs::write(&rc_path, rc))
+        .map_err(|e| format!("Failed to write '{}': {}", rc_path.display(), e))?;
+
+    let shell = format!("bash --rcfile {} -i", shell_quote(&rc_path.display().to_string()));
+    let mut child = crate::sandbox::on_host(in_terminal(config, &shell))
+        .spawn()
+        .map_err(|e| format!("Failed to start terminal '{}': {}", terminal(config), e))?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Shell builtins and keywords that never exist as files.
+const SHELL_BUILTINS: &[&str] = &[
+    ".", ":", "[", "[[", "alias", "break", "case", "cd", "continue", "declare", "do", "done",
+    "echo", "elif", "else", "esac", "eval", "exit", "export", "false", "fi", "for", "function",
+    "if", "local", "printf", "pwd", "read", "return", "set", "shift", "source", "test", "then",
+    "trap", "true", "ulimit", "umask", "unset", "until", "wait", "while",
+];
+
+/// Wrappers whose first non-option argument is the program that actually runs.
+const WRAPPERS: &[&str] = &["env", "exec", "nice", "nohup", "sudo", "time"];
+
+/// Splits a shell command into simple commands (separated by `&&`, `||`, `;`,
+/// `|`, `&` or newlines) of unquoted words. This is not a full shell parser, but
+/// it understands quoting well enough to find the program each command runs.
+fn simple_commands(command: &str) -> Vec<Vec<String>> {
+    let mut commands = vec![Vec::new()];
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+    let end_word = |word: &mut String, in_word: &mut bool, commands: &mut Vec<Vec<String>>| {
+        if *in_word {
+            commands.last_mut().unwrap().push(std::mem::take(word));
+            *in_word = false;
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                for c in chars.by_ref() {
+                    if c == '\'' {
+                        break;
+                    }
+                    word.push(c);
+                }
+            }
+            '"' => {
+                in_word = true;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => word.extend(chars.next()),
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                // A backslash-newline is a line continuation, anything else is escaped.
+                match chars.next() {
+                    Some('\n') | None => {}
+                    Some(c) => {
+                        in_word = true;
+                        word.push(c);
+                    }
+                }
+            }
+            '&' | '|' | ';' | '\n' | '(' | ')' => {
+                end_word(&mut word, &mut in_word, &mut commands);
+                if chars.peek() == Some(&c) && (c == '&' || c == '|') {
+                    chars.next();
+                }
+                if !commands.last().unwrap().is_empty() {
+                    commands.push(Vec::new());
+                }
+            }
+            c if c.is_whitespace() => end_word(&mut word, &mut in_word, &mut commands),
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    end_word(&mut word, &mut in_word, &mut commands);
+    commands.retain(|c| !c.is_empty());
+    commands
+}
+
+/// The start of the simple command in `command` passing the most `--flags`,
+/// up to its first flag and without leading variable assignments, e.g.
+/// `python -m lerobot.record` in
+/// `cd ~/lerobot && python -m lerobot.record --robot.type=so100_follower ...`.
+pub fn flag_program(command: &str) -> Option<String> {
+    let flags = |words: &Vec<String>| words.iter().filter(|w| w.starts_with("--")).count();
+    let words = simple_commands(command).into_iter().filter(|w| flags(w) > 0).max_by_key(flags)?;
+    let program: Vec<String> = words
+        .iter()
+        .skip_while(|w| w.split_once('=').is_some_and(|(name, _)| !name.is_empty() && !name.contains('/')))
+        .take_while(|w| !w.starts_with("--"))
+        .map(|w| shell_quote(w))
+        .collect();
+    Some(program.join(" ")).filter(|p| !p.is_empty())
+}
+
+/// Returns the program a simple command runs, skipping leading variable
+/// assignments and wrappers like `sudo` or `env`.
+fn program(words: &[String]) -> Option<&str> {
+    let mut after_wrapper = false;
+    for word in words {
+        let is_assignment = word
+            .split_once('=')
+            .is_some_and(|(name, _)| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+        // Options of a wrapper, e.g. `nice -n 10` or `sudo -E`.
+        let is_wrapper_arg = after_wrapper && (word.starts_with('-') || word.chars().all(|c| c.is_ascii_digit()));
+        if is_assignment || is_wrapper_arg {
+            continue;
+        }
+        if WRAPPERS.contains(&word.as_str()) {
+            after_wrapper = true;
+            continue;
+        }
+        return Some(word);
+    }
+    None
+}
+
+/// Looks for `program` in the directories of `$PATH`.
+fn in_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// The base directory of the conda installation, from the config or, failing
+/// that, from the `CONDA_EXE` variable conda sets up in its shells.
+fn conda_base(config: &Config) -> Option<PathBuf> {
+    match config.app.conda_path.as_deref().filter(|p| !p.is_empty()) {
+        Some(path) => Some(PathBuf::from(paths::expand(path))),
+        None => std::env::var_os("CONDA_EXE")
+            .map(PathBuf::from)
+            .and_then(|exe| Some(exe.parent()?.parent()?.to_path_buf())),
+    }
+}
+
+/// The `bin` directory of the conda environment `env`.
+fn conda_env_bin(base: &Path, env: &str) -> PathBuf {
+    if env == "base" {
+        base.join("bin")
+    } else {
+        base.join("envs").join(env).join("bin")
+    }
+}
+
+/// Checks that every program `command` runs exists, either as a path (relative
+/// paths are resolved against the working directory), on `$PATH`, or in the
+/// conda environment active at that point (via `conda activate X` or
+/// `conda run -n X`). The working directory and initial conda environment are
+/// those of `variant`. Returns a description of each missing program.
+///
+/// Aliases and functions defined in the user's `.bashrc` cannot be seen from
+/// here, so callers should let the operator launch anyway.
+pub fn missing_programs(config: &Config, variant: &Variant, command: &str) -> Vec<String> {
+    let conda = conda_base(config);
+    let working_directory = PathBuf::from(paths::expand(variant.working_directory(&config.commands)));
+    let mut active_env = variant.conda_env(&config.commands).map(str::to_string);
+    let mut missing = Vec::new();
+
+    for words in simple_commands(command) {
+        let Some(mut program) = program(&words) else { continue };
+        let mut env = active_env.clone();
+
+        if program == "conda" {
+            let args: Vec<&str> = words.iter().map(String::as_str).skip_while(|w| *w != "conda").skip(1).collect();
+            let env_flag = args.iter().position(|a| *a == "-n" || *a == "--name").and_then(|i| args.get(i + 1));
+            match args.first() {
+                Some(&"activate") => {
+                    active_env = Some(args.get(1).unwrap_or(&"base").to_string());
+                    continue;
+                }
+                Some(&"run") => {
+                    // `conda run [options] PROGRAM ...`: the program is the first
+                    // argument that is neither an option nor an option's value.
+                    let mut rest = args[1..].iter();
+                    let mut found = None;
+                    while let Some(arg) = rest.next() {
+                        if matches!(*arg, "-n" | "--name" | "-p" | "--prefix" | "--cwd") {
+                            rest.next();
+                        } else if !arg.starts_with('-') {
+                            found = Some(*arg);
+                            break;
+                        }
+                    }
+                    let Some(found) = found else { continue };
+                    program = found;
+                    env = env_flag.map(|e| e.to_string()).or(env);
+                }
+                // Sourcing conda.sh makes `conda` a shell function.
+                _ if conda.as_ref().is_some_and(|c| c.join("etc/profile.d/conda.sh").is_file()) => continue,
+                _ => {}
+            }
+        }
+
+        if SHELL_BUILTINS.contains(&program) {
+            continue;
+        }
+        let program = paths::expand(program);
+        let found = if program.contains('/') {
+            let path = Path::new(&program);
+            path.is_file() || (path.is_relative() && working_directory.join(path).is_file())
+        } else {
+            let env_bin = env.as_deref().zip(conda.as_deref()).map(|(env, base)| conda_env_bin(base, env));
+            env_bin.is_some_and(|bin| bin.join(&program).is_file()) || in_path(&program)
+        };
+        if !found {
+            missing.push(match env {
+                Some(env) => format!("'{}' (not found in $PATH or conda env '{}')", program, env),
+                None => format!("'{}' (not found in $PATH)", program),
+            });
+        }
+    }
+    missing
+}
+
+/// Finds the value of `--flag=value` or `--flag value` in `command` and returns
+/// the byte range of the value. Quoted values include their quotes.
+fn flag_span(command: &str, flag: &str) -> Option<(usize, usize)> {
+    let mut search_from = 0;
+    while let Some(pos) = command[search_from..].find(flag) {
+        let start = search_from + pos;
+        let after = start + flag.len();
+        search_from = after;
+        let preceded_ok = command[..start].chars().next_back().is_none_or(|c| c.is_whitespace());
+        let next = command[after..].chars().next();
+        let value_start = match next {
+            Some('=') => after + 1,
+            Some(c) if c.is_whitespace() && c != '\n' => after + command[after..].len() - command[after..].trim_start().len(),
+            _ => continue,
+        };
+        if !preceded_ok {
+            continue;
+        }
+        let rest = &command[value_start..];
+        let len = match rest.chars().next() {
+            Some(q @ ('\'' | '"')) => rest[1..].find(q).map_or(rest.len(), |end| end + 2),
+            _ => rest.find(char::is_whitespace).unwrap_or(rest.len()),
+        };
+        return Some((value_start, value_start + len));
+    }
+    None
+}
+
+/// Returns the (unquoted) value of `--flag` in `command`, if present.
+pub fn flag_value(command: &str, flag: &str) -> Option<String> {
+    let (start, end) = flag_span(command, flag)?;
+    let value = &command[start..end];
+    let unquoted = value
+        .strip_prefix(['\'', '"'])
+        .and_then(|v| v.strip_suffix(['\'', '"']))
+        .unwrap_or(value);
+    Some(unquoted.to_string())
+}
+
+/// Quotes `value` for the shell if it contains anything but safe characters.
+pub fn shell_quote(value: &str) -> String {
+    let safe = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,+@%".contains(c));
+    if safe {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Replaces the value of `--flag` in `command` with `value`. Returns `None` if
+/// the command does not contain the flag.
+pub fn set_flag(command: &str, flag: &str, value: &str) -> Option<String> {
+    let (start, end) = flag_span(command, flag)?;
+    Some(format!("{}{}{}", &command[..start], shell_quote(value), &command[end..]))
+}
+
+/// Inserts `--flag=value` right after the value of `--anchor`, so it ends up in
+/// the same simple command. Returns `None` if the command does not contain the anchor.
+pub fn insert_flag_after(command: &str, anchor: &str, flag: &str, value: &str) -> Option<String> {
+    let (_, end) = flag_span(command, anchor)?;
+    Some(format!("{} {}={}{}", &command[..end], flag, shell_quote(value), &command[end..]))
+}
+
+/// Sets `--flag` to `value` in `command`: replaces the existing value if there
+/// is one, and otherwise adds the flag after the dataset repo id (so it lands
+/// in the LeRobot command) or, failing that, at the end of the command.
+pub fn set_or_add_flag(command: &str, flag: &str, value: &str) -> String {
+    set_flag(command, flag, value)
+        .or_else(|| insert_flag_after(command, crate::dataset::REPO_ID_FLAG, flag, value))
+        .unwrap_or_else(|| {
+            // Drop a trailing line continuation so the flag is not escaped by it.
+            let end = command.trim_end().trim_end_matches('\\').trim_end();
+            format!("{} {}={}", end, flag, shell_quote(value))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml: &str) -> Config {
+        let commands = "[commands]\nteleoperation = \"teleop\"\nreplay = \"replay\"\nrecord = \"record\"\n";
+        toml::from_str(&format!("{}{}", commands, toml)).unwrap()
+    }
+
+    #[test]
+    fn conda_path_is_quoted() {
+        let config = config("[app]\nconda_path = \"/opt/my conda\"\n");
+        let init = conda_init(&config, Some("rec"));
+        assert_eq!(init, "source '/opt/my conda/etc/profile.d/conda.sh' && conda activate rec && ");
+    }
+
+    #[test]
+    fn simple_commands_are_split_at_operators() {
+        let commands = simple_commands("cd /x && python a.py --x=1 | tee log; echo done || (false)\nls &");
+        let expected: Vec<Vec<&str>> = vec![
+            vec!["cd", "/x"],
+            vec!["python", "a.py", "--x=1"],
+            vec!["tee", "log"],
+            vec!["echo", "done"],
+            vec!["false"],
+            vec!["ls"],
+        ];
+        assert_eq!(commands, expected);
+        assert!(simple_commands("  ;; && ").is_empty());
+    }
+
+    #[test]
+    fn simple_commands_unquote_words() {
+        let commands = simple_commands(r#"echo 'a && b' "c \"d\" e" f\ g '' x\
+            y"#);
+        assert_eq!(commands, vec![vec!["echo", "a && b", r#"c "d" e"#, "f g", "", "x", "y"]]);
+    }
+
+    #[test]
+    fn missing_programs_are_looked_up_in_path_working_directory_and_conda() {
+        let dir = std::env::temp_dir().join(format!("teleop-missing-programs-{}", std::process::id()));
+        let bin = dir.join("conda/envs/lerobot/bin");
+        fs::create_dir_all(&bin).unwrap();
+        fs::write(bin.join("lerobot-record"), "").unwrap();
+        fs::write(dir.join("run.sh"), "").unwrap();
+        let config = config(&format!("[app]\nconda_path = \"{}\"\n", dir.join("conda").display()));
+        let variant = Variant { working_directory: Some(dir.display().to_string()), ..Default::default() };
+
+        let command = "cd /tmp && ./run.sh --x=1 | ls; conda activate lerobot && lerobot-record --fps=30";
+        assert!(missing_programs(&config, &variant, command).is_empty());
+        let command = "./missing.sh; teleop-no-such-program; conda run -n lerobot lerobot-train";
+        assert_eq!(
+            missing_programs(&config, &variant, command),
+            [
+                "'./missing.sh' (not found in $PATH)",
+                "'teleop-no-such-program' (not found in $PATH)",
+                "'lerobot-train' (not found in $PATH or conda env 'lerobot')",
+            ]
+        );
+        let variant = Variant { conda_env: Some("lerobot".into()), ..variant };
+        assert!(missing_programs(&config, &variant, "lerobot-record").is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn flag_values_are_found_with_or_without_equals() {
+        let command =
+            "lerobot-record --robot.port=/dev/ttyACM0 --dataset.repo_id me/data --dataset.single_task='pick it'";
+        assert_eq!(flag_value(command, "--robot.port").as_deref(), Some("/dev/ttyACM0"));
+        assert_eq!(flag_value(command, "--dataset.repo_id").as_deref(), Some("me/data"));
+        assert_eq!(flag_value(command, "--dataset.single_task").as_deref(), Some("pick it"));
+        assert_eq!(flag_value("a --x=\"1 2\" b", "--x").as_deref(), Some("1 2"));
+        assert_eq!(flag_value("a --x=", "--x").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn flag_span_skips_other_flags_sharing_a_prefix() {
+        let command = "run --fps_max=60 x--fps=1 --fps=30";
+        assert_eq!(flag_value(command, "--fps").as_deref(), Some("30"));
+        assert_eq!(flag_span(command, "--fps"), Some((32, 34)));
+        assert_eq!(flag_value("run --fps", "--fps"), None);
+        assert_eq!(flag_value("run --fps\n30", "--fps"), None);
+    }
+
+    #[test]
+    fn shell_quote_quotes_only_when_needed() {
+        assert_eq!(shell_quote("me/data-1_v2.0"), "me/data-1_v2.0");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("pick it up"), "'pick it up'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote("$HOME"), "'$HOME'");
+    }
+
+    #[test]
+    fn quoted_values_survive_the_shell() {
+        for value in ["it's a \"test\"", "$(rm -rf /)", "a\nb", "*"] {
+            let script = format!("printf %s {}", shell_quote(value));
+            let output = Command::new("bash").args(["-c", &script]).output().unwrap();
+            assert_eq!(String::from_utf8_lossy(&output.stdout), value);
+        }
+    }
+
+    #[test]
+    fn flags_are_set_in_place() {
+        assert_eq!(set_flag("a --x=1 b", "--x", "two words").as_deref(), Some("a --x='two words' b"));
+        assert_eq!(set_flag("a --x '1 2' b", "--x", "3").as_deref(), Some("a --x 3 b"));
+        assert_eq!(set_flag("a", "--x", "3"), None);
+    }
+
+    #[test]
+    fn flags_are_added_to_the_lerobot_command() {
+        assert_eq!(set_or_add_flag("a \\\n  --y=1 \\\n", "--x", "3"), "a \\\n  --y=1 --x=3");
+        assert_eq!(
+            set_or_add_flag("rec --dataset.repo_id=me/d && echo", "--x", "3"),
+            "rec --dataset.repo_id=me/d --x=3 && echo"
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use tracing::{error, info};
+use std::process::Child;
+
+use crate::{process, sandbox};
+
+/// A background process started next to the main one, e.g. TensorBoard for a
+/// training run. It runs without a terminal, is shown in the GUI while it
+/// runs and is stopped when the app exits.
+pub struct Companion {
+    /// What the process is, e.g. "TensorBoard".
+    pub label: String,
+    /// Where the process can be reached, shown as a link.
+    pub url: Option<String>,
+    child: Child,
+}
+
+impl Companion {
+    /// Starts `shell_command` with `bash -c`.
+    pub fn spawn(label: &str, shell_command: &str, url: Option<String>) -> Result<Self, String> {
+        let child = sandbox::bash(shell_command)
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", label, e))?;
+        info!("Started {} with PID {}", label, child.id());
+        Ok(Self { label: label.to_string(), url, child })
+    }
+
+    /// Whether the process is still running. An unexpected exit is logged.
+    pub fn running(&mut self) -> bool {
+        match self.child.try_wait() {
+            Ok(None) => true,
+            Ok(Some(status)) => {
+                if !status.success() {
+                    error!("{} exited with {}", self.label, status);
+                }
+                false
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Stops the process and everything it started.
+    pub fn stop(&mut self) {
+        // Killing bash alone would leave the program it runs behind.
+        let _ = process::signal_children(self.child.id(), "TERM");
+        let _ = sandbox::kill(&mut self.child);
+        let _ = self.child.wait();
+    }
+}
+
+impl Drop for Companion {
+    fn drop(&mut self) {
+        if self.running() {
+            self.stop();
+        }
+    }
+}
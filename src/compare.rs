@@ -0,0 +1,129 @@
+use eframe::egui;
+use std::collections::BTreeSet;
+
+use crate::checkpoints::Checkpoint;
+use crate::config::Config;
+use crate::history::RunRecord;
+use crate::ProcessType;
+
+/// State of the A/B comparison form: one process type launched twice with a
+/// single value changed, e.g. the policy checkpoint.
+pub struct ComparePanel {
+    process_type: ProcessType,
+    /// The placeholder (or `--flag`) that differs between the two runs.
+    key: String,
+    a: String,
+    b: String,
+}
+
+impl Default for ComparePanel {
+    fn default() -> Self {
+        Self {
+            process_type: ProcessType::Replay,
+            key: "checkpoint".to_string(),
+            a: String::new(),
+            b: String::new(),
+        }
+    }
+}
+
+/// A comparison to run: the process type and the `(key, value)` of run A and B.
+pub type Comparison = (ProcessType, [(String, String); 2]);
+
+impl ComparePanel {
+    /// Shows the form. Returns the comparison to run when its button is clicked.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        config: &Config,
+        checkpoints: &[Checkpoint],
+        enabled: bool,
+    ) -> Option<Comparison> {
+        let mut start = None;
+        egui::Grid::new("compare_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Process:");
+            egui::ComboBox::from_id_source("compare_type")
+                .selected_text(format!("{:?}", self.process_type))
+                .show_ui(ui, |ui| {
+                    for process_type in [ProcessType::Replay, ProcessType::Evaluate] {
+                        if config.shows(process_type) {
+                            ui.selectable_value(&mut self.process_type, process_type, format!("{:?}", process_type));
+                        }
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Vary:");
+            ui.add(egui::TextEdit::singleline(&mut self.key).hint_text("placeholder or --flag"));
+            ui.end_row();
+
+            for (label, value) in [("A:", &mut self.a), ("B:", &mut self.b)] {
+                ui.label(label);
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(value);
+                    if self.key == "checkpoint" && !checkpoints.is_empty() {
+                        egui::ComboBox::from_id_source(("compare_checkpoint", label))
+                            .selected_text("Pick")
+                            .show_ui(ui, |ui| {
+                                for checkpoint in checkpoints {
+                                    if ui.selectable_label(*value == checkpoint.value, &checkpoint.label).clicked() {
+                                        *value = checkpoint.value.clone();
+                                    }
+                                }
+                            });
+                    }
+                });
+                ui.end_row();
+            }
+        });
+        let ready = enabled && !self.key.trim().is_empty() && !self.a.is_empty() && !self.b.is_empty();
+        if ui.add_enabled(ready, egui::Button::new("Run A, then B")).clicked() {
+            let key = self.key.trim().to_string();
+            start = Some((self.process_type, [(key.clone(), self.a.clone()), (key, self.b.clone())]));
+        }
+        start
+    }
+}
+
+/// The metrics of runs A and B side by side, with the difference B - A.
+pub fn diff_ui(ui: &mut egui::Ui, a: &RunRecord, b: &RunRecord) {
+    let metrics = |run: &RunRecord| {
+        let mut metrics = run.metrics.clone();
+        if let Some(result) = &run.evaluation {
+            metrics.insert("success rate (%)".to_string(), result.success_rate());
+            metrics.insert("episodes".to_string(), result.episodes() as f64);
+        }
+        metrics
+    };
+    let (a, b) = (metrics(a), metrics(b));
+    if a.is_empty() && b.is_empty() {
+        ui.label("No metrics were found in the output of either run.");
+        return;
+    }
+    let names: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    let value = |v: Option<&f64>| v.map(|v| format!("{:.4}", v)).unwrap_or_else(|| "-".to_string());
+    egui::Grid::new("compare_diff_grid").num_columns(4).striped(true).show(ui, |ui| {
+        for header in ["Metric", "A", "B", "B - A"] {
+            ui.strong(header);
+        }
+        ui.end_row();
+        for name in names {
+            let (va, vb) = (a.get(name), b.get(name));
+            ui.label(name.as_str());
+            ui.label(value(va));
+            ui.label(value(vb));
+            match (va, vb) {
+                (Some(va), Some(vb)) if va != vb => {
+                    ui.colored_label(egui::Color32::YELLOW, format!("{:+.4}", vb - va));
+                }
+                (Some(_), Some(_)) => {
+                    ui.weak("same");
+                }
+                _ => {
+                    ui.label("");
+                }
+            }
+            ui.end_row();
+        }
+    });
+}
@@ -0,0 +1,1214 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::audit::AuditLog;
+use crate::checkpoints::CheckpointsConfig;
+use crate::clock::ClockCheckConfig;
+use crate::cores::CoreDumpsConfig;
+use crate::dataset::{self, NamingConfig};
+use crate::discovery::HostsConfig;
+use crate::disk::DiskCheckConfig;
+use crate::evaluation::EvaluationConfig;
+use crate::exclusivity::{self, ExclusivityConfig};
+use crate::faults::FaultsConfig;
+use crate::frames::FramesConfig;
+use crate::git::GitConfig;
+use crate::hardware::HardwareCheckConfig;
+use crate::hub::HubConfig;
+use crate::params::Param;
+use crate::plugins::{self, PluginsConfig};
+use crate::mqtt::MqttConfig;
+use crate::realtime::RealtimeConfig;
+use crate::ros::RosConfig;
+use crate::scripts::{self, ScriptConfig};
+use crate::session::SessionConfig;
+use crate::stall::StallCheckConfig;
+use crate::sweep::SweepConfig;
+use crate::telemetry::LoggingConfig;
+use crate::updates::UpdatesConfig;
+use crate::voice::VoiceConfig;
+use crate::web::WebConfig;
+use crate::{paths, placeholders, ProcessType};
+
+/// How serious a config problem is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    /// The config cannot be used (or a command would certainly fail).
+    Error,
+    /// Probably a mistake, but the app can carry on.
+    Warning,
+}
+
+/// A single problem found while loading or validating the config.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Dotted path of the offending key, e.g. `commands.record`. Empty for
+    /// problems with a file as a whole.
+    pub key: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { severity: Severity::Error, key: key.into(), message: message.into() }
+    }
+
+    fn warning(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, key: key.into(), message: message.into() }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        if self.key.is_empty() {
+            write!(f, "{}: {}", level, self.message)
+        } else {
+            write!(f, "{}: {}: {}", level, self.key, self.message)
+        }
+    }
+}
+
+/// Whether any of `diagnostics` is an error.
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == Severity::Error)
+}
+
+/// Struct to hold the command strings from config.toml.
+#[derive(Deserialize, Clone)]
+pub struct Commands {
+    #[serde(default)]
+    pub working_directory: String,
+    /// Conda environment activated before each command (requires `app.conda_path`).
+    #[serde(default)]
+    pub conda_env: Option<String>,
+    pub teleoperation: CommandVariants,
+    pub record: CommandVariants,
+    pub replay: CommandVariants,
+    /// Uploads a dataset, launched from the recent datasets list.
+    #[serde(default = "default_push_command")]
+    pub push: CommandVariants,
+    /// Evaluates a policy. Optional: the Evaluate button only shows up if set.
+    #[serde(default)]
+    pub evaluate: Option<CommandVariants>,
+    /// Trains a policy. Optional: the Train button only shows up if set.
+    #[serde(default)]
+    pub train: Option<CommandVariants>,
+}
+
+fn default_push_command() -> CommandVariants {
+    CommandVariants(vec![Variant {
+        command: "huggingface-cli upload {repo_id} {dataset_path} --repo-type=dataset".to_string(),
+        ..Default::default()
+    }])
+}
+
+/// One named way of running a process type, e.g. recording "with cameras".
+/// Unknown keys are errors: a misspelled `working_directory` would otherwise
+/// run the command somewhere else without a word.
+#[derive(Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Variant {
+    /// Shown in the variant dropdown. Empty for a plain command string.
+    #[serde(default)]
+    pub name: String,
+    /// The command for this platform; empty if it lists commands for other
+    /// platforms only.
+    #[serde(deserialize_with = "platform_command")]
+    pub command: String,
+    /// Overrides `commands.working_directory` for this command.
+    #[serde(default)]
+    pub working_directory: Option<String>,
+    /// Overrides `commands.conda_env` for this command; `""` disables it.
+    #[serde(default)]
+    pub conda_env: Option<String>,
+    /// Values filled in from a form in the GUI, each used as a `{name}` placeholder.
+    #[serde(default)]
+    pub params: Vec<Param>,
+    /// A short command run by "Test connection" to check that the hardware
+    /// answers; without one, the serial ports of the command are opened.
+    #[serde(default)]
+    pub probe: Option<String>,
+    /// Launches the command with elevated scheduling if set.
+    #[serde(default)]
+    pub realtime: Option<RealtimeConfig>,
+    /// A regex matching the line the command prints once it is up and
+    /// running, which the `start` of a workflow script waits for.
+    #[serde(default)]
+    pub ready_pattern: Option<String>,
+}
+
+/// The commands of a variant for each platform, e.g. `command.linux = "..."`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlatformCommands {
+    #[serde(default)]
+    linux: Option<String>,
+    #[serde(default)]
+    macos: Option<String>,
+    #[serde(default)]
+    windows: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged, expecting = "expected a command string or a table of commands for `linux`, `macos` and `windows`")]
+enum PlatformCommand {
+    All(String),
+    PerPlatform(PlatformCommands),
+}
+
+/// Picks the command for the platform the app runs on, so one config can be
+/// shared by teammates on different operating systems.
+fn platform_command<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let commands = match PlatformCommand::deserialize(deserializer)? {
+        PlatformCommand::All(command) => return Ok(command),
+        PlatformCommand::PerPlatform(commands) => commands,
+    };
+    let command = match std::env::consts::OS {
+        "linux" => commands.linux,
+        "macos" => commands.macos,
+        "windows" => commands.windows,
+        _ => None,
+    };
+    Ok(command.unwrap_or_default())
+}
+
+impl Variant {
+    /// The directory this command runs in, given the global `commands` settings.
+    pub fn working_directory<'a>(&'a self, commands: &'a Commands) -> &'a str {
+        self.working_directory.as_deref().unwrap_or(&commands.working_directory)
+    }
+
+    /// The conda environment activated for this command, if any.
+    pub fn conda_env<'a>(&'a self, commands: &'a Commands) -> Option<&'a str> {
+        self.conda_env
+            .as_deref()
+            .or(commands.conda_env.as_deref())
+            .filter(|e| !e.is_empty())
+    }
+}
+
+/// The variants configured for one process type; never empty. The first one
+/// is the default.
+#[derive(Clone)]
+pub struct CommandVariants(pub Vec<Variant>);
+
+/// A command may be written in the config as a plain string, a table with the
+/// command and its own working directory and conda environment, or a list of
+/// such tables with a `name` each, to choose from in the GUI.
+///
+/// Read by hand rather than as an untagged enum, which would report a mistake
+/// in a table only as matching none of the forms.
+impl<'de> Deserialize<'de> for CommandVariants {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let variant = |value: serde_json::Value, at: &str| -> Result<Variant, D::Error> {
+            serde_path_to_error::deserialize(value).map_err(|e| {
+                let path = format!("{}{}", at, e.path()).trim_end_matches('.').to_string();
+                let message = e.into_inner().to_string();
+                D::Error::custom(if path.is_empty() { message } else { format!("{}: {}", path, message) })
+            })
+        };
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(command) => Ok(Self(vec![Variant { command, ..Default::default() }])),
+            serde_json::Value::Array(values) if values.is_empty() => Err(D::Error::custom("no variants listed")),
+            serde_json::Value::Array(values) => values
+                .into_iter()
+                .enumerate()
+                .map(|(index, value)| variant(value, &format!("[{}].", index)))
+                .collect::<Result<_, _>>()
+                .map(Self),
+            table @ serde_json::Value::Object(_) => Ok(Self(vec![variant(table, "")?])),
+            _ => Err(D::Error::custom(
+                "expected a command string, a { command, ... } table or a list of { name, command, ... } variants",
+            )),
+        }
+    }
+}
+
+impl Commands {
+    /// The variants configured for `process_type`.
+    /// Empty for an optional process type that is not configured.
+    pub fn variants(&self, process_type: ProcessType) -> &[Variant] {
+        let variants = match process_type {
+            ProcessType::Teleoperation => &self.teleoperation,
+            ProcessType::Record => &self.record,
+            ProcessType::Replay => &self.replay,
+            ProcessType::Push => &self.push,
+            ProcessType::Evaluate => match &self.evaluate {
+                Some(variants) => variants,
+                None => return &[],
+            },
+            ProcessType::Train => match &self.train {
+                Some(variants) => variants,
+                None => return &[],
+            },
+        };
+        &variants.0
+    }
+
+    /// Whether `process_type` has a command and can be launched.
+    pub fn is_configured(&self, process_type: ProcessType) -> bool {
+        !self.variants(process_type).is_empty()
+    }
+
+    /// Variant `index` of `process_type`, falling back to the first (default)
+    /// variant if there is no such index, or to an empty command if the
+    /// process type is not configured.
+    pub fn variant(&self, process_type: ProcessType, index: usize) -> &Variant {
+        static UNCONFIGURED: Variant = Variant {
+            name: String::new(),
+            command: String::new(),
+            working_directory: None,
+            conda_env: None,
+            params: Vec::new(),
+            probe: None,
+            realtime: None,
+            ready_pattern: None,
+        };
+        let variants = self.variants(process_type);
+        variants.get(index).or(variants.first()).unwrap_or(&UNCONFIGURED)
+    }
+
+    /// Every variant of every process type, with its index.
+    pub fn all_variants(&self) -> impl Iterator<Item = (ProcessType, usize, &Variant)> {
+        ProcessType::ALL
+            .into_iter()
+            .flat_map(move |t| self.variants(t).iter().enumerate().map(move |(i, v)| (t, i, v)))
+    }
+}
+
+/// Struct for application-level settings from config.toml.
+#[derive(Deserialize, Clone)]
+pub struct AppConfig {
+    /// The terminal emulator to use.
+    /// We use an Option so we can default if it's missing from the TOML file.
+    #[serde(default)]
+    pub terminal: Option<String>,
+    /// Path to the conda installation directory.
+    #[serde(default)]
+    pub conda_path: Option<String>,
+    /// Names offered in the operator selector. Free entry is always possible.
+    #[serde(default)]
+    pub operators: Vec<String>,
+    /// Port TensorBoard is started on for training runs.
+    #[serde(default = "default_tensorboard_port")]
+    pub tensorboard_port: u16,
+}
+
+fn default_tensorboard_port() -> u16 {
+    6006
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            terminal: None,
+            conda_path: None,
+            operators: Vec::new(),
+            tensorboard_port: default_tensorboard_port(),
+        }
+    }
+}
+
+/// A group of launch buttons, as listed in `[[buttons]]`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ButtonGroup {
+    /// Shown above the buttons, if set.
+    #[serde(default)]
+    pub label: Option<String>,
+    /// The process types of the buttons in order, e.g. `["teleoperation", "record"]`.
+    pub processes: Vec<String>,
+}
+
+/// Struct to represent the overall configuration.
+#[derive(Deserialize, Clone)]
+pub struct Config {
+    /// We use `serde(default)` so the app doesn't crash if the `[app]` table is missing.
+    #[serde(default)]
+    pub app: AppConfig,
+    pub commands: Commands,
+    /// The launch buttons this station shows, in groups; every configured
+    /// process type if empty.
+    #[serde(default)]
+    pub buttons: Vec<ButtonGroup>,
+    /// Environment variables to set for launched commands, mapped to the name
+    /// of the secret in the OS keyring holding their value.
+    #[serde(default)]
+    pub secrets: BTreeMap<String, String>,
+    /// How a structured recording session runs.
+    #[serde(default)]
+    pub session: SessionConfig,
+    /// Names a new dataset for every recording if set.
+    #[serde(default)]
+    pub dataset_naming: Option<NamingConfig>,
+    /// How the outcome of Evaluate runs is read from their output.
+    #[serde(default)]
+    pub evaluation: EvaluationConfig,
+    /// How the frame rate and dropped frames are read from Record output.
+    #[serde(default)]
+    pub frames: FramesConfig,
+    /// Output of Record runs upon which they are stopped.
+    #[serde(default)]
+    pub faults: FaultsConfig,
+    /// Where trained policies are found for `{checkpoint}`.
+    #[serde(default)]
+    pub checkpoints: CheckpointsConfig,
+    /// Batches of runs over combinations of values, e.g. checkpoints.
+    #[serde(default)]
+    pub sweeps: Vec<SweepConfig>,
+    /// Workflow scripts launching runs and asking the operator.
+    #[serde(default)]
+    pub scripts: Vec<ScriptConfig>,
+    /// Publishes status events to an MQTT broker if set.
+    #[serde(default)]
+    pub mqtt: Option<MqttConfig>,
+    /// Publishes status events to and takes emergency stops from ROS 2 if set.
+    #[serde(default)]
+    pub ros: Option<RosConfig>,
+    /// Serves a read-only status page if set.
+    #[serde(default)]
+    pub web: Option<WebConfig>,
+    /// Starts and stops recordings on spoken hotwords if set.
+    #[serde(default)]
+    pub voice: Option<VoiceConfig>,
+    /// Hugging Face namespaces offered for composing `{repo_id}` if set.
+    #[serde(default)]
+    pub hub: Option<HubConfig>,
+    /// Robot PCs offered for `{host}`, found over mDNS.
+    #[serde(default)]
+    pub hosts: Option<HostsConfig>,
+    /// Compares the clocks of the machines involved before recording if set.
+    #[serde(default)]
+    pub clock_check: Option<ClockCheckConfig>,
+    /// Checks the checkout of the working directory before recording if set.
+    #[serde(default)]
+    pub git: Option<GitConfig>,
+    /// Benchmarks the dataset disk when a recording session starts if set.
+    #[serde(default)]
+    pub disk_check: Option<DiskCheckConfig>,
+    /// Processes that may not run at the same time.
+    #[serde(default)]
+    pub exclusivity: ExclusivityConfig,
+    /// What the hardware check measures the Record setup against.
+    #[serde(default)]
+    pub hardware_check: HardwareCheckConfig,
+    /// Warns when Record stops writing to its dataset if set.
+    #[serde(default)]
+    pub stall_check: Option<StallCheckConfig>,
+    /// Keeps the core dumps of commands that crash if set.
+    #[serde(default)]
+    pub core_dumps: Option<CoreDumpsConfig>,
+    /// The app's own log level and file.
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    /// Looks for newer releases on startup if set.
+    #[serde(default)]
+    pub updates: Option<UpdatesConfig>,
+    /// Robot-specific extensions contributing buttons, status widgets and
+    /// output parsers.
+    #[serde(default)]
+    pub plugins: PluginsConfig,
+}
+
+impl Config {
+    /// The launch buttons to show: the label of each group and its process
+    /// types, in order.
+    pub fn button_groups(&self) -> Vec<(Option<&str>, Vec<ProcessType>)> {
+        if self.buttons.is_empty() {
+            // Push needs a dataset, so it is launched from the recent datasets list.
+            let main = ProcessType::ALL.into_iter().filter(|t| *t != ProcessType::Push);
+            return vec![(None, main.filter(|t| self.commands.is_configured(*t)).collect())];
+        }
+        self.buttons
+            .iter()
+            .map(|group| {
+                let process_types = group.processes.iter().filter_map(|p| ProcessType::from_key(p));
+                (group.label.as_deref(), process_types.filter(|t| self.commands.is_configured(*t)).collect())
+            })
+            .collect()
+    }
+
+    /// Whether this station launches `process_type`: it is configured and,
+    /// if `[[buttons]]` is set, listed there.
+    pub fn shows(&self, process_type: ProcessType) -> bool {
+        let listed = || self.buttons.iter().flat_map(|g| &g.processes).any(|p| p == process_type.key());
+        self.commands.is_configured(process_type) && (self.buttons.is_empty() || listed())
+    }
+}
+
+/// Loads configuration from the specified path. Returns a `Result` indicating
+/// success or failure, with an error message if loading fails.
+///
+/// The format is chosen by extension: `.yaml`/`.yml` and `.json` files are
+/// accepted besides TOML, which is assumed for any other extension.
+///
+/// Files listed in a top-level `include = [...]` array are loaded first, in
+/// order, and each later file overrides the tables and keys of the earlier
+/// ones; the including file itself is applied last. Include paths are relative
+/// to the file that includes them. Changes to any of the files since the last
+/// load are recorded in the audit log.
+///
+/// On success the config is returned along with any non-fatal problems found
+/// by [`validate`]; on failure all we have is the list of problems.
+pub fn load(
+    config_path: &Path,
+    user: &str,
+    audit: &AuditLog,
+) -> Result<(Config, Vec<Diagnostic>), Vec<Diagnostic>> {
+    let mut visiting = Vec::new();
+    let merged = load_layered(config_path, Some((user, audit)), &mut visiting)
+        .map_err(|e| vec![Diagnostic::error("", e)])?;
+    from_value(merged)
+}
+
+/// Like [`load`], but records nothing in the audit log, for read-only checks
+/// such as `--check-config`.
+pub fn load_unaudited(config_path: &Path) -> Result<(Config, Vec<Diagnostic>), Vec<Diagnostic>> {
+    let merged = merged(config_path).map_err(|e| vec![Diagnostic::error("", e)])?;
+    from_value(merged)
+}
+
+/// The config in `merged`, the contents of the config files, with the
+/// problems found in it.
+fn from_value(merged: serde_json::Value) -> Result<(Config, Vec<Diagnostic>), Vec<Diagnostic>> {
+    // Keys serde does not know about are silently ignored by default; collect
+    // them so typos like `[command]` or `conda_pth` are reported.
+    let mut diagnostics = Vec::new();
+    let config: Config = {
+        let mut on_ignored = |path: serde_ignored::Path| {
+            diagnostics.push(Diagnostic::warning(path.to_string(), "unknown key (ignored)"));
+        };
+        let deserializer = serde_ignored::Deserializer::new(merged, &mut on_ignored);
+        serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let key = e.path().to_string();
+            let key = if key == "." { String::new() } else { key };
+            vec![Diagnostic::error(key, e.into_inner().to_string())]
+        })?
+    };
+    diagnostics.extend(validate(&config));
+    Ok((config, diagnostics))
+}
+
+/// Checks the parts of a parsed config that serde cannot: empty commands,
+/// paths that do not exist and placeholders nothing provides a value for.
+pub fn validate(config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for process_type in ProcessType::ALL {
+        let variants = config.commands.variants(process_type);
+        for (index, variant) in variants.iter().enumerate() {
+            if variants.len() > 1 && variants[..index].iter().any(|v| v.name == variant.name) {
+                diagnostics.push(Diagnostic::error(
+                    format!("commands.{}[{}]", process_type.key(), index),
+                    format!("duplicate variant name '{}'", variant.name),
+                ));
+            }
+        }
+    }
+
+    for (process_type, index, variant) in config.commands.all_variants() {
+        let key = match config.commands.variants(process_type).len() {
+            1 => format!("commands.{}", process_type.key()),
+            _ => format!("commands.{}[{}]", process_type.key(), index),
+        };
+        let key = key.as_str();
+        let command = &variant.command;
+        if command.trim().is_empty() {
+            diagnostics.push(Diagnostic::error(
+                key,
+                format!("command is empty, or has no `{}` entry for this platform", std::env::consts::OS),
+            ));
+        }
+        if let Some(realtime) = &variant.realtime {
+            if !realtime.range().contains(&realtime.priority()) {
+                diagnostics.push(Diagnostic::error(
+                    format!("{}.realtime.priority", key),
+                    format!("must be between {} and {}", realtime.range().start(), realtime.range().end()),
+                ));
+            }
+        }
+        if let Some(Err(e)) = variant.ready_pattern.as_deref().map(regex::Regex::new) {
+            diagnostics.push(Diagnostic::error(format!("{}.ready_pattern", key), format!("invalid regex: {}", e)));
+        }
+        for (i, param) in variant.params.iter().enumerate() {
+            let param_key = format!("{}.params[{}]", key, i);
+            if param.name.is_empty() || !param.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                diagnostics.push(Diagnostic::error(
+                    param_key,
+                    format!("invalid name '{}'; use letters, digits and underscores", param.name),
+                ));
+            } else if placeholders::BUILTIN.contains(&param.name.as_str()) {
+                diagnostics.push(Diagnostic::error(param_key, format!("'{}' is filled by the app", param.name)));
+            } else if variant.params[..i].iter().any(|p| p.name == param.name) {
+                diagnostics.push(Diagnostic::error(param_key, format!("duplicate parameter '{}'", param.name)));
+            } else if let Err(e) = param.check(&param.default_value()) {
+                diagnostics.push(Diagnostic::error(param_key, format!("invalid default: {}", e)));
+            } else if !placeholders::names(command).contains(&param.name) {
+                diagnostics.push(Diagnostic::warning(
+                    param_key,
+                    format!("{{{}}} is not used in the command", param.name),
+                ));
+            }
+        }
+        let available: Vec<&str> = placeholders::BUILTIN
+            .iter()
+            .copied()
+            .chain(variant.params.iter().map(|p| p.name.as_str()))
+            .collect();
+        for name in placeholders::names(command) {
+            if !available.contains(&name.as_str()) {
+                diagnostics.push(Diagnostic::error(
+                    key,
+                    format!(
+                        "unknown placeholder {{{}}}; available: {}",
+                        name,
+                        available.iter().map(|n| format!("{{{}}}", n)).collect::<Vec<_>>().join(", ")
+                    ),
+                ));
+            }
+        }
+        if process_type == ProcessType::Record && config.dataset_naming.is_some() {
+            if crate::command::flag_value(command, dataset::REPO_ID_FLAG).is_none() {
+                diagnostics.push(Diagnostic::warning(
+                    key,
+                    format!("has no {}, so [dataset_naming] cannot name its datasets", dataset::REPO_ID_FLAG),
+                ));
+            }
+            if crate::command::flag_value(command, dataset::ROOT_FLAG).is_some() {
+                diagnostics.push(Diagnostic::warning(
+                    key,
+                    format!("sets {}, so every named dataset is stored in the same folder", dataset::ROOT_FLAG),
+                ));
+            }
+        }
+        if let Some(working_directory) = variant.working_directory.as_deref().filter(|w| !w.is_empty()) {
+            if !Path::new(&paths::expand(working_directory)).is_dir() {
+                diagnostics.push(Diagnostic::error(
+                    format!("{}.working_directory", key),
+                    format!("directory '{}' does not exist", paths::expand(working_directory)),
+                ));
+            }
+        }
+    }
+
+    let patterns = [
+        ("evaluation.success", &config.evaluation.success, &[][..]),
+        ("evaluation.failure", &config.evaluation.failure, &[][..]),
+        ("evaluation.metrics", &config.evaluation.metrics, &["name", "value"][..]),
+        ("frames.fps", &config.frames.fps, &["fps"][..]),
+        ("frames.dropped", &config.frames.dropped, &[][..]),
+    ];
+    for (key, pattern, groups) in patterns {
+        match regex::Regex::new(pattern) {
+            Err(e) => diagnostics.push(Diagnostic::error(key, format!("invalid regex: {}", e))),
+            Ok(regex) => {
+                for group in groups.iter().filter(|g| !regex.capture_names().any(|n| n == Some(**g))) {
+                    diagnostics.push(Diagnostic::error(key, format!("missing the named group (?P<{}>...)", group)));
+                }
+            }
+        }
+    }
+
+    for (index, pattern) in config.faults.patterns.iter().enumerate() {
+        if let Err(e) = regex::Regex::new(pattern) {
+            diagnostics.push(Diagnostic::error(format!("faults.patterns[{}]", index), format!("invalid regex: {}", e)));
+        }
+    }
+    if !["INT", "TERM", "HUP", "QUIT", "USR1", "USR2"].contains(&config.faults.signal.as_str()) {
+        diagnostics.push(Diagnostic::error(
+            "faults.signal",
+            format!("unsupported signal '{}'; expected e.g. INT or TERM", config.faults.signal),
+        ));
+    }
+
+    for (index, group) in config.buttons.iter().enumerate() {
+        let key = format!("buttons[{}].processes", index);
+        if group.processes.is_empty() {
+            diagnostics.push(Diagnostic::warning(key.as_str(), "no process types listed"));
+        }
+        for (i, process) in group.processes.iter().enumerate() {
+            match ProcessType::from_key(process) {
+                None => diagnostics.push(Diagnostic::error(
+                    key.as_str(),
+                    format!(
+                        "unknown process '{}'; expected one of {}",
+                        process,
+                        ProcessType::ALL.map(ProcessType::key).join(", ")
+                    ),
+                )),
+                Some(process_type) if !config.commands.is_configured(process_type) => diagnostics.push(
+                    Diagnostic::warning(key.as_str(), format!("commands.{} is not configured; not shown", process)),
+                ),
+                Some(_) => {}
+            }
+            let mut earlier = config.buttons[..index].iter().flat_map(|g| &g.processes).chain(&group.processes[..i]);
+            if earlier.any(|p| p == process) {
+                diagnostics.push(Diagnostic::warning(key.as_str(), format!("'{}' is listed more than once", process)));
+            }
+        }
+    }
+
+    for (index, sweep) in config.sweeps.iter().enumerate() {
+        let key = format!("sweeps[{}]", index);
+        if config.sweeps[..index].iter().any(|s| s.name == sweep.name) {
+            diagnostics.push(Diagnostic::error(key.as_str(), format!("duplicate sweep name '{}'", sweep.name)));
+        }
+        match ProcessType::from_key(&sweep.process) {
+            None => diagnostics.push(Diagnostic::error(
+                format!("{}.process", key),
+                format!(
+                    "unknown process '{}'; expected one of {}",
+                    sweep.process,
+                    ProcessType::ALL.map(ProcessType::key).join(", ")
+                ),
+            )),
+            Some(process_type) if !config.commands.is_configured(process_type) => diagnostics.push(
+                Diagnostic::error(format!("{}.process", key), format!("commands.{} is not configured", sweep.process)),
+            ),
+            Some(_) => {}
+        }
+        for (name, values) in &sweep.values {
+            if values.is_empty() {
+                diagnostics.push(Diagnostic::error(format!("{}.values.{}", key, name), "no values listed"));
+            }
+        }
+    }
+
+    for (index, script) in config.scripts.iter().enumerate() {
+        let key = format!("scripts[{}]", index);
+        if config.scripts[..index].iter().any(|s| s.name == script.name) {
+            diagnostics.push(Diagnostic::error(key.as_str(), format!("duplicate script name '{}'", script.name)));
+        }
+        if let Err(e) = scripts::check(script) {
+            diagnostics.push(Diagnostic::error(format!("{}.path", key), e));
+        }
+    }
+
+    if let Some(hub) = &config.hub {
+        if hub.namespaces.is_empty() {
+            diagnostics.push(Diagnostic::error("hub.namespaces", "must list at least one user or organization"));
+        }
+        for namespace in &hub.namespaces {
+            if let Err(e) = dataset::check_repo_id(&format!("{}/dataset", namespace)) {
+                diagnostics.push(Diagnostic::error("hub.namespaces", format!("'{}': {}", namespace, e)));
+            }
+        }
+    }
+
+    if let Some(clock_check) = &config.clock_check {
+        if clock_check.max_skew_ms.is_nan() || clock_check.max_skew_ms <= 0.0 {
+            diagnostics.push(Diagnostic::error("clock_check.max_skew_ms", "must be positive"));
+        }
+    }
+
+    if let Some(disk_check) = &config.disk_check {
+        if disk_check.size_mb == 0 {
+            diagnostics.push(Diagnostic::error("disk_check.size_mb", "must be at least 1"));
+        }
+        if disk_check.seconds.is_nan() || disk_check.seconds <= 0.0 {
+            diagnostics.push(Diagnostic::error("disk_check.seconds", "must be positive"));
+        }
+    }
+
+    for (name, others) in &config.exclusivity.rules {
+        let key = format!("exclusivity.{}", name);
+        if !exclusivity::is_known(name) {
+            diagnostics.push(Diagnostic::error(&key, format!("'{}' is not a process type or companion", name)));
+        }
+        for other in others.iter().filter(|o| *o != exclusivity::ANY && !exclusivity::is_known(o)) {
+            diagnostics.push(Diagnostic::error(&key, format!("'{}' is not a process type or companion", other)));
+        }
+    }
+
+    let hardware_check = &config.hardware_check;
+    if hardware_check.seconds.is_nan() || hardware_check.seconds < 1.0 {
+        diagnostics.push(Diagnostic::error("hardware_check.seconds", "must be at least 1"));
+    }
+    for (key, share) in [
+        ("hardware_check.min_fps_share", hardware_check.min_fps_share),
+        ("hardware_check.max_usb_load", hardware_check.max_usb_load),
+    ] {
+        if share.is_nan() || share <= 0.0 || share > 1.0 {
+            diagnostics.push(Diagnostic::error(key, "must be above 0 and at most 1"));
+        }
+    }
+    if hardware_check.max_latency_ms.is_nan() || hardware_check.max_latency_ms <= 0.0 {
+        diagnostics.push(Diagnostic::error("hardware_check.max_latency_ms", "must be positive"));
+    }
+    if hardware_check.pings == 0 {
+        diagnostics.push(Diagnostic::error("hardware_check.pings", "must be at least 1"));
+    }
+
+    if let Some(stall_check) = &config.stall_check {
+        if stall_check.seconds.is_nan() || stall_check.seconds < 1.0 {
+            diagnostics.push(Diagnostic::error("stall_check.seconds", "must be at least 1"));
+        }
+    }
+
+    if let Some(voice) = &config.voice {
+        if voice.command.trim().is_empty() {
+            diagnostics.push(Diagnostic::error("voice.command", "is empty"));
+        }
+        if voice.start.iter().chain(&voice.stop).all(|p| p.trim().is_empty()) {
+            diagnostics.push(Diagnostic::error("voice", "no start or stop phrases"));
+        }
+    }
+
+    if let Some(web) = &config.web {
+        if web.address.rsplit_once(':').is_none_or(|(_, port)| port.parse::<u16>().is_err()) {
+            diagnostics.push(Diagnostic::error("web.address", "must be `<host>:<port>`, e.g. `0.0.0.0:8787`"));
+        }
+    }
+
+    if let Some(naming) = &config.dataset_naming {
+        let parts: Vec<&str> = naming.template.split('/').collect();
+        if parts.len() != 2 || parts.iter().any(|p| p.is_empty()) {
+            diagnostics.push(Diagnostic::error(
+                "dataset_naming.template",
+                format!("expected '<namespace>/<name>', got '{}'", naming.template),
+            ));
+        }
+        let params = config.commands.variants(ProcessType::Record).iter().flat_map(|v| &v.params);
+        let available: Vec<&str> = placeholders::BUILTIN
+            .iter()
+            .copied()
+            .chain(["user", "task", "date", "time", "seq"])
+            .chain(params.map(|p| p.name.as_str()))
+            .collect();
+        for name in placeholders::names(&naming.template) {
+            if !available.contains(&name.as_str()) {
+                let message = format!("unknown placeholder {{{}}}", name);
+                diagnostics.push(Diagnostic::error("dataset_naming.template", message));
+            }
+        }
+        if chrono::format::StrftimeItems::new(&naming.date_format).any(|i| i == chrono::format::Item::Error) {
+            diagnostics.push(Diagnostic::error(
+                "dataset_naming.date_format",
+                format!("invalid format '{}'", naming.date_format),
+            ));
+        }
+    }
+
+    if config.core_dumps.as_ref().is_some_and(|c| c.max_mb == Some(0)) {
+        diagnostics.push(Diagnostic::error("core_dumps.max_mb", "must be at least 1"));
+    }
+
+    if let Some(updates) = &config.updates {
+        if updates.repo.split('/').filter(|part| !part.is_empty()).count() != 2 {
+            diagnostics.push(Diagnostic::error("updates.repo", format!("expected 'owner/name', got '{}'", updates.repo)));
+        }
+    }
+
+    for (index, path) in config.plugins.paths.iter().enumerate() {
+        let key = format!("plugins.paths[{}]", index);
+        let manifest = match plugins::load_manifest(Path::new(&paths::expand(path))) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                diagnostics.push(Diagnostic::error(key, e));
+                continue;
+            }
+        };
+        for parser in &manifest.parsers {
+            for process in parser.process.iter().filter(|p| ProcessType::from_key(p).is_none()) {
+                diagnostics.push(Diagnostic::error(
+                    key.as_str(),
+                    format!("plugin '{}' parses output of unknown process '{}'", manifest.name, process),
+                ));
+            }
+        }
+    }
+
+    if let Some(level) = &config.logging.level {
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(level) {
+            diagnostics.push(Diagnostic::error("logging.level", format!("invalid level: {}", e)));
+        }
+    }
+
+    if config.session.episodes == 0 {
+        diagnostics.push(Diagnostic::error("session.episodes", "must be at least 1"));
+    }
+    for (task, &target) in &config.session.targets {
+        if target == 0 {
+            diagnostics.push(Diagnostic::error(format!("session.targets.{}", task), "must be at least 1"));
+        }
+    }
+    let targets: u32 = config.session.targets.values().sum();
+    if targets > config.session.episodes {
+        diagnostics.push(Diagnostic::warning(
+            "session.targets",
+            format!(
+                "add up to {} episodes, but the session ends after {} (`session.episodes`)",
+                targets, config.session.episodes
+            ),
+        ));
+    }
+
+    let working_directory = &config.commands.working_directory;
+    if !working_directory.is_empty() && !Path::new(&paths::expand(working_directory)).is_dir() {
+        diagnostics.push(Diagnostic::error(
+            "commands.working_directory",
+            format!("directory '{}' does not exist", paths::expand(working_directory)),
+        ));
+    }
+
+    if let Some(conda_path) = config.app.conda_path.as_deref().filter(|p| !p.is_empty()) {
+        let conda_path = PathBuf::from(paths::expand(conda_path));
+        if !conda_path.is_dir() {
+            diagnostics.push(Diagnostic::error(
+                "app.conda_path",
+                format!("directory '{}' does not exist", conda_path.display()),
+            ));
+        } else if !conda_path.join("etc/profile.d/conda.sh").is_file() {
+            diagnostics.push(Diagnostic::warning(
+                "app.conda_path",
+                format!(
+                    "'{}' does not contain etc/profile.d/conda.sh; is it a conda installation?",
+                    conda_path.display()
+                ),
+            ));
+        }
+    }
+
+    diagnostics
+}
+
+/// Renders a list of config problems, errors first.
+pub fn diagnostics_ui(ui: &mut eframe::egui::Ui, diagnostics: &[Diagnostic]) {
+    use eframe::egui::Color32;
+
+    for severity in [Severity::Error, Severity::Warning] {
+        for d in diagnostics.iter().filter(|d| d.severity == severity) {
+            let color = match severity {
+                Severity::Error => Color32::RED,
+                Severity::Warning => Color32::YELLOW,
+            };
+            ui.colored_label(color, d.to_string());
+        }
+    }
+}
+
+/// Replaces the command of `process_type` in the TOML file at `config_path`
+/// with `command`, keeping comments and formatting of the rest of the file
+/// intact, and returns the new file contents. `variant` is the index of the
+/// variant to change, or `None` if the process type has a single command; such
+/// a command is written to `config_path` itself, which takes precedence over
+/// any included file.
+pub fn save_command(
+    config_path: &Path,
+    process_type: ProcessType,
+    variant: Option<usize>,
+    command: &str,
+) -> Result<String, String> {
+    let is_toml = !matches!(
+        config_path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref(),
+        Some("yaml" | "yml" | "json")
+    );
+    if !is_toml {
+        return Err(format!("Only TOML configs can be edited from the GUI; edit '{}' by hand.", config_path.display()));
+    }
+    let contents = fs::read_to_string(config_path)
+        .map_err(|e| format!("Failed to read '{}': {}", config_path.display(), e))?;
+    let mut document: toml_edit::DocumentMut = contents
+        .parse()
+        .map_err(|e| format!("Failed to parse '{}': {}", config_path.display(), e))?;
+
+    let commands = document
+        .entry("commands")
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut()
+        .ok_or_else(|| format!("'commands' in '{}' is not a table", config_path.display()))?;
+    let key = process_type.key();
+    let not_found = || format!("commands.{} is not defined in '{}'; is it in an included file?", key, config_path.display());
+    match (commands.get_mut(key), variant) {
+        (None, None) => {
+            commands.insert(key, toml_edit::value(command));
+        }
+        (Some(toml_edit::Item::Value(old @ toml_edit::Value::String(_))), None) => replace_value(old, command),
+        (Some(toml_edit::Item::ArrayOfTables(variants)), index) => {
+            let table = variants.get_mut(index.unwrap_or(0)).ok_or_else(not_found)?;
+            set_command(table, command);
+        }
+        (Some(toml_edit::Item::Value(toml_edit::Value::Array(variants))), index) => {
+            let table = variants
+                .get_mut(index.unwrap_or(0))
+                .and_then(|v| v.as_inline_table_mut())
+                .ok_or_else(not_found)?;
+            set_command(table, command);
+        }
+        // A `[commands.x]` table with its own working directory etc.
+        (Some(item), None) if item.is_table_like() => {
+            set_command(item.as_table_like_mut().ok_or_else(not_found)?, command);
+        }
+        _ => return Err(not_found()),
+    }
+
+    let contents = document.to_string();
+    fs::write(config_path, &contents).map_err(|e| format!("Failed to write '{}': {}", config_path.display(), e))?;
+    Ok(contents)
+}
+
+/// Sets the `command` key of a command table, see [`replace_value`].
+fn set_command(table: &mut dyn toml_edit::TableLike, command: &str) {
+    match table.get_mut("command").and_then(|item| item.as_value_mut()) {
+        Some(old) => replace_value(old, command),
+        None => {
+            table.insert("command", toml_edit::value(command));
+        }
+    }
+}
+
+/// Replaces `old` with `command` but keeps its decor, so comments before the
+/// key and after the value, e.g. `record = "..." # 50 episodes`, survive.
+fn replace_value(old: &mut toml_edit::Value, command: &str) {
+    let decor = old.decor().clone();
+    *old = toml_edit::Value::from(command);
+    *old.decor_mut() = decor;
+}
+
+/// Parses a config file in the format indicated by its extension into a
+/// format-independent value.
+fn parse(path: &Path, contents: &str) -> Result<serde_json::Value, String> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    let result = match extension.as_deref() {
+        Some("yaml") | Some("yml") => serde_yaml::from_str(contents).map_err(|e| e.to_string()),
+        Some("json") => serde_json::from_str(contents).map_err(|e| e.to_string()),
+        _ => toml::from_str(contents).map_err(|e| e.to_string()),
+    };
+    result.map_err(|e| format!("Failed to parse '{}': {}", path.display(), e))
+}
+
+/// The config of `config_path` with its includes merged, as it is before being
+/// read into a [`Config`]. Unlike [`load`], changes are not audited.
+pub fn merged(config_path: &Path) -> Result<serde_json::Value, String> {
+    load_layered(config_path, None, &mut Vec::new())
+}
+
+/// The config file at `config_path` and every file it includes, directly or
+/// through another include, each listed once.
+pub fn files(config_path: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    collect_files(config_path, &mut files)?;
+    Ok(files)
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let canonical = fs::canonicalize(path)
+        .map_err(|e| format!("Failed to read config file '{}': {}", path.display(), e))?;
+    if files.contains(&canonical) {
+        return Ok(());
+    }
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config file '{}': {}", path.display(), e))?;
+    let value = parse(path, &contents)?;
+    files.push(canonical);
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let includes = value.get("include").and_then(|i| i.as_array()).into_iter().flatten();
+    for include in includes.filter_map(|i| i.as_str()) {
+        collect_files(&base_dir.join(include), files)?;
+    }
+    Ok(())
+}
+
+/// Reads `path` and everything it includes into a single merged table.
+/// `visiting` holds the chain of files currently being loaded, to detect cycles.
+fn load_layered(
+    path: &Path,
+    audit: Option<(&str, &AuditLog)>,
+    visiting: &mut Vec<PathBuf>,
+) -> Result<serde_json::Value, String> {
+    let canonical = fs::canonicalize(path)
+        .map_err(|e| format!("Failed to read config file '{}': {}", path.display(), e))?;
+    if visiting.contains(&canonical) {
+        return Err(format!("Config include cycle detected at '{}'", path.display()));
+    }
+
+    let config_str = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config file '{}': {}", path.display(), e))?;
+    if let Some((user, audit)) = audit {
+        audit.check_config(user, path, &config_str);
+    }
+    let mut value = parse(path, &config_str)?;
+    // An empty YAML document parses to null; treat it like an empty table.
+    if value.is_null() {
+        value = serde_json::Value::Object(serde_json::Map::new());
+    }
+
+    let includes = match value.as_object_mut().and_then(|t| t.remove("include")) {
+        None => Vec::new(),
+        Some(serde_json::Value::Array(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                serde_json::Value::String(s) => Ok(s),
+                other => Err(format!(
+                    "'include' in '{}' must only contain strings, found {}",
+                    path.display(),
+                    other
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Some(_) => {
+            return Err(format!(
+                "'include' in '{}' must be an array of file names",
+                path.display()
+            ))
+        }
+    };
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    visiting.push(canonical);
+    let mut merged = serde_json::Value::Object(serde_json::Map::new());
+    for include in includes {
+        let layer = load_layered(&base_dir.join(include), audit, visiting)?;
+        merge(&mut merged, layer);
+    }
+    visiting.pop();
+    merge(&mut merged, value);
+    Ok(merged)
+}
+
+/// Merges `overlay` into `base`. Tables are merged key by key; any other value
+/// in `overlay` (including arrays) replaces the one in `base`.
+fn merge(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The commands every config needs, followed by `rest`.
+    fn from_toml(rest: &str) -> Result<(Config, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let contents = format!("[commands]\nteleoperation = \"teleop\"\nreplay = \"replay\"\n{}", rest);
+        from_value(parse(Path::new("config.toml"), &contents).unwrap())
+    }
+
+    #[test]
+    fn misspelled_keys_of_a_command_table_are_errors() {
+        let diagnostics = from_toml(
+            r#"
+            [commands.record]
+            command = "record"
+            working_dir = "/nonexistent"
+            "#,
+        )
+        .err()
+        .expect("the typo is an error");
+        assert_eq!(diagnostics[0].key, "commands.record");
+        assert!(diagnostics[0].message.starts_with("working_dir: unknown field `working_dir`"));
+    }
+
+    #[test]
+    fn misspelled_keys_of_a_variant_name_the_variant() {
+        let diagnostics = from_toml(
+            r#"
+            [[commands.record]]
+            name = "a"
+            command = "record"
+            [[commands.record]]
+            name = "b"
+            command = "record"
+            probe_cmd = "true"
+            "#,
+        )
+        .err()
+        .expect("the typo is an error");
+        assert!(diagnostics[0].message.starts_with("[1].probe_cmd: unknown field `probe_cmd`"));
+    }
+
+    #[test]
+    fn commands_can_be_strings_tables_or_lists() {
+        let (config, _) = from_toml(
+            r#"
+            [commands.record]
+            command = "record"
+            conda_env = "rec"
+            [[commands.train]]
+            name = "a"
+            command = "train"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.commands.variant(ProcessType::Teleoperation, 0).command, "teleop");
+        assert_eq!(config.commands.variant(ProcessType::Record, 0).conda_env.as_deref(), Some("rec"));
+        assert_eq!(config.commands.variant(ProcessType::Train, 0).name, "a");
+    }
+
+    #[test]
+    fn includes_are_merged_before_the_including_file() {
+        let dir = std::env::temp_dir().join(format!("config-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("shared")).unwrap();
+        fs::write(
+            dir.join("shared/base.toml"),
+            "[commands]\nteleoperation = \"teleop\"\nreplay = \"replay\"\nrecord = \"record\"\n\
+             working_directory = \"/base\"\n[app]\nconda_path = \"/conda\"\noperators = [\"a\", \"b\"]\n",
+        )
+        .unwrap();
+        // Include paths are relative to the file that includes them.
+        let robot = "include: [base.toml]\ncommands:\n  record: record --robot\n";
+        fs::write(dir.join("shared/robot.yaml"), robot).unwrap();
+        fs::write(
+            dir.join("config.toml"),
+            "include = [\"shared/robot.yaml\"]\n[app]\noperators = [\"c\"]\n",
+        )
+        .unwrap();
+
+        let (config, _) = load_unaudited(&dir.join("config.toml")).unwrap();
+        assert_eq!(config.commands.variant(ProcessType::Record, 0).command, "record --robot");
+        assert_eq!(config.commands.variant(ProcessType::Replay, 0).command, "replay");
+        assert_eq!(config.commands.working_directory, "/base");
+        // Tables are merged key by key, arrays replaced.
+        assert_eq!(config.app.conda_path.as_deref(), Some("/conda"));
+        assert_eq!(config.app.operators, ["c"]);
+        assert_eq!(files(&dir.join("config.toml")).unwrap().len(), 3);
+
+        fs::write(dir.join("shared/base.toml"), "include = [\"robot.yaml\"]\n").unwrap();
+        let diagnostics = load_unaudited(&dir.join("config.toml")).err().expect("the cycle is an error");
+        assert!(diagnostics[0].message.starts_with("Config include cycle detected"), "{}", diagnostics[0].message);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn push_uploads_a_dataset_path_with_spaces_as_one_argument() {
+        let (config, _) = from_toml("record = \"record\"\n").unwrap();
+        let values = [("repo_id", "lab/demo"), ("dataset_path", "/data/my datasets/lab/demo")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let command = crate::placeholders::substitute(&config.commands.variant(ProcessType::Push, 0).command, &values);
+        let output = std::process::Command::new("bash")
+            .args(["-c", &format!("printf '%s\\n' {}", command.trim_start_matches("huggingface-cli "))])
+            .output()
+            .unwrap();
+        let arguments: Vec<_> = String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect();
+        assert_eq!(arguments, ["upload", "lab/demo", "/data/my datasets/lab/demo", "--repo-type=dataset"]);
+    }
+
+    #[test]
+    fn unknown_keys_elsewhere_are_warnings() {
+        let (_, diagnostics) = from_toml("record = \"record\"\nconda_pth = \"x\"\n").unwrap();
+        assert!(diagnostics.iter().any(|d| d.key == "commands.conda_pth" && d.severity == Severity::Warning));
+    }
+}
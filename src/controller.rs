@@ -0,0 +1,295 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::net::UdpSocket;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use tracing::{error, info};
+
+/// A joystick axis or button, by its index in the Linux joystick API.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Input {
+    Axis(u8),
+    Button(u8),
+}
+
+/// An input forwarded to the teleop script under `name`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Mapping {
+    pub name: String,
+    pub input: Input,
+    /// Flips the sign of an axis.
+    #[serde(default)]
+    pub invert: bool,
+}
+
+/// The controller bridge, set up in the GUI and remembered in the app state.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BridgeSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// The joystick device, e.g. `/dev/input/js0`.
+    #[serde(default)]
+    pub device: String,
+    /// The local UDP port the mapped values are sent to.
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub mappings: Vec<Mapping>,
+}
+
+fn default_port() -> u16 {
+    5599
+}
+
+impl Default for BridgeSettings {
+    fn default() -> Self {
+        Self { enabled: false, device: String::new(), port: default_port(), mappings: Vec::new() }
+    }
+}
+
+/// The joysticks plugged in, as `(device, name)`.
+pub fn devices() -> Vec<(String, String)> {
+    let Ok(entries) = fs::read_dir("/dev/input") else { return Vec::new() };
+    let mut devices: Vec<(String, String)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let file_name = e.file_name().to_string_lossy().into_owned();
+            file_name.starts_with("js").then_some(file_name)
+        })
+        .map(|file_name| {
+            let name = fs::read_to_string(Path::new("/sys/class/input").join(&file_name).join("device/name"))
+                .map(|n| n.trim().to_string())
+                .unwrap_or_default();
+            (format!("/dev/input/{}", file_name), name)
+        })
+        .collect();
+    devices.sort();
+    devices
+}
+
+/// An event of the Linux joystick API (`struct js_event`).
+#[derive(Clone, Copy, Debug)]
+struct Event {
+    input: Input,
+    /// -1 to 1 for axes, 0 or 1 for buttons.
+    value: f64,
+    /// Reports the initial state rather than a change.
+    initial: bool,
+}
+
+const JS_EVENT_BUTTON: u8 = 0x01;
+const JS_EVENT_AXIS: u8 = 0x02;
+const JS_EVENT_INIT: u8 = 0x80;
+
+fn parse(bytes: [u8; 8]) -> Option<Event> {
+    let value = i16::from_le_bytes([bytes[4], bytes[5]]);
+    let kind = bytes[6];
+    let number = bytes[7];
+    let input = match kind & !JS_EVENT_INIT {
+        JS_EVENT_BUTTON => Input::Button(number),
+        JS_EVENT_AXIS => Input::Axis(number),
+        _ => return None,
+    };
+    let value = match input {
+        Input::Axis(_) => (f64::from(value) / 32767.0).clamp(-1.0, 1.0),
+        Input::Button(_) => f64::from(value.signum().abs()),
+    };
+    Some(Event { input, value, initial: kind & JS_EVENT_INIT != 0 })
+}
+
+/// The current values of the mapped inputs, as a JSON object by name.
+fn payload(mappings: &[Mapping], values: &BTreeMap<(u8, u8), f64>) -> String {
+    let object: serde_json::Map<String, serde_json::Value> = mappings
+        .iter()
+        .map(|m| {
+            let value = values.get(&key(m.input)).copied().unwrap_or(0.0);
+            let value = if m.invert && matches!(m.input, Input::Axis(_)) { -value } else { value };
+            (m.name.clone(), value.into())
+        })
+        .collect();
+    serde_json::Value::Object(object).to_string()
+}
+
+/// Orders axes before buttons.
+fn key(input: Input) -> (u8, u8) {
+    match input {
+        Input::Axis(n) => (0, n),
+        Input::Button(n) => (1, n),
+    }
+}
+
+/// Reads `device` and sends the mapped values to the port whenever one of
+/// them changes, until the device goes away or the bridge is dropped.
+fn forward(
+    device: &str,
+    mut settings: (Vec<Mapping>, u16),
+    updates: Receiver<(Vec<Mapping>, u16)>,
+    events: Sender<Event>,
+    ctx: egui::Context,
+) -> Result<(), String> {
+    let mut file = fs::File::open(device).map_err(|e| format!("Failed to open '{}': {}", device, e))?;
+    let socket = UdpSocket::bind("127.0.0.1:0").map_err(|e| format!("Failed to open a UDP socket: {}", e))?;
+    let mut values = BTreeMap::new();
+    loop {
+        let mut bytes = [0; 8];
+        file.read_exact(&mut bytes).map_err(|e| format!("Failed to read '{}': {}", device, e))?;
+        let Some(event) = parse(bytes) else { continue };
+        if let Some(latest) = updates.try_iter().last() {
+            settings = latest;
+        }
+        let (mappings, port) = &settings;
+        values.insert(key(event.input), event.value);
+        if !event.initial && mappings.iter().any(|m| m.input == event.input) {
+            // Nobody listening is fine; the teleop script may not run yet.
+            let _ = socket.send_to(payload(mappings, &values).as_bytes(), ("127.0.0.1", *port));
+        }
+        if events.send(event).is_err() {
+            return Ok(());
+        }
+        ctx.request_repaint();
+    }
+}
+
+/// A controller being forwarded in the background.
+pub struct Bridge {
+    pub device: String,
+    events: Receiver<Event>,
+    updates: Sender<(Vec<Mapping>, u16)>,
+    done: Receiver<Result<(), String>>,
+    /// The current value of every input seen so far.
+    values: BTreeMap<(u8, u8), f64>,
+    /// Why forwarding stopped, if it did.
+    pub error: Option<String>,
+    /// The mapping whose input is set by the next input moved, if any.
+    learning: Option<usize>,
+}
+
+impl Bridge {
+    /// Starts forwarding the device of `settings`.
+    pub fn start(settings: &BridgeSettings, ctx: &egui::Context) -> Self {
+        let (event_sender, events) = mpsc::channel();
+        let (updates, update_receiver) = mpsc::channel();
+        let (done_sender, done) = mpsc::channel();
+        let device = settings.device.clone();
+        let initial = (settings.mappings.clone(), settings.port);
+        let ctx = ctx.clone();
+        info!("Forwarding controller '{}' to UDP port {}", device, settings.port);
+        std::thread::spawn(move || {
+            let result = forward(&device, initial, update_receiver, event_sender, ctx.clone());
+            let _ = done_sender.send(result);
+            ctx.request_repaint();
+        });
+        Self {
+            device: settings.device.clone(),
+            events,
+            updates,
+            done,
+            values: BTreeMap::new(),
+            error: None,
+            learning: None,
+        }
+    }
+
+    /// Hands changed mappings or port to the forwarding thread.
+    pub fn update(&self, settings: &BridgeSettings) {
+        let _ = self.updates.send((settings.mappings.clone(), settings.port));
+    }
+
+    /// Picks up the inputs moved since the last call; returns whether a
+    /// mapping learned its input.
+    pub fn poll(&mut self, settings: &mut BridgeSettings) -> bool {
+        let mut learned = false;
+        for event in self.events.try_iter() {
+            let previous = self.values.insert(key(event.input), event.value);
+            let moved = !event.initial && (event.value - previous.unwrap_or(0.0)).abs() >= 0.5;
+            if let Some(mapping) = self.learning.filter(|_| moved).and_then(|i| settings.mappings.get_mut(i)) {
+                mapping.input = event.input;
+                self.learning = None;
+                learned = true;
+            }
+        }
+        if self.error.is_none() {
+            if let Ok(Err(e)) = self.done.try_recv() {
+                error!("{}", e);
+                self.error = Some(e);
+            }
+        }
+        learned
+    }
+}
+
+fn input_label(input: Input) -> String {
+    match input {
+        Input::Axis(n) => format!("Axis {}", n),
+        Input::Button(n) => format!("Button {}", n),
+    }
+}
+
+/// The settings form with the live values of the inputs; returns whether the
+/// settings changed.
+pub fn ui(ui: &mut egui::Ui, settings: &mut BridgeSettings, bridge: Option<&mut Bridge>) -> bool {
+    let before = settings.clone();
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut settings.enabled, "Forward");
+        egui::ComboBox::from_id_source("controller_device")
+            .selected_text(if settings.device.is_empty() { "pick a controller" } else { settings.device.as_str() })
+            .show_ui(ui, |ui| {
+                for (device, name) in devices() {
+                    let label = format!("{} {}", device, name);
+                    ui.selectable_value(&mut settings.device, device, label);
+                }
+            });
+        ui.label("to UDP port");
+        ui.add(egui::DragValue::new(&mut settings.port).clamp_range(1024..=65535));
+    });
+    let Some(bridge) = bridge else {
+        ui.weak("Pick a controller and enable forwarding to see its inputs.");
+        return *settings != before;
+    };
+    if let Some(e) = &bridge.error {
+        ui.colored_label(egui::Color32::RED, e);
+    }
+
+    let mut remove = None;
+    egui::Grid::new("controller_mappings").num_columns(5).show(ui, |ui| {
+        for (index, mapping) in settings.mappings.iter_mut().enumerate() {
+            ui.add(egui::TextEdit::singleline(&mut mapping.name).desired_width(100.0));
+            if bridge.learning == Some(index) {
+                ui.label("move an input...");
+            } else if ui.button(input_label(mapping.input)).on_hover_text("Click, then move an input").clicked() {
+                bridge.learning = Some(index);
+            }
+            let enabled = matches!(mapping.input, Input::Axis(_));
+            ui.add_enabled(enabled, egui::Checkbox::new(&mut mapping.invert, "invert"));
+            let value = bridge.values.get(&key(mapping.input)).copied().unwrap_or(0.0);
+            ui.monospace(format!("{:+.2}", if mapping.invert && enabled { -value } else { value }));
+            if ui.small_button("✖").clicked() {
+                remove = Some(index);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(index) = remove {
+        settings.mappings.remove(index);
+        bridge.learning = None;
+    }
+    if ui.button("Add mapping").clicked() {
+        let name = format!("input{}", settings.mappings.len() + 1);
+        settings.mappings.push(Mapping { name, input: Input::Button(0), invert: false });
+        bridge.learning = Some(settings.mappings.len() - 1);
+    }
+
+    ui.collapsing("All inputs", |ui| {
+        ui.horizontal_wrapped(|ui| {
+            for (&(kind, number), value) in &bridge.values {
+                let input = if kind == 0 { Input::Axis(number) } else { Input::Button(number) };
+                ui.monospace(format!("{}: {:+.2}", input_label(input), value));
+            }
+        });
+    });
+    *settings != before
+}
@@ -0,0 +1,195 @@
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+use std::time::SystemTime;
+use tracing::{error, info};
+
+use crate::paths;
+
+/// The `[core_dumps]` config table: core dumps of launched commands that
+/// crash are kept and linked from their run.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct CoreDumpsConfig {
+    /// Where dumps are kept; `cores` in the data directory if unset.
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// Largest dump written, in MB. As large as the system allows if unset.
+    #[serde(default)]
+    pub max_mb: Option<u64>,
+}
+
+impl CoreDumpsConfig {
+    pub fn dir(&self) -> PathBuf {
+        match &self.dir {
+            Some(dir) => PathBuf::from(paths::expand(dir)),
+            None => paths::data_dir().join("cores"),
+        }
+    }
+}
+
+/// Signals whose default action dumps core (`man 7 signal`).
+const DUMPING_SIGNALS: [i32; 10] = [3, 4, 5, 6, 7, 8, 11, 24, 25, 31];
+
+/// The core dump of a run being looked for once it ended.
+pub struct Watch {
+    /// Relative core file patterns are relative to this.
+    working_directory: PathBuf,
+    started_at: DateTime<Local>,
+    /// Where the dump is kept.
+    dest: PathBuf,
+    /// The `ulimit -c` set for the command.
+    limit: String,
+}
+
+impl Watch {
+    /// A watch for the run started at `started_at` whose output goes to `log_file`.
+    pub fn new(
+        config: &CoreDumpsConfig,
+        log_file: &Path,
+        working_directory: PathBuf,
+        started_at: DateTime<Local>,
+    ) -> Self {
+        let name = log_file.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        Self {
+            working_directory,
+            started_at,
+            dest: config.dir().join(format!("{}.core", name)),
+            limit: config.max_mb.map(|mb| (mb * 1024).to_string()).unwrap_or_else(|| "unlimited".to_string()),
+        }
+    }
+
+    /// Wraps `command` so that it may dump core, up to `max_mb` (or the hard
+    /// limit).
+    pub fn wrap(&self, command: &str) -> String {
+        format!("ulimit -c {} 2>/dev/null || ulimit -c hard\n{}", self.limit, command)
+    }
+
+    /// The signal a command that exited with `exit_code` was killed by, if it
+    /// was one that dumps core.
+    pub fn signal(exit_code: Option<i32>) -> Option<i32> {
+        let signal = exit_code? - 128;
+        DUMPING_SIGNALS.contains(&signal).then_some(signal)
+    }
+
+    /// Finds the dump written since the run started and moves it to its
+    /// destination; `None` if there is none, e.g. because dumps are disabled
+    /// system-wide.
+    fn collect(&self) -> Result<Option<PathBuf>, String> {
+        if let Some(parent) = self.dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+        }
+        let pattern = fs::read_to_string("/proc/sys/kernel/core_pattern")
+            .map_err(|e| format!("Failed to read the core pattern: {}", e))?;
+        let pattern = pattern.trim();
+        match pattern.strip_prefix('|') {
+            Some(handler) if handler.contains("systemd-coredump") => self.collect_from_coredumpctl(),
+            Some(handler) => Err(format!(
+                "Core dumps go to '{}', which they cannot be collected from; see /proc/sys/kernel/core_pattern",
+                handler.split_whitespace().next().unwrap_or(handler)
+            )),
+            None => self.collect_from_file(pattern),
+        }
+    }
+
+    /// The latest dump kept by systemd-coredump since the run started.
+    fn collect_from_coredumpctl(&self) -> Result<Option<PathBuf>, String> {
+        let output = Command::new("coredumpctl")
+            .arg(format!("--since=@{}", self.started_at.timestamp()))
+            .arg("--output")
+            .arg(&self.dest)
+            .arg("dump")
+            .output()
+            .map_err(|e| format!("Failed to run coredumpctl: {}", e))?;
+        if output.status.success() && self.dest.is_file() {
+            Ok(Some(self.dest.clone()))
+        } else if String::from_utf8_lossy(&output.stderr).contains("No coredumps found") {
+            Ok(None)
+        } else {
+            Err(format!("coredumpctl failed: {}", String::from_utf8_lossy(&output.stderr).trim()))
+        }
+    }
+
+    /// The latest dump written since the run started by the kernel itself,
+    /// to a file named after `pattern` such as `core` or `/tmp/core.%e.%p`.
+    fn collect_from_file(&self, pattern: &str) -> Result<Option<PathBuf>, String> {
+        let pattern = self.working_directory.join(pattern);
+        let dir = pattern.parent().filter(|d| !d.to_string_lossy().contains('%'));
+        let Some((dir, name)) = dir.zip(pattern.file_name()) else {
+            return Err(format!("Core dumps cannot be found from the pattern '{}'", pattern.display()));
+        };
+        let prefix = name.to_string_lossy();
+        let prefix = prefix.split('%').next().unwrap_or_default();
+        let since = SystemTime::from(self.started_at);
+        let Ok(entries) = fs::read_dir(dir) else { return Ok(None) };
+        let newest = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with(prefix))
+            .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+            .filter(|(modified, path)| *modified >= since && is_elf(path))
+            .max_by_key(|(modified, _)| *modified);
+        let Some((_, path)) = newest else { return Ok(None) };
+        // Copied if the destination is on another file system.
+        fs::rename(&path, &self.dest)
+            .or_else(|_| fs::copy(&path, &self.dest).and_then(|_| fs::remove_file(&path)))
+            .map_err(|e| format!("Failed to move '{}' to '{}': {}", path.display(), self.dest.display(), e))?;
+        Ok(Some(self.dest.clone()))
+    }
+}
+
+/// Whether `path` starts like a core file does.
+fn is_elf(path: &Path) -> bool {
+    let mut magic = [0; 4];
+    fs::File::open(path).and_then(|mut f| f.read_exact(&mut magic)).is_ok() && magic == *b"\x7fELF"
+}
+
+/// The dump found for a run, if any.
+type Collected = Result<Option<PathBuf>, String>;
+
+/// Dumps being collected in the background, by the index of their run.
+#[derive(Default)]
+pub struct Collector {
+    pending: Vec<(usize, Receiver<Collected>)>,
+}
+
+impl Collector {
+    /// Starts looking for the dump of the run at `index`.
+    pub fn start(&mut self, index: usize, watch: Watch) {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(watch.collect());
+        });
+        self.pending.push((index, receiver));
+    }
+
+    /// Whether any dump is still being collected.
+    pub fn collecting(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// The dumps collected since the last call, by the index of their run.
+    pub fn poll(&mut self) -> Vec<(usize, PathBuf)> {
+        let mut collected = Vec::new();
+        self.pending.retain(|(index, receiver)| match receiver.try_recv() {
+            Ok(Ok(Some(path))) => {
+                info!("Kept the core dump of run {} at '{}'", index, path.display());
+                collected.push((*index, path));
+                false
+            }
+            Ok(Ok(None)) => {
+                info!("No core dump was written for run {}", index);
+                false
+            }
+            Ok(Err(e)) => {
+                error!("{}", e);
+                false
+            }
+            Err(mpsc::TryRecvError::Empty) => true,
+            Err(mpsc::TryRecvError::Disconnected) => false,
+        });
+        collected
+    }
+}
@@ -0,0 +1,552 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{command, placeholders};
+
+/// The LeRobot flag naming the dataset a command records into or replays from.
+pub const REPO_ID_FLAG: &str = "--dataset.repo_id";
+/// The LeRobot flag overriding where a dataset is stored locally.
+pub const ROOT_FLAG: &str = "--dataset.root";
+
+/// How many datasets the "Recent datasets" list remembers.
+const MAX_RECENT: usize = 10;
+
+/// A dataset used by a recent run.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RecentDataset {
+    pub repo_id: String,
+    /// The `--dataset.root` the dataset was used with, if any.
+    #[serde(default)]
+    pub root: Option<String>,
+    pub last_used: DateTime<Local>,
+}
+
+impl RecentDataset {
+    /// Where the dataset lives on disk.
+    pub fn local_path(&self) -> PathBuf {
+        local_path(&self.repo_id, self.root.as_deref())
+    }
+}
+
+/// Extracts the dataset a command uses, if it passes `--dataset.repo_id`.
+pub fn from_command(command: &str) -> Option<RecentDataset> {
+    let repo_id = command::flag_value(command, REPO_ID_FLAG).filter(|r| !r.is_empty())?;
+    Some(RecentDataset {
+        repo_id,
+        root: command::flag_value(command, ROOT_FLAG),
+        last_used: Local::now(),
+    })
+}
+
+/// Checks `repo_id` against the naming rules of the Hugging Face Hub, which
+/// LeRobot also applies to local datasets.
+pub fn check_repo_id(repo_id: &str) -> Result<(), String> {
+    let (namespace, name) = repo_id
+        .split_once('/')
+        .ok_or_else(|| format!("'{}' is not of the form <namespace>/<name>.", repo_id))?;
+    for (what, part) in [("namespace", namespace), ("name", name)] {
+        if part.is_empty() {
+            return Err(format!("The {} is empty.", what));
+        }
+        if part.len() > 96 {
+            return Err(format!("The {} is longer than 96 characters.", what));
+        }
+        if let Some(c) = part.chars().find(|c| !c.is_ascii_alphanumeric() && !matches!(c, '-' | '_' | '.')) {
+            return Err(format!("The {} contains '{}'; only letters, digits, '-', '_' and '.' are allowed.", what, c));
+        }
+        if part.starts_with(['-', '.']) || part.ends_with(['-', '.']) {
+            return Err(format!("The {} starts or ends with '-' or '.'.", what));
+        }
+        if part.contains("--") || part.contains("..") {
+            return Err(format!("The {} contains '--' or '..'.", what));
+        }
+    }
+    if name.ends_with(".git") {
+        return Err("The name ends with '.git'.".to_string());
+    }
+    Ok(())
+}
+
+/// Moves (or adds) `dataset` to the front of `recent`, keeping the list short.
+pub fn remember(recent: &mut Vec<RecentDataset>, dataset: RecentDataset) {
+    recent.retain(|d| d.repo_id != dataset.repo_id);
+    recent.insert(0, dataset);
+    recent.truncate(MAX_RECENT);
+}
+
+/// The `[dataset_naming]` config table: every Record launch, or recording
+/// session, gets a dataset of its own named from `template` instead of
+/// recording into the one in the command.
+#[derive(Deserialize, Clone, Debug)]
+pub struct NamingConfig {
+    /// The repo id, e.g. `{user}/{task}_{date}_{seq}`. `{user}`, `{task}`
+    /// (from `--dataset.single_task`), `{date}`, `{time}` and the placeholders
+    /// of the Record command are filled in; `{seq}` counts up from 1 to the
+    /// first repo id not used yet.
+    pub template: String,
+    /// How `{date}` is written, in `strftime` format.
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+}
+
+fn default_date_format() -> String {
+    "%Y%m%d".to_string()
+}
+
+/// `value` with everything but letters, digits, `-`, `_` and `.` replaced by
+/// `_`, so it can be part of a repo id.
+fn slug(value: &str) -> String {
+    let mut slug = String::new();
+    for c in value.trim().chars() {
+        if c.is_ascii_alphanumeric() || matches!(c, '-' | '.') {
+            slug.push(c);
+        } else if !slug.ends_with('_') {
+            slug.push('_');
+        }
+    }
+    slug.trim_matches('_').chars().take(64).collect()
+}
+
+/// A new repo id from the template of `config`, with `values` filled in;
+/// `taken` tells which repo ids are in use. A template without `{seq}` gets
+/// `_2`, `_3`, ... appended if its repo id is taken.
+pub fn new_name(
+    config: &NamingConfig,
+    values: &HashMap<String, String>,
+    taken: impl Fn(&str) -> bool,
+) -> Result<String, String> {
+    let now = Local::now();
+    let mut values: HashMap<String, String> = values.iter().map(|(k, v)| (k.clone(), slug(v))).collect();
+    values.retain(|_, v| !v.is_empty());
+    values.insert("date".to_string(), slug(&now.format(&config.date_format).to_string()));
+    values.insert("time".to_string(), now.format("%H%M%S").to_string());
+    let has_seq = placeholders::names(&config.template).iter().any(|n| n == "seq");
+    let mut seq = 1;
+    loop {
+        values.insert("seq".to_string(), seq.to_string());
+        let mut name = placeholders::fill(&config.template, &values);
+        if let Some(unfilled) = placeholders::names(&name).first() {
+            return Err(format!("The dataset name template uses {{{}}}, which has no value.", unfilled));
+        }
+        if !has_seq && seq > 1 {
+            name = format!("{}_{}", name, seq);
+        }
+        if !taken(&name) {
+            return Ok(name);
+        }
+        seq += 1;
+    }
+}
+
+/// Where LeRobot stores `repo_id` locally: `root` if given, otherwise under
+/// `$HF_LEROBOT_HOME` (which defaults to `$HF_HOME/lerobot`, and that to
+/// `~/.cache/huggingface/lerobot`).
+pub fn local_path(repo_id: &str, root: Option<&str>) -> PathBuf {
+    if let Some(root) = root.filter(|r| !r.is_empty()) {
+        return PathBuf::from(crate::paths::expand(root));
+    }
+    let lerobot_home = match env::var_os("HF_LEROBOT_HOME") {
+        Some(home) => PathBuf::from(home),
+        None => {
+            let hf_home = env::var_os("HF_HOME").map(PathBuf::from).unwrap_or_else(|| {
+                PathBuf::from(env::var_os("HOME").unwrap_or_default()).join(".cache/huggingface")
+            });
+            hf_home.join("lerobot")
+        }
+    };
+    lerobot_home.join(repo_id)
+}
+
+/// The parts of a LeRobot dataset's `meta/info.json` we use.
+#[derive(Deserialize, Clone, Debug)]
+pub struct DatasetInfo {
+    #[serde(default)]
+    pub codebase_version: String,
+    #[serde(default)]
+    pub robot_type: Option<String>,
+    #[serde(default)]
+    pub total_episodes: u64,
+    #[serde(default)]
+    pub total_frames: u64,
+    #[serde(default)]
+    pub fps: f64,
+    /// Feature name -> description (dtype, shape, video info, ...).
+    #[serde(default)]
+    pub features: serde_json::Map<String, serde_json::Value>,
+    /// How many episodes share a chunk folder (v2).
+    #[serde(default)]
+    pub chunks_size: Option<u64>,
+    /// Where the data and videos of each episode are stored (v2), e.g.
+    /// `data/chunk-{episode_chunk:03d}/episode_{episode_index:06d}.parquet`.
+    #[serde(default)]
+    pub data_path: Option<String>,
+    #[serde(default)]
+    pub video_path: Option<String>,
+}
+
+/// Reads `meta/info.json` of the dataset stored at `path`.
+pub fn read_info(path: &Path) -> Result<DatasetInfo, String> {
+    let info_path = path.join("meta").join("info.json");
+    let contents = fs::read_to_string(&info_path)
+        .map_err(|e| format!("'{}' is not a LeRobot dataset ({}: {})", path.display(), info_path.display(), e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse '{}': {}", info_path.display(), e))
+}
+
+/// The number of episodes of each task in the dataset stored at `path`, from
+/// `meta/episodes.jsonl`; empty for v3 datasets, which keep it in parquet.
+pub fn episode_tasks(path: &Path) -> BTreeMap<String, u32> {
+    let mut tasks = BTreeMap::new();
+    let Ok(contents) = fs::read_to_string(path.join("meta").join("episodes.jsonl")) else { return tasks };
+    for entry in contents.lines().filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok()) {
+        let episode_tasks = entry.get("tasks").and_then(|t| t.as_array()).into_iter().flatten();
+        for task in episode_tasks.filter_map(|t| t.as_str()) {
+            *tasks.entry(task.to_string()).or_default() += 1;
+        }
+    }
+    tasks
+}
+
+/// Identifies the dataset stored in `folder`. LeRobot keeps datasets at
+/// `<root>/<namespace>/<name>`, so the last two path components make up the
+/// repo id; `root` is only recorded if the folder is not where LeRobot would
+/// look for that repo id by default.
+pub fn from_folder(folder: &Path) -> Result<(RecentDataset, DatasetInfo), String> {
+    let info = read_info(folder)?;
+    let folder = fs::canonicalize(folder).unwrap_or_else(|_| folder.to_path_buf());
+    let name = folder.file_name().and_then(|n| n.to_str());
+    let namespace = folder.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str());
+    let repo_id = match (namespace, name) {
+        (Some(namespace), Some(name)) => format!("{}/{}", namespace, name),
+        _ => return Err(format!("Cannot derive a repo id from '{}'", folder.display())),
+    };
+    let default_path = local_path(&repo_id, None);
+    let is_default = fs::canonicalize(&default_path).is_ok_and(|p| p == folder);
+    let dataset = RecentDataset {
+        repo_id,
+        root: (!is_default).then(|| folder.display().to_string()),
+        last_used: Local::now(),
+    };
+    Ok((dataset, info))
+}
+
+/// A camera stream of a dataset, as far as its metadata tells.
+#[derive(Debug, Clone)]
+pub struct CameraInfo {
+    pub name: String,
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+    pub fps: Option<f64>,
+}
+
+impl DatasetInfo {
+    /// Total recorded time in seconds.
+    pub fn duration_s(&self) -> Option<f64> {
+        (self.fps > 0.0).then(|| self.total_frames as f64 / self.fps)
+    }
+
+    /// Average episode length in frames.
+    pub fn mean_episode_frames(&self) -> Option<f64> {
+        (self.total_episodes > 0).then(|| self.total_frames as f64 / self.total_episodes as f64)
+    }
+
+    /// The camera streams with their resolution and rate: from the `video.*`
+    /// entries LeRobot records for videos, otherwise from the feature's shape
+    /// and the dataset's rate.
+    pub fn camera_info(&self) -> Vec<CameraInfo> {
+        self.features
+            .iter()
+            .filter(|(_, f)| matches!(f.get("dtype").and_then(|d| d.as_str()), Some("video") | Some("image")))
+            .map(|(name, feature)| {
+                let video = |key: &str| feature.get("info").and_then(|i| i.get(key));
+                let shape: Vec<u64> = feature
+                    .get("shape")
+                    .and_then(|s| s.as_array())
+                    .map(|s| s.iter().filter_map(|d| d.as_u64()).collect())
+                    .unwrap_or_default();
+                let names: Vec<&str> = feature
+                    .get("names")
+                    .and_then(|n| n.as_array())
+                    .map(|n| n.iter().filter_map(|d| d.as_str()).collect())
+                    .unwrap_or_default();
+                // Shapes are (height, width, channels) unless the names say otherwise.
+                let dimension = |dimension: &str, default: usize| {
+                    let index = names.iter().position(|n| *n == dimension).unwrap_or(default);
+                    shape.get(index).copied()
+                };
+                CameraInfo {
+                    name: name.strip_prefix("observation.images.").unwrap_or(name).to_string(),
+                    width: video("video.width").and_then(|w| w.as_u64()).or_else(|| dimension("width", 1)),
+                    height: video("video.height").and_then(|h| h.as_u64()).or_else(|| dimension("height", 0)),
+                    fps: video("video.fps").and_then(|f| f.as_f64()).or((self.fps > 0.0).then_some(self.fps)),
+                }
+            })
+            .collect()
+    }
+
+    /// The features stored as videos, by key.
+    fn video_keys(&self) -> Vec<&str> {
+        self.features
+            .iter()
+            .filter(|(_, f)| f.get("dtype").and_then(|d| d.as_str()) == Some("video"))
+            .map(|(key, _)| key.as_str())
+            .collect()
+    }
+
+    /// The files of episode `index`, relative to the dataset: its data and
+    /// videos. Only v2 datasets keep a file per episode; empty for others.
+    pub fn episode_files(&self, index: u64) -> Vec<PathBuf> {
+        if !self.codebase_version.starts_with("v2.") {
+            return Vec::new();
+        }
+        let chunk = index / self.chunks_size.filter(|&c| c > 0).unwrap_or(1000);
+        let mut files = Vec::new();
+        if let Some(template) = &self.data_path {
+            files.push(PathBuf::from(fill_path(template, chunk, index, "")));
+        }
+        if let Some(template) = &self.video_path {
+            files.extend(self.video_keys().iter().map(|key| PathBuf::from(fill_path(template, chunk, index, key))));
+        }
+        files
+    }
+
+    /// Names of the camera streams (image or video features) in the dataset.
+    pub fn cameras(&self) -> Vec<&str> {
+        self.features
+            .iter()
+            .filter(|(_, f)| matches!(f.get("dtype").and_then(|d| d.as_str()), Some("video") | Some("image")))
+            .map(|(name, _)| name.strip_prefix("observation.images.").unwrap_or(name))
+            .collect()
+    }
+}
+
+/// An episode removed from a dataset.
+#[derive(Debug, Clone)]
+pub struct DeletedEpisode {
+    pub index: u64,
+    pub frames: u64,
+}
+
+/// Fills a LeRobot path template such as
+/// `data/chunk-{episode_chunk:03d}/episode_{episode_index:06d}.parquet`.
+fn fill_path(template: &str, chunk: u64, episode: u64, video_key: &str) -> String {
+    let pattern = regex::Regex::new(r"\{(\w+)(?::0(\d+)d)?\}").expect("valid regex");
+    pattern
+        .replace_all(template, |captures: &regex::Captures| {
+            let width = captures.get(2).and_then(|w| w.as_str().parse().ok()).unwrap_or(0);
+            match &captures[1] {
+                "episode_chunk" => format!("{:0width$}", chunk, width = width),
+                "episode_index" => format!("{:0width$}", episode, width = width),
+                "video_key" => video_key.to_string(),
+                _ => captures[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// Writes `contents` to `path` through a temporary file, so a crash cannot
+/// leave it half written.
+fn replace_file(path: &Path, contents: &str) -> Result<(), String> {
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, contents)
+        .and_then(|()| fs::rename(&temporary, path))
+        .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))
+}
+
+/// `meta/<file>` without the line of episode `index`, or `None` if the file
+/// does not exist.
+fn without_episode(
+    meta: &Path,
+    file: &str,
+    index: u64,
+) -> Result<Option<(String, Option<serde_json::Value>)>, String> {
+    let path = meta.join(file);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read '{}': {}", path.display(), e)),
+    };
+    let mut kept = String::new();
+    let mut removed = None;
+    for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+        let entry: serde_json::Value =
+            serde_json::from_str(line).map_err(|e| format!("Failed to parse '{}': {}", path.display(), e))?;
+        if entry.get("episode_index").and_then(|i| i.as_u64()) == Some(index) {
+            removed = Some(entry);
+        } else {
+            kept.push_str(line);
+            kept.push('\n');
+        }
+    }
+    Ok(Some((kept, removed)))
+}
+
+/// Deletes the last episode of the LeRobot v2 dataset at `path`: its data and
+/// videos, its lines in `meta/episodes.jsonl` and `meta/episodes_stats.jsonl`,
+/// and its frames from the totals in `meta/info.json`. The episodes before it
+/// keep their indices, so nothing else has to be rewritten. The metadata is
+/// updated first: should deleting a file fail, LeRobot ignores it anyway.
+pub fn delete_last_episode(path: &Path) -> Result<DeletedEpisode, String> {
+    let meta = path.join("meta");
+    let info_path = meta.join("info.json");
+    let contents =
+        fs::read_to_string(&info_path).map_err(|e| format!("Failed to read '{}': {}", info_path.display(), e))?;
+    let info: serde_json::Value =
+        serde_json::from_str(&contents).map_err(|e| format!("Failed to parse '{}': {}", info_path.display(), e))?;
+    let typed: DatasetInfo = serde_json::from_value(info.clone()).map_err(|e| e.to_string())?;
+    // v3 datasets keep many episodes per file.
+    if !typed.codebase_version.starts_with("v2.") {
+        return Err(format!(
+            "Deleting episodes is only supported for v2 datasets; this one is {}.",
+            typed.codebase_version
+        ));
+    }
+    let Some(index) = typed.total_episodes.checked_sub(1) else {
+        return Err("The dataset has no episodes.".to_string());
+    };
+    let chunks_size = typed.chunks_size.filter(|&c| c > 0).unwrap_or(1000);
+
+    let (episodes, removed) = without_episode(&meta, "episodes.jsonl", index)?
+        .ok_or_else(|| format!("'{}' has no meta/episodes.jsonl.", path.display()))?;
+    let frames = removed
+        .and_then(|e| e.get("length").and_then(|l| l.as_u64()))
+        .ok_or_else(|| format!("Episode {} is missing from meta/episodes.jsonl.", index))?;
+    let stats = without_episode(&meta, "episodes_stats.jsonl", index)?;
+
+    let videos = typed.video_keys();
+    let files: Vec<PathBuf> = typed.episode_files(index).iter().map(|f| path.join(f)).collect();
+
+    // The totals are replaced in the text, which keeps the order and layout
+    // of everything else in the file.
+    let remaining = index;
+    let total_videos = info.get("total_videos").and_then(|v| v.as_u64()).unwrap_or(0);
+    let mut contents = contents;
+    for (key, value) in [
+        ("total_episodes", remaining),
+        ("total_frames", typed.total_frames.saturating_sub(frames)),
+        ("total_videos", total_videos.saturating_sub(videos.len() as u64)),
+        ("total_chunks", remaining.div_ceil(chunks_size)),
+    ] {
+        let pattern = regex::Regex::new(&format!(r#""{}"(\s*):(\s*)\d+"#, key)).expect("valid regex");
+        contents = pattern.replace(&contents, format!(r#""{}"${{1}}:${{2}}{}"#, key, value)).into_owned();
+    }
+    let train = regex::Regex::new(r#""train"(\s*):(\s*)"0:\d+""#).expect("valid regex");
+    let contents = train.replace(&contents, format!(r#""train"${{1}}:${{2}}"0:{}""#, remaining)).into_owned();
+
+    replace_file(&meta.join("episodes.jsonl"), &episodes)?;
+    if let Some((stats, _)) = stats {
+        replace_file(&meta.join("episodes_stats.jsonl"), &stats)?;
+    }
+    replace_file(&info_path, &contents)?;
+    for file in &files {
+        match fs::remove_file(file) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to delete '{}': {}", file.display(), e)),
+        }
+        // Folders left empty go too, as LeRobot would not have made them.
+        for folder in file.ancestors().skip(1).take_while(|f| *f != path) {
+            if fs::remove_dir(folder).is_err() {
+                break;
+            }
+        }
+    }
+    Ok(DeletedEpisode { index, frames })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INFO: &str = r#"{
+    "codebase_version": "v2.1",
+    "total_episodes": 2,
+    "total_frames": 30,
+    "total_videos": 2,
+    "total_chunks": 1,
+    "chunks_size": 1000,
+    "fps": 30,
+    "splits": {
+        "train": "0:2"
+    },
+    "data_path": "data/chunk-{episode_chunk:03d}/episode_{episode_index:06d}.parquet",
+    "video_path": "videos/chunk-{episode_chunk:03d}/{video_key}/episode_{episode_index:06d}.mp4",
+    "features": {
+        "observation.images.top": {"dtype": "video"},
+        "action": {"dtype": "float32"}
+    }
+}"#;
+
+    /// A v2 dataset with two episodes of 10 and 20 frames, in a fresh folder.
+    fn dataset(name: &str, info: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("dataset-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        for dir in ["meta", "data/chunk-000", "videos/chunk-000/observation.images.top"] {
+            fs::create_dir_all(path.join(dir)).unwrap();
+        }
+        fs::write(path.join("meta/info.json"), info).unwrap();
+        fs::write(
+            path.join("meta/episodes.jsonl"),
+            "{\"episode_index\": 0, \"length\": 10}\n{\"episode_index\": 1, \"length\": 20}\n",
+        )
+        .unwrap();
+        fs::write(
+            path.join("meta/episodes_stats.jsonl"),
+            "{\"episode_index\": 0, \"stats\": {}}\n{\"episode_index\": 1, \"stats\": {}}\n",
+        )
+        .unwrap();
+        for episode in 0..2 {
+            fs::write(path.join(format!("data/chunk-000/episode_{:06}.parquet", episode)), "").unwrap();
+            let video = format!("videos/chunk-000/observation.images.top/episode_{:06}.mp4", episode);
+            fs::write(path.join(video), "").unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn deleting_the_last_episode_updates_the_metadata_and_removes_its_files() {
+        let path = dataset("delete", INFO);
+        let deleted = delete_last_episode(&path).unwrap();
+        assert_eq!((deleted.index, deleted.frames), (1, 20));
+
+        let info = fs::read_to_string(path.join("meta/info.json")).unwrap();
+        let expected = INFO
+            .replace("\"total_episodes\": 2", "\"total_episodes\": 1")
+            .replace("\"total_frames\": 30", "\"total_frames\": 10")
+            .replace("\"total_videos\": 2", "\"total_videos\": 1")
+            .replace("\"train\": \"0:2\"", "\"train\": \"0:1\"");
+        // Only the numbers change; the layout of the file is kept.
+        assert_eq!(info, expected);
+        let episodes = fs::read_to_string(path.join("meta/episodes.jsonl")).unwrap();
+        assert_eq!(episodes, "{\"episode_index\": 0, \"length\": 10}\n");
+        let stats = fs::read_to_string(path.join("meta/episodes_stats.jsonl")).unwrap();
+        assert_eq!(stats, "{\"episode_index\": 0, \"stats\": {}}\n");
+        assert!(!path.join("data/chunk-000/episode_000001.parquet").exists());
+        assert!(!path.join("videos/chunk-000/observation.images.top/episode_000001.mp4").exists());
+        assert!(path.join("data/chunk-000/episode_000000.parquet").exists());
+        assert!(path.join("videos/chunk-000/observation.images.top/episode_000000.mp4").exists());
+
+        // The last one leaves no chunk folders behind.
+        assert_eq!(delete_last_episode(&path).unwrap().index, 0);
+        let info = read_info(&path).unwrap();
+        assert_eq!((info.total_episodes, info.total_frames), (0, 0));
+        assert!(fs::read_to_string(path.join("meta/info.json")).unwrap().contains("\"total_chunks\": 0"));
+        assert!(!path.join("data/chunk-000").exists());
+        assert!(!path.join("videos/chunk-000").exists());
+        assert_eq!(delete_last_episode(&path).err().as_deref(), Some("The dataset has no episodes."));
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn v3_datasets_are_left_alone() {
+        let path = dataset("v3", &INFO.replace("v2.1", "v3.0"));
+        let error = delete_last_episode(&path).unwrap_err();
+        assert_eq!(error, "Deleting episodes is only supported for v2 datasets; this one is v3.0.");
+        assert_eq!(fs::read_to_string(path.join("meta/info.json")).unwrap(), INFO.replace("v2.1", "v3.0"));
+        assert!(path.join("data/chunk-000/episode_000001.parquet").exists());
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
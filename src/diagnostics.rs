@@ -0,0 +1,222 @@
+use regex::Regex;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::config;
+use crate::history::History;
+use crate::paths;
+
+/// How many of the latest runs have their output included.
+const RECENT_RUNS: usize = 10;
+
+/// Only the end of a log is included beyond this size.
+const MAX_LOG_BYTES: u64 = 2 << 20;
+
+/// What goes into a diagnostics bundle besides the config and history.
+pub struct Sources<'a> {
+    pub config_path: &'a Path,
+    pub history: &'a History,
+    /// The app's own log files.
+    pub app_logs: Vec<PathBuf>,
+}
+
+/// Replaces what looks like a credential in `text`: Hugging Face and similar
+/// tokens, and the value after `token=`, `--password ` and the like. The home
+/// directory, which usually names the user, becomes `~`.
+pub fn sanitize(text: &str) -> String {
+    static PATTERNS: OnceLock<[Regex; 2]> = OnceLock::new();
+    static HOME: OnceLock<Option<Regex>> = OnceLock::new();
+    let [tokens, assignments] = PATTERNS.get_or_init(|| {
+        [
+            Regex::new(r"\b(?:hf|ghp|gho|github_pat|sk|xox[bp])_[A-Za-z0-9_-]{8,}").unwrap(),
+            Regex::new(r#"(?i)(\b[\w.-]*(?:token|password|passwd|secret|api[_-]?key)["']?\s*[=:]\s*|--[\w.-]*(?:token|password|secret|api[_-]?key)[ =])["']?[^\s"',]+["']?"#)
+                .unwrap(),
+        ]
+    });
+    let home = HOME.get_or_init(|| {
+        let home = std::env::var("HOME").ok()?;
+        let home = home.trim_end_matches('/');
+        // Not followed by more of a name, so `/home/ana` leaves `/home/anabel` alone.
+        (!home.is_empty()).then(|| Regex::new(&format!(r"{}($|[^\w.-])", regex::escape(home))).unwrap())
+    });
+    let text = tokens.replace_all(text, "<redacted>");
+    let text = assignments.replace_all(&text, "${1}<redacted>");
+    match home {
+        Some(home) => home.replace_all(&text, "~${1}").into_owned(),
+        None => text.into_owned(),
+    }
+}
+
+/// [`sanitize`]s every string in `value`, and drops the values of keys that
+/// look like they hold a credential.
+fn sanitize_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(s) => *s = sanitize(s),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(sanitize_json),
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if ["token", "password", "secret", "api_key"].iter().any(|k| key.contains(k)) && value.is_string() {
+                    *value = serde_json::Value::String("<redacted>".to_string());
+                } else {
+                    sanitize_json(value);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The end of the file at `path`, at most [`MAX_LOG_BYTES`].
+fn read_tail(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(MAX_LOG_BYTES)))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// The output of `program args`, or why it could not be run.
+fn run(program: &str, args: &[&str]) -> String {
+    match Command::new(program).args(args).output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        Err(e) => format!("({} failed: {})", program, e),
+    }
+}
+
+/// The app version, OS, desktop session and tools the app relies on.
+fn platform_info() -> String {
+    let os = fs::read_to_string("/etc/os-release")
+        .ok()
+        .and_then(|r| r.lines().find_map(|l| l.strip_prefix("PRETTY_NAME=")).map(|n| n.trim_matches('"').to_string()))
+        .unwrap_or_else(|| std::env::consts::OS.to_string());
+    let env = |name: &str| std::env::var(name).unwrap_or_default();
+    let mut info = format!(
+        "teleop-record-replay {}\nCreated: {}\nOS: {}\nKernel: {}\nArchitecture: {}\nSession: {} ({})\n",
+        env!("CARGO_PKG_VERSION"),
+        chrono::Local::now().to_rfc3339(),
+        os,
+        run("uname", &["-srv"]),
+        std::env::consts::ARCH,
+        env("XDG_SESSION_TYPE"),
+        env("XDG_CURRENT_DESKTOP"),
+    );
+    for tool in ["mkfifo", "conda", "avahi-browse", "mosquitto_pub", "ros2", "ssh"] {
+        let found = run("bash", &["-c", &format!("command -v {}", tool)]);
+        info.push_str(&format!("{}: {}\n", tool, if found.is_empty() { "not found" } else { &found }));
+    }
+    info
+}
+
+/// Writes a zip archive of everything useful in a bug report to `dir` and
+/// returns its path. Credentials found in the config, commands and logs are
+/// redacted; files that cannot be read are noted in `errors.txt`.
+pub fn export(sources: &Sources, dir: &Path) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+    let path = dir.join(format!("diagnostics-{}.zip", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+    let file = File::create(&path).map_err(|e| format!("Failed to create '{}': {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let mut errors = Vec::new();
+    let add = |zip: &mut ZipWriter<File>, name: &str, contents: &str| -> Result<(), String> {
+        zip.start_file(name, SimpleFileOptions::default())
+            .and_then(|_| Ok(zip.write_all(contents.as_bytes())?))
+            .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))
+    };
+
+    add(&mut zip, "platform.txt", &platform_info())?;
+
+    match config::merged(sources.config_path) {
+        Ok(mut merged) => {
+            sanitize_json(&mut merged);
+            add(&mut zip, "config.json", &serde_json::to_string_pretty(&merged).unwrap_or_default())?;
+        }
+        Err(e) => errors.push(e),
+    }
+
+    match serde_json::to_value(sources.history.runs()) {
+        Ok(mut runs) => {
+            sanitize_json(&mut runs);
+            add(&mut zip, "history.json", &serde_json::to_string_pretty(&runs).unwrap_or_default())?;
+        }
+        Err(e) => errors.push(format!("Failed to serialize the run history: {}", e)),
+    }
+
+    let run_logs = sources.history.runs().iter().rev().take(RECENT_RUNS).filter_map(|r| r.log_file.clone());
+    for (folder, log) in sources.app_logs.iter().cloned().map(|l| ("logs", l)).chain(run_logs.map(|l| ("runs", l))) {
+        let name = log.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        match read_tail(&log) {
+            Ok(contents) => add(&mut zip, &format!("{}/{}", folder, name), &sanitize(&contents))?,
+            Err(e) => errors.push(format!("Failed to read '{}': {}", log.display(), e)),
+        }
+    }
+
+    if !errors.is_empty() {
+        add(&mut zip, "errors.txt", &errors.join("\n"))?;
+    }
+    zip.finish().map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Where bundles are written.
+pub fn default_dir() -> PathBuf {
+    paths::data_dir().join("diagnostics")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credentials_are_redacted() {
+        assert_eq!(sanitize("login with hf_abcdefgh12345678 now"), "login with <redacted> now");
+        assert_eq!(sanitize("HF_TOKEN=abc123 rec"), "HF_TOKEN=<redacted> rec");
+        assert_eq!(sanitize("api_key: \"abc123\""), "api_key: <redacted>");
+        assert_eq!(sanitize("push --hub.token xyz --private"), "push --hub.token <redacted> --private");
+        assert_eq!(sanitize("--db-password=hunter2"), "--db-password=<redacted>");
+    }
+
+    #[test]
+    fn the_home_directory_is_shortened() {
+        let home = std::env::var("HOME").unwrap();
+        let home = home.trim_end_matches('/');
+        if home.is_empty() {
+            return;
+        }
+        assert_eq!(sanitize(&format!("cd {}/data && ls {}", home, home)), "cd ~/data && ls ~");
+        assert_eq!(sanitize(&format!("'{}'", home)), "'~'");
+        let longer = format!("{}bel/data", home);
+        assert_eq!(sanitize(&longer), longer);
+    }
+
+    #[test]
+    fn ordinary_text_is_left_alone() {
+        let text = "lerobot-record --robot.port=/dev/ttyACM0 --dataset.repo_id=me/tokens_v2 --fps=30\nsk_x done";
+        assert_eq!(sanitize(text), text);
+    }
+
+    #[test]
+    fn json_values_of_secret_looking_keys_are_dropped() {
+        let mut config = serde_json::json!({
+            "hub": { "HF_Token": "plain", "repo": "me/data" },
+            "commands": ["rec --token=abc123", "replay"],
+            "api_key_count": 3,
+            "password": null,
+        });
+        sanitize_json(&mut config);
+        assert_eq!(
+            config,
+            serde_json::json!({
+                "hub": { "HF_Token": "<redacted>", "repo": "me/data" },
+                "commands": ["rec --token=<redacted>", "replay"],
+                "api_key_count": 3,
+                "password": null,
+            })
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use eframe::egui;
+use tracing::{debug, error};
+use serde::Deserialize;
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+
+/// The `[hosts]` config table: robot PCs commands can run on through the
+/// `{host}` placeholder, e.g. `ssh {host} lerobot-record ...`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct HostsConfig {
+    /// The mDNS/DNS-SD service type the robot PCs advertise.
+    #[serde(default = "default_service")]
+    pub service: String,
+    /// Hosts always offered, e.g. ones not advertising the service.
+    #[serde(default)]
+    pub known: Vec<String>,
+}
+
+fn default_service() -> String {
+    "_ssh._tcp".to_string()
+}
+
+/// A robot PC found on the network.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Host {
+    /// The advertised service name, shown in the picker.
+    pub name: String,
+    /// What `{host}` is replaced with: the IP address found, or a known host as configured.
+    pub address: String,
+}
+
+/// Looks for hosts advertising `service` with `avahi-browse`, which waits
+/// for the answers, so this takes a moment.
+fn browse(service: &str) -> Result<Vec<Host>, String> {
+    let output = Command::new("avahi-browse")
+        .args(["--resolve", "--terminate", "--parsable", "--no-db-lookup", service])
+        .output()
+        .map_err(|e| format!("Failed to run avahi-browse (is avahi-utils installed?): {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "avahi-browse failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    // Resolved entries look like
+    // `=;eth0;IPv4;robot-1;_ssh._tcp;local;robot-1.local;192.168.1.23;22;`.
+    let mut hosts = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let fields: Vec<&str> = line.split(';').collect();
+        if fields.len() < 9 || fields[0] != "=" || fields[2] != "IPv4" {
+            continue;
+        }
+        let host = Host { name: unescape(fields[3]), address: fields[7].to_string() };
+        if !hosts.contains(&host) {
+            hosts.push(host);
+        }
+    }
+    debug!("Found {} host(s) advertising {}", hosts.len(), service);
+    Ok(hosts)
+}
+
+/// Undoes avahi-browse's escaping of service names: `\032` for a space
+/// (a decimal byte value) or a backslash before punctuation.
+fn unescape(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let code = (bytes[i] == b'\\')
+            .then(|| name.get(i + 1..i + 4)?.parse::<u8>().ok())
+            .flatten();
+        match code {
+            Some(code) => {
+                out.push(code);
+                i += 4;
+            }
+            None if bytes[i] == b'\\' && i + 1 < bytes.len() => {
+                out.push(bytes[i + 1]);
+                i += 2;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Discovery of robot hosts in the background.
+#[derive(Default)]
+pub struct Discovery {
+    /// Hosts found by the last search.
+    pub hosts: Vec<Host>,
+    /// Why the last search failed, if it did.
+    pub error: Option<String>,
+    pending: Option<Receiver<Result<Vec<Host>, String>>>,
+    /// Whether a search was started at all.
+    started: bool,
+}
+
+impl Discovery {
+    /// Starts a new search unless one is running; `ctx` is woken up when it is done.
+    pub fn refresh(&mut self, service: &str, ctx: &egui::Context) {
+        if self.pending.is_some() {
+            return;
+        }
+        self.started = true;
+        let (sender, receiver) = mpsc::channel();
+        let service = service.to_string();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = sender.send(browse(&service));
+            ctx.request_repaint();
+        });
+        self.pending = Some(receiver);
+    }
+
+    /// Starts the first search, once.
+    pub fn start(&mut self, service: &str, ctx: &egui::Context) {
+        if !self.started {
+            self.refresh(service, ctx);
+        }
+    }
+
+    /// Whether a search is running.
+    pub fn searching(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Picks up the result of a finished search.
+    pub fn poll(&mut self) {
+        let Some(receiver) = &self.pending else { return };
+        let Ok(result) = receiver.try_recv() else { return };
+        self.pending = None;
+        match result {
+            Ok(hosts) => {
+                self.hosts = hosts;
+                self.error = None;
+            }
+            Err(e) => {
+                error!("{}", e);
+                self.error = Some(e);
+            }
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use tracing::{debug, warn};
+use regex::Regex;
+use serde::Deserialize;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// The `[disk_check]` config table: a write benchmark of the dataset volume
+/// run when a recording session starts.
+#[derive(Deserialize, Clone, Debug)]
+pub struct DiskCheckConfig {
+    /// Most data written by the benchmark, in MB.
+    #[serde(default = "default_size_mb")]
+    pub size_mb: u64,
+    /// Longest the benchmark runs, in seconds.
+    #[serde(default = "default_seconds")]
+    pub seconds: f64,
+    /// Throughput needed, in MB/s. Estimated from the cameras in the Record
+    /// command if unset.
+    #[serde(default)]
+    pub required_mb_s: Option<f64>,
+}
+
+fn default_size_mb() -> u64 {
+    256
+}
+
+fn default_seconds() -> f64 {
+    3.0
+}
+
+impl Default for DiskCheckConfig {
+    fn default() -> Self {
+        Self { size_mb: default_size_mb(), seconds: default_seconds(), required_mb_s: None }
+    }
+}
+
+const MB: f64 = 1_000_000.0;
+
+/// Size of each write; every one is flushed to the disk before the next.
+const CHUNK: usize = 8 << 20;
+
+/// Frame rate assumed for a camera that does not set one.
+const DEFAULT_FPS: f64 = 30.0;
+
+/// The throughput the cameras in `command` need, in MB/s, with the number of
+/// cameras found. Cameras are LeRobot's `--robot.cameras` entries such as
+/// `{type: opencv, width: 640, height: 480, fps: 30}`; frames are counted as
+/// raw RGB, as they are written out as images before being encoded.
+pub fn required_throughput(command: &str) -> Option<(f64, usize)> {
+    static PATTERNS: OnceLock<[Regex; 4]> = OnceLock::new();
+    let [entry, width, height, fps] = PATTERNS.get_or_init(|| {
+        [
+            Regex::new(r"\{[^{}]*\}").unwrap(),
+            Regex::new(r"\bwidth\s*:\s*(\d+)").unwrap(),
+            Regex::new(r"\bheight\s*:\s*(\d+)").unwrap(),
+            Regex::new(r"\bfps\s*:\s*(\d+(?:\.\d+)?)").unwrap(),
+        ]
+    });
+    let number = |re: &Regex, text: &str| re.captures(text).and_then(|c| c[1].parse::<f64>().ok());
+    let mut bytes_per_second = 0.0;
+    let mut cameras = 0;
+    for camera in entry.find_iter(command).map(|m| m.as_str()) {
+        let (Some(width), Some(height)) = (number(width, camera), number(height, camera)) else { continue };
+        bytes_per_second += width * height * 3.0 * number(fps, camera).unwrap_or(DEFAULT_FPS);
+        cameras += 1;
+    }
+    (cameras > 0).then(|| (bytes_per_second / MB, cameras))
+}
+
+/// Measures the sustained write throughput of the volume `dir` is on, in MB/s.
+pub fn benchmark(config: &DiskCheckConfig, dir: &Path) -> Result<f64, String> {
+    let path = dir.join(".teleop-record-replay-disk-check");
+    let result = write_test_file(config, &path);
+    let _ = fs::remove_file(&path);
+    result.map_err(|e| format!("Disk benchmark in '{}' failed: {}", dir.display(), e))
+}
+
+fn write_test_file(config: &DiskCheckConfig, path: &Path) -> std::io::Result<f64> {
+    // Varied data, so that a compressing file system does not skew the result.
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let chunk: Vec<u8> = (0..CHUNK)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect();
+    let limit = Duration::from_secs_f64(config.seconds.max(0.1));
+    let mut file = fs::File::create(path)?;
+    let started = Instant::now();
+    let mut written = 0u64;
+    while written < config.size_mb * MB as u64 && started.elapsed() < limit {
+        file.write_all(&chunk)?;
+        file.sync_data()?;
+        written += CHUNK as u64;
+    }
+    Ok(written as f64 / MB / started.elapsed().as_secs_f64())
+}
+
+/// Runs the benchmark on the volume of `dataset_path` (or its closest existing
+/// parent, as the dataset may not exist yet). Returns a warning if the volume
+/// is too slow for `command` or could not be tested.
+pub fn check(config: &DiskCheckConfig, command: &str, dataset_path: &Path) -> Option<String> {
+    let required = match config.required_mb_s {
+        Some(required) => Some((required, None)),
+        None => required_throughput(command).map(|(required, cameras)| (required, Some(cameras))),
+    };
+    let Some((required, cameras)) = required else {
+        debug!("No camera found in the Record command; skipping the disk benchmark");
+        return None;
+    };
+    let dir = dataset_path.ancestors().find(|p| p.is_dir())?;
+    let warning = match benchmark(config, dir) {
+        Ok(throughput) if throughput < required => {
+            let needed_by = cameras.map(|n| format!(" for {} camera(s)", n)).unwrap_or_default();
+            format!(
+                "The dataset disk writes {:.0} MB/s, less than the {:.0} MB/s needed{}; frames may be dropped.",
+                throughput, required, needed_by
+            )
+        }
+        Ok(throughput) => {
+            debug!("Dataset disk writes {:.0} MB/s, {:.0} MB/s needed", throughput, required);
+            return None;
+        }
+        Err(e) => e,
+    };
+    warn!("{}", warning);
+    Some(warning)
+}
@@ -0,0 +1,174 @@
+use eframe::egui;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::history::{self, History};
+use crate::{command, ProcessType};
+
+/// The `[evaluation]` config table: how to read the outcome of an Evaluate
+/// run from its output.
+#[derive(Deserialize, Clone, Debug)]
+pub struct EvaluationConfig {
+    /// Regex matching successful episodes in the output. Each match counts one
+    /// success, or the number in its `count` group if it has one, e.g. for a
+    /// summary line like "Successes: 7".
+    #[serde(default = "default_success_pattern")]
+    pub success: String,
+    /// Like `success`, for failed episodes.
+    #[serde(default = "default_failure_pattern")]
+    pub failure: String,
+    /// The flag of the Evaluate command naming the policy checkpoint, which
+    /// results are grouped by.
+    #[serde(default = "default_checkpoint_flag")]
+    pub checkpoint_flag: String,
+    /// Regex matching named numbers in the output of Replay and Evaluate runs,
+    /// with `name` and `value` groups, e.g. LeRobot's `'pc_success': 40.0`.
+    /// Compared side by side in A/B comparisons; the last value of each name counts.
+    #[serde(default = "default_metrics_pattern")]
+    pub metrics: String,
+}
+
+fn default_success_pattern() -> String {
+    r"(?i)\bsuccess(?:es)?:\s*(?P<count>\d+)".to_string()
+}
+
+fn default_failure_pattern() -> String {
+    r"(?i)\bfailures?:\s*(?P<count>\d+)".to_string()
+}
+
+fn default_checkpoint_flag() -> String {
+    "--policy.path".to_string()
+}
+
+fn default_metrics_pattern() -> String {
+    r#"['"](?P<name>[A-Za-z_][\w./]*)['"]\s*:\s*(?P<value>-?\d+(?:\.\d+)?(?:[eE][-+]?\d+)?)"#.to_string()
+}
+
+impl Default for EvaluationConfig {
+    fn default() -> Self {
+        Self {
+            success: default_success_pattern(),
+            failure: default_failure_pattern(),
+            checkpoint_flag: default_checkpoint_flag(),
+            metrics: default_metrics_pattern(),
+        }
+    }
+}
+
+/// The outcome of one Evaluate run.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EvalResult {
+    /// The evaluated policy checkpoint, if the command names one.
+    pub checkpoint: Option<String>,
+    pub successes: u32,
+    pub failures: u32,
+}
+
+impl EvalResult {
+    pub fn episodes(&self) -> u32 {
+        self.successes + self.failures
+    }
+
+    /// Successes as a percentage of all episodes.
+    pub fn success_rate(&self) -> f64 {
+        100.0 * self.successes as f64 / self.episodes().max(1) as f64
+    }
+}
+
+/// Adds up the matches of `pattern` in `output`, see [`EvaluationConfig::success`].
+fn count(pattern: &Regex, output: &str) -> u32 {
+    pattern
+        .captures_iter(output)
+        .map(|c| c.name("count").map_or(1, |n| n.as_str().parse().unwrap_or(0)))
+        .sum()
+}
+
+/// Reads the outcome of an Evaluate run of `command` from its `output`.
+/// Returns `None` if no episode outcome was found, e.g. because the run failed
+/// early, so it does not skew the success rates.
+pub fn parse(config: &EvaluationConfig, command: &str, output: &str) -> Result<Option<EvalResult>, String> {
+    let compile = |pattern: &str| Regex::new(pattern).map_err(|e| format!("Invalid evaluation pattern: {}", e));
+    let successes = count(&compile(&config.success)?, output);
+    let failures = count(&compile(&config.failure)?, output);
+    if successes + failures == 0 {
+        return Ok(None);
+    }
+    Ok(Some(EvalResult {
+        checkpoint: command::flag_value(command, &config.checkpoint_flag),
+        successes,
+        failures,
+    }))
+}
+
+/// Reads the metrics of a run from its `output`, see [`EvaluationConfig::metrics`].
+pub fn metrics(config: &EvaluationConfig, output: &str) -> Result<BTreeMap<String, f64>, String> {
+    let pattern = Regex::new(&config.metrics).map_err(|e| format!("Invalid metrics pattern: {}", e))?;
+    Ok(pattern
+        .captures_iter(output)
+        .filter_map(|c| Some((c.name("name")?.as_str().to_string(), c.name("value")?.as_str().parse().ok()?)))
+        .collect())
+}
+
+/// Adds up `results` per checkpoint, along with the number of runs each.
+pub fn aggregate<'a>(results: impl Iterator<Item = &'a EvalResult>) -> BTreeMap<String, (u32, EvalResult)> {
+    let mut by_checkpoint: BTreeMap<String, (u32, EvalResult)> = BTreeMap::new();
+    for result in results {
+        let name = result.checkpoint.clone().unwrap_or_else(|| "(unknown)".to_string());
+        let (runs, total) = by_checkpoint.entry(name).or_insert_with(|| {
+            (0, EvalResult { checkpoint: result.checkpoint.clone(), successes: 0, failures: 0 })
+        });
+        *runs += 1;
+        total.successes += result.successes;
+        total.failures += result.failures;
+    }
+    by_checkpoint
+}
+
+/// Success rates of all recorded Evaluate runs, one row per checkpoint.
+pub fn results_ui(ui: &mut egui::Ui, history: &History) {
+    let by_checkpoint = aggregate(
+        history
+            .runs()
+            .iter()
+            .filter(|r| r.process_type == ProcessType::Evaluate)
+            .filter_map(|r| r.evaluation.as_ref()),
+    );
+    if by_checkpoint.is_empty() {
+        ui.label("Results of Evaluate runs will show up here.");
+        return;
+    }
+    results_table(ui, "evaluation_results_grid", &by_checkpoint);
+}
+
+/// Shows aggregated results as a table, with a button copying it as CSV.
+pub fn results_table(ui: &mut egui::Ui, id: &str, by_checkpoint: &BTreeMap<String, (u32, EvalResult)>) {
+    egui::Grid::new(id).num_columns(5).striped(true).show(ui, |ui| {
+        for header in ["Checkpoint", "Runs", "Episodes", "Successes", "Success rate"] {
+            ui.strong(header);
+        }
+        ui.end_row();
+        for (checkpoint, (runs, total)) in by_checkpoint {
+            ui.label(checkpoint);
+            ui.label(runs.to_string());
+            ui.label(total.episodes().to_string());
+            ui.label(total.successes.to_string());
+            ui.label(format!("{:.1} %", total.success_rate()));
+            ui.end_row();
+        }
+    });
+    if ui.button("Copy as CSV").on_hover_text("Paste into a spreadsheet").clicked() {
+        let mut csv = String::from("checkpoint,runs,episodes,successes,success_rate\n");
+        for (checkpoint, (runs, total)) in by_checkpoint {
+            csv.push_str(&format!(
+                "{},{},{},{},{:.1}\n",
+                history::csv_field(checkpoint),
+                runs,
+                total.episodes(),
+                total.successes,
+                total.success_rate()
+            ));
+        }
+        ui.output_mut(|o| o.copied_text = csv);
+    }
+}
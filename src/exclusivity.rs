@@ -0,0 +1,74 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::ProcessType;
+
+/// The companion processes rules can name besides the process types.
+pub const COMPANIONS: &[&str] = &["tensorboard"];
+
+/// Stands for every process in a rule.
+pub const ANY: &str = "*";
+
+/// The `[exclusivity]` config table: for a process type or companion, the
+/// ones it may not run at the same time as, e.g. `tensorboard = ["record"]`.
+/// Rules work both ways: TensorBoard does not start while Record runs, and
+/// Record does not start while TensorBoard runs.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(transparent)]
+pub struct ExclusivityConfig {
+    pub rules: BTreeMap<String, Vec<String>>,
+}
+
+/// Whether `name` is a process type or companion rules can name.
+pub fn is_known(name: &str) -> bool {
+    ProcessType::from_key(name).is_some() || COMPANIONS.contains(&name)
+}
+
+impl ExclusivityConfig {
+    /// Whether a rule keeps `a` and `b` from running together.
+    fn excludes(&self, a: &str, b: &str) -> bool {
+        let lists = |x: &str, y: &str| self.rules.get(x).is_some_and(|l| l.iter().any(|n| n == y || n == ANY));
+        lists(a, b) || lists(b, a)
+    }
+
+    /// Why `launching` may not start while the processes `running` (process
+    /// type and companion keys, with their labels) run, if it may not.
+    pub fn refusal(&self, launching: (&str, &str), running: &[(&str, &str)]) -> Option<String> {
+        let (key, label) = launching;
+        let (_, blocker) = running.iter().find(|(other, _)| self.excludes(key, other))?;
+        Some(format!(
+            "{} may not run while {} runs, as set in [exclusivity]; stop {} first.",
+            label, blocker, blocker
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(rules: &[(&str, &[&str])]) -> ExclusivityConfig {
+        let rules = rules.iter().map(|(k, v)| (k.to_string(), v.iter().map(|s| s.to_string()).collect())).collect();
+        ExclusivityConfig { rules }
+    }
+
+    #[test]
+    fn rules_work_both_ways() {
+        let config = config(&[("tensorboard", &["record"])]);
+        assert!(config.refusal(("record", "Record"), &[("tensorboard", "TensorBoard")]).is_some());
+        assert!(config.refusal(("tensorboard", "TensorBoard"), &[("record", "Record")]).is_some());
+        assert!(config.refusal(("tensorboard", "TensorBoard"), &[("train", "Train")]).is_none());
+        assert!(config.refusal(("record", "Record"), &[]).is_none());
+    }
+
+    #[test]
+    fn any_excludes_everything() {
+        let config = config(&[("tensorboard", &[ANY])]);
+        let refusal = config.refusal(("train", "Train"), &[("tensorboard", "TensorBoard")]);
+        assert_eq!(
+            refusal.as_deref(),
+            Some("Train may not run while TensorBoard runs, as set in [exclusivity]; stop TensorBoard first.")
+        );
+        assert!(config.refusal(("tensorboard", "TensorBoard"), &[("push", "Push")]).is_some());
+    }
+}
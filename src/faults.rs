@@ -0,0 +1,53 @@
+use regex::Regex;
+use serde::Deserialize;
+
+/// The `[faults]` config table: output of a Record run that means the
+/// hardware failed, upon which the run is stopped and its episode failed.
+#[derive(Deserialize, Clone, Debug)]
+pub struct FaultsConfig {
+    /// Regexes matched against every line of output, e.g. `Torque disabled`.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// The signal the process is stopped with; `INT` is like pressing Ctrl+C
+    /// in its terminal, which LeRobot shuts down cleanly on.
+    #[serde(default = "default_signal")]
+    pub signal: String,
+}
+
+fn default_signal() -> String {
+    "INT".to_string()
+}
+
+impl Default for FaultsConfig {
+    fn default() -> Self {
+        Self { patterns: Vec::new(), signal: default_signal() }
+    }
+}
+
+/// Looks for faults in the output of a running Record process.
+pub struct FaultDetector {
+    patterns: Vec<Regex>,
+}
+
+impl FaultDetector {
+    /// A detector for `config`, or `None` if it has no patterns.
+    pub fn new(config: &FaultsConfig) -> Result<Option<Self>, String> {
+        if config.patterns.is_empty() {
+            return Ok(None);
+        }
+        let patterns = config
+            .patterns
+            .iter()
+            .map(|p| Regex::new(p).map_err(|e| format!("Invalid fault pattern: {}", e)))
+            .collect::<Result<_, _>>()?;
+        Ok(Some(Self { patterns }))
+    }
+
+    /// The first of `lines` reporting a fault, if any.
+    pub fn find<'a>(&self, lines: &'a [String]) -> Option<&'a str> {
+        lines
+            .iter()
+            .find(|line| self.patterns.iter().any(|p| p.is_match(line)))
+            .map(|line| line.trim())
+    }
+}
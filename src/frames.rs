@@ -0,0 +1,134 @@
+use eframe::egui;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::command;
+
+/// The `[frames]` config table: how the frame rate and dropped frames are read
+/// from the output of Record runs.
+#[derive(Deserialize, Clone, Debug)]
+pub struct FramesConfig {
+    /// Regex matching the measured frame rate in the output, with an `fps`
+    /// group, e.g. LeRobot's `dt: 33.41 (29.9hz)` or "running slower (24.2 Hz)".
+    #[serde(default = "default_fps_pattern")]
+    pub fps: String,
+    /// Regex matching dropped frames. Each match counts one dropped frame, or
+    /// the number in its `count` group if it has one.
+    #[serde(default = "default_dropped_pattern")]
+    pub dropped: String,
+    /// The flag of the Record command holding the frame rate aimed for.
+    #[serde(default = "default_target_fps_flag")]
+    pub target_fps_flag: String,
+}
+
+fn default_fps_pattern() -> String {
+    r"(?i)(?:\(|slower \()(?P<fps>\d+(?:\.\d+)?)\s*hz\)".to_string()
+}
+
+fn default_dropped_pattern() -> String {
+    r"(?i)\bdropped (?P<count>\d+) frames?|\bframes? (?:might be |were |was )?dropped|timed out waiting for (?:a )?frame"
+        .to_string()
+}
+
+fn default_target_fps_flag() -> String {
+    "--dataset.fps".to_string()
+}
+
+impl Default for FramesConfig {
+    fn default() -> Self {
+        Self {
+            fps: default_fps_pattern(),
+            dropped: default_dropped_pattern(),
+            target_fps_flag: default_target_fps_flag(),
+        }
+    }
+}
+
+/// The frame rate LeRobot records at unless told otherwise.
+const DEFAULT_TARGET_FPS: f64 = 30.0;
+
+/// How well the frames of a Record run (one episode in a session) were captured.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    pub dropped: u32,
+    /// Average of the frame rates reported, if any were.
+    pub mean_fps: Option<f64>,
+    /// Lowest frame rate reported.
+    pub min_fps: Option<f64>,
+}
+
+/// Follows the output of a running Record process.
+pub struct FrameMonitor {
+    fps_pattern: Regex,
+    dropped_pattern: Regex,
+    pub target_fps: f64,
+    /// The frame rate reported last.
+    pub fps: Option<f64>,
+    pub stats: FrameStats,
+    samples: u32,
+}
+
+impl FrameMonitor {
+    /// A monitor for a Record run of `command`.
+    pub fn new(config: &FramesConfig, command: &str) -> Result<Self, String> {
+        let compile = |pattern: &str| Regex::new(pattern).map_err(|e| format!("Invalid frames pattern: {}", e));
+        Ok(Self {
+            fps_pattern: compile(&config.fps)?,
+            dropped_pattern: compile(&config.dropped)?,
+            target_fps: command::flag_value(command, &config.target_fps_flag)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_TARGET_FPS),
+            fps: None,
+            stats: FrameStats::default(),
+            samples: 0,
+        })
+    }
+
+    /// Takes in a line of output.
+    pub fn feed(&mut self, line: &str) {
+        for captures in self.dropped_pattern.captures_iter(line) {
+            self.stats.dropped += captures.name("count").map_or(1, |n| n.as_str().parse().unwrap_or(0));
+        }
+        let Some(fps) = self.fps_pattern.captures(line).and_then(|c| c.name("fps")?.as_str().parse::<f64>().ok())
+        else {
+            return;
+        };
+        self.fps = Some(fps);
+        self.samples += 1;
+        let mean = self.stats.mean_fps.unwrap_or(0.0);
+        self.stats.mean_fps = Some(mean + (fps - mean) / self.samples as f64);
+        self.stats.min_fps = Some(self.stats.min_fps.map_or(fps, |min| min.min(fps)));
+    }
+
+    /// The live gauge: the frame rate against the target and the frames dropped so far.
+    pub fn ui(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let Some(fps) = self.fps else {
+                ui.weak("Waiting for the frame rate...");
+                return;
+            };
+            let fraction = (fps / self.target_fps).min(1.0) as f32;
+            let color = if fraction < 0.9 {
+                egui::Color32::from_rgb(200, 60, 40)
+            } else {
+                egui::Color32::from_rgb(40, 150, 70)
+            };
+            ui.add(
+                egui::ProgressBar::new(fraction)
+                    .desired_width(200.0)
+                    .fill(color)
+                    .text(format!("{:.1} / {:.0} fps", fps, self.target_fps)),
+            );
+        });
+        describe_drops(ui, self.stats.dropped);
+    }
+}
+
+/// "N frames dropped", in yellow unless there are none.
+pub fn describe_drops(ui: &mut egui::Ui, dropped: u32) {
+    if dropped == 0 {
+        ui.weak("No frames dropped");
+    } else {
+        ui.colored_label(egui::Color32::YELLOW, format!("{} frame(s) dropped", dropped));
+    }
+}
@@ -0,0 +1,134 @@
+use eframe::egui;
+use serde::Deserialize;
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use crate::sandbox;
+
+/// The `[git]` config table: before recording, the working directory is
+/// checked to be a clean checkout of the expected branch, so it is known
+/// which code recorded a dataset.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct GitConfig {
+    /// The branch recordings are expected to be made from, if any.
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Refuse to record instead of asking whether to record anyway.
+    #[serde(default)]
+    pub block: bool,
+}
+
+/// The checkout of a working directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitStatus {
+    /// `None` for a detached HEAD.
+    pub branch: Option<String>,
+    pub commit: String,
+    /// Whether tracked files have uncommitted changes.
+    pub dirty: bool,
+}
+
+impl GitStatus {
+    /// E.g. `main @ 3a315a5, modified`.
+    pub fn label(&self) -> String {
+        format!(
+            "{} @ {}{}",
+            self.branch.as_deref().unwrap_or("detached HEAD"),
+            self.commit.get(..7).unwrap_or(&self.commit),
+            if self.dirty { ", modified" } else { "" }
+        )
+    }
+
+    /// What is wrong with recording from this checkout.
+    pub fn problems(&self, config: &GitConfig) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.dirty {
+            problems.push("The working directory has uncommitted changes.".to_string());
+        }
+        if let Some(expected) = config.branch.as_deref().filter(|b| !b.is_empty()) {
+            if self.branch.as_deref() != Some(expected) {
+                let branch = self.branch.as_deref().unwrap_or("a detached HEAD");
+                problems.push(format!("The working directory is on {}, not on {}.", branch, expected));
+            }
+        }
+        problems
+    }
+}
+
+/// Parses `git status --porcelain=v2 --branch`.
+fn parse(output: &str) -> Option<GitStatus> {
+    let mut commit = None;
+    let mut branch = None;
+    let mut dirty = false;
+    for line in output.lines() {
+        if let Some(oid) = line.strip_prefix("# branch.oid ") {
+            commit = Some(oid.to_string());
+        } else if let Some(head) = line.strip_prefix("# branch.head ") {
+            branch = Some(head.to_string()).filter(|h| h != "(detached)");
+        } else if !line.starts_with('#') && !line.is_empty() {
+            dirty = true;
+        }
+    }
+    // A repository without commits has no commit to record from.
+    let commit = commit.filter(|c| c != "(initial)")?;
+    Some(GitStatus { branch, commit, dirty })
+}
+
+/// The checkout of `dir` (the current directory if empty).
+pub fn status(dir: &str) -> Result<GitStatus, String> {
+    let mut git = Command::new("git");
+    if !dir.is_empty() {
+        git.arg("-C").arg(dir);
+    }
+    git.args(["status", "--porcelain=v2", "--branch", "--untracked-files=no"]);
+    let output = sandbox::on_host(git).output().map_err(|e| format!("Failed to run git: {}", e))?;
+    let shown = if dir.is_empty() { "The working directory" } else { dir };
+    if !output.status.success() {
+        return Err(format!("{} is not a git repository.", shown));
+    }
+    parse(&String::from_utf8_lossy(&output.stdout)).ok_or_else(|| format!("{} has no commits yet.", shown))
+}
+
+/// How often the status shown in the GUI is refreshed.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The checkout of a directory, refreshed in the background for display.
+#[derive(Default)]
+pub struct StatusWatch {
+    dir: String,
+    checked_at: Option<Instant>,
+    running: Option<Receiver<Result<GitStatus, String>>>,
+    pub last: Option<Result<GitStatus, String>>,
+}
+
+impl StatusWatch {
+    /// Picks up the latest status of `dir` and starts refreshing it when due.
+    pub fn poll(&mut self, dir: &str, ctx: &egui::Context) {
+        if dir != self.dir {
+            *self = Self { dir: dir.to_string(), ..Self::default() };
+        }
+        if let Some(receiver) = &self.running {
+            match receiver.try_recv() {
+                Ok(status) => {
+                    self.last = Some(status);
+                    self.running = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => self.running = None,
+            }
+        }
+        if self.checked_at.is_none_or(|at| at.elapsed() >= REFRESH_INTERVAL) {
+            let (sender, receiver) = mpsc::channel();
+            let dir = dir.to_string();
+            let ctx = ctx.clone();
+            std::thread::spawn(move || {
+                let _ = sender.send(status(&dir));
+                ctx.request_repaint();
+            });
+            self.running = Some(receiver);
+            self.checked_at = Some(Instant::now());
+        }
+        ctx.request_repaint_after(REFRESH_INTERVAL);
+    }
+}
@@ -0,0 +1,415 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::disk::{self, DiskCheckConfig};
+use crate::preview::{self, Camera};
+use crate::{command, sandbox};
+
+/// The `[hardware_check]` config table: what the hardware check measures the
+/// setup of the Record command against.
+#[derive(Deserialize, Clone, Debug)]
+pub struct HardwareCheckConfig {
+    /// How long each camera is captured from, in seconds.
+    #[serde(default = "default_seconds")]
+    pub seconds: f64,
+    /// Share of its configured frame rate a camera must deliver.
+    #[serde(default = "default_min_fps_share")]
+    pub min_fps_share: f64,
+    /// Share of the bandwidth of a USB bus the cameras on it may need.
+    #[serde(default = "default_max_usb_load")]
+    pub max_usb_load: f64,
+    /// Slowest median answer of an arm to a ping, in milliseconds.
+    #[serde(default = "default_max_latency_ms")]
+    pub max_latency_ms: f64,
+    /// Pings sent to each arm.
+    #[serde(default = "default_pings")]
+    pub pings: u32,
+    /// Runs the check when a recording session starts, which then only
+    /// begins if it passes.
+    #[serde(default)]
+    pub before_session: bool,
+}
+
+fn default_seconds() -> f64 {
+    3.0
+}
+
+fn default_min_fps_share() -> f64 {
+    0.9
+}
+
+fn default_max_usb_load() -> f64 {
+    0.8
+}
+
+fn default_max_latency_ms() -> f64 {
+    5.0
+}
+
+fn default_pings() -> u32 {
+    20
+}
+
+impl Default for HardwareCheckConfig {
+    fn default() -> Self {
+        Self {
+            seconds: default_seconds(),
+            min_fps_share: default_min_fps_share(),
+            max_usb_load: default_max_usb_load(),
+            max_latency_ms: default_max_latency_ms(),
+            pings: default_pings(),
+            before_session: false,
+        }
+    }
+}
+
+/// The result of one measurement.
+#[derive(Clone, Debug)]
+pub struct Measurement {
+    /// What was measured, e.g. `Camera 'wrist'`.
+    pub name: String,
+    /// Whether it meets the requirement; `None` if it could not be measured
+    /// or there is nothing to compare it with.
+    pub passed: Option<bool>,
+    pub detail: String,
+}
+
+impl Measurement {
+    fn new(name: String, passed: Option<bool>, detail: String) -> Self {
+        Self { name, passed, detail }
+    }
+}
+
+/// The serial protocols of the motors of the LeRobot arms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MotorBus {
+    /// Feetech STS/SCS servos, e.g. of the SO-100 and SO-101.
+    Feetech,
+    /// Dynamixel servos (protocol 2.0), e.g. of the Koch arms.
+    Dynamixel,
+}
+
+/// Both run at 1 Mbit/s in LeRobot.
+const BAUD_RATE: u32 = 1_000_000;
+/// The motor pinged; LeRobot numbers the motors of an arm from 1.
+const MOTOR_ID: u8 = 1;
+/// Longest wait for the answer to a ping.
+const PING_TIMEOUT: Duration = Duration::from_millis(100);
+
+impl MotorBus {
+    /// The bus of a robot or teleoperator of LeRobot type `kind`, e.g.
+    /// `so101_follower`.
+    fn of(kind: &str) -> Option<Self> {
+        if ["so100", "so101", "lekiwi"].iter().any(|k| kind.contains(k)) {
+            Some(MotorBus::Feetech)
+        } else if kind.contains("koch") {
+            Some(MotorBus::Dynamixel)
+        } else {
+            None
+        }
+    }
+
+    /// A ping of motor `id`, with the length of the status packet answering it.
+    fn ping(self, id: u8) -> (Vec<u8>, usize) {
+        match self {
+            MotorBus::Feetech => {
+                let checksum = !(id.wrapping_add(2).wrapping_add(1));
+                (vec![0xFF, 0xFF, id, 2, 1, checksum], 6)
+            }
+            MotorBus::Dynamixel => {
+                let mut packet = vec![0xFF, 0xFF, 0xFD, 0x00, id, 3, 0, 1];
+                let crc = dynamixel_crc(&packet);
+                packet.extend(crc.to_le_bytes());
+                (packet, 14)
+            }
+        }
+    }
+}
+
+/// The CRC-16 of Dynamixel protocol 2.0 packets.
+fn dynamixel_crc(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// The serial ports in `command` with the bus of the arm on each, e.g.
+/// `--robot.port` with the bus of `--robot.type`.
+fn arms(command: &str) -> Vec<(String, String, Option<MotorBus>)> {
+    ["robot", "teleop"]
+        .iter()
+        .filter_map(|device| {
+            let port = command::flag_value(command, &format!("--{}.port", device)).filter(|p| !p.is_empty())?;
+            let kind = command::flag_value(command, &format!("--{}.type", device)).unwrap_or_default();
+            let bus = MotorBus::of(&kind);
+            Some((port, kind, bus))
+        })
+        .collect()
+}
+
+/// Captures from `camera` with ffmpeg for `seconds`, passing the frames on
+/// without decoding them, and compares the frame rate with the configured one.
+fn camera_rate(config: &HardwareCheckConfig, camera: &Camera, device: &str) -> Measurement {
+    static FRAME: OnceLock<Regex> = OnceLock::new();
+    let frame = FRAME.get_or_init(|| Regex::new(r"frame=\s*(\d+)").unwrap());
+    let name = format!("Camera '{}'", camera.name);
+    let mut ffmpeg = Command::new("ffmpeg");
+    ffmpeg.args(["-hide_banner", "-loglevel", "error", "-stats", "-f", "v4l2"]);
+    if let (Some(width), Some(height)) = (camera.width, camera.height) {
+        ffmpeg.arg("-video_size").arg(format!("{}x{}", width, height));
+    }
+    if let Some(fps) = camera.fps {
+        ffmpeg.arg("-framerate").arg(fps.to_string());
+    }
+    ffmpeg.arg("-i").arg(device).args(["-c:v", "copy", "-t"]).arg(config.seconds.to_string());
+    ffmpeg.args(["-f", "null", "-"]);
+    let output = match sandbox::on_host(ffmpeg).stdin(Stdio::null()).output() {
+        Ok(output) => output,
+        Err(e) => return Measurement::new(name, Some(false), format!("Failed to run ffmpeg: {}", e)),
+    };
+    // The statistics are rewritten in place with carriage returns.
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let frames = frame.captures_iter(&stderr).last().and_then(|c| c[1].parse::<u32>().ok());
+    let Some(frames) = frames.filter(|_| output.status.success()) else {
+        let reason = stderr.split(['\r', '\n']).rev().map(str::trim).find(|l| !l.is_empty());
+        return Measurement::new(name, Some(false), reason.unwrap_or("No frames captured").to_string());
+    };
+    let fps = frames as f64 / config.seconds;
+    match camera.fps {
+        Some(wanted) => {
+            let passed = fps >= wanted as f64 * config.min_fps_share;
+            Measurement::new(name, Some(passed), format!("{:.1} fps of {} on {}", fps, wanted, device))
+        }
+        None => Measurement::new(name, None, format!("{:.1} fps on {}; no fps configured", fps, device)),
+    }
+}
+
+/// The USB bus number of the V4L2 `device`, e.g. `/dev/video0`, from sysfs.
+fn usb_bus(device: &str) -> Option<u32> {
+    let video = fs::canonicalize(device).ok()?;
+    let sysfs = Path::new("/sys/class/video4linux").join(video.file_name()?).join("device");
+    // The device of a video node is a USB interface; its parent is the camera.
+    let interface = fs::canonicalize(sysfs).ok()?;
+    fs::read_to_string(interface.parent()?.join("busnum")).ok()?.trim().parse().ok()
+}
+
+/// The speed of USB bus `bus`, in Mbit/s.
+fn bus_speed(bus: u32) -> Option<f64> {
+    fs::read_to_string(format!("/sys/bus/usb/devices/usb{}/speed", bus)).ok()?.trim().parse().ok()
+}
+
+/// The bandwidth the cameras need on each USB bus they share, against the
+/// speed of the bus. Frames are counted as uncompressed YUYV, 16 bits a
+/// pixel, which is what most webcams send unless asked for MJPEG.
+fn usb_load(config: &HardwareCheckConfig, cameras: &[(Camera, String)]) -> Vec<Measurement> {
+    let mut buses: BTreeMap<u32, Vec<&Camera>> = BTreeMap::new();
+    let mut measurements = Vec::new();
+    for (camera, device) in cameras {
+        match usb_bus(device) {
+            Some(bus) => buses.entry(bus).or_default().push(camera),
+            None => measurements.push(Measurement::new(
+                format!("USB of camera '{}'", camera.name),
+                None,
+                format!("{} is not a USB device", device),
+            )),
+        }
+    }
+    for (bus, cameras) in buses {
+        let name = format!("USB bus {}", bus);
+        let Some(speed) = bus_speed(bus) else {
+            measurements.push(Measurement::new(name, None, "Unknown bus speed".to_string()));
+            continue;
+        };
+        let needed: f64 = cameras
+            .iter()
+            .map(|c| {
+                let pixels = c.width.unwrap_or(640) as f64 * c.height.unwrap_or(480) as f64;
+                pixels * 16.0 * c.fps.unwrap_or(30) as f64 / 1_000_000.0
+            })
+            .sum();
+        let load = needed / speed;
+        let names: Vec<&str> = cameras.iter().map(|c| c.name.as_str()).collect();
+        let detail = format!(
+            "{} need {:.0} of {:.0} Mbit/s uncompressed ({:.0}%)",
+            names.join(", "),
+            needed,
+            speed,
+            load * 100.0
+        );
+        measurements.push(Measurement::new(name, Some(load <= config.max_usb_load), detail));
+    }
+    measurements
+}
+
+/// Pings motor [`MOTOR_ID`] on `port` and times the answers.
+fn serial_latency(config: &HardwareCheckConfig, port: &str, kind: &str, bus: Option<MotorBus>) -> Measurement {
+    let name = format!("Arm on {}", port);
+    let Some(bus) = bus else {
+        let kind = if kind.is_empty() { "an unknown type" } else { kind };
+        return Measurement::new(name, None, format!("Cannot ping the motors of {}", kind));
+    };
+    match ping_times(config, port, bus) {
+        Ok(mut times) if !times.is_empty() => {
+            times.sort_by(f64::total_cmp);
+            let median = times[times.len() / 2];
+            let detail = format!(
+                "{:.1} ms median, {:.1} ms worst round trip ({} of {} answered)",
+                median,
+                times[times.len() - 1],
+                times.len(),
+                config.pings.max(1)
+            );
+            let passed = median <= config.max_latency_ms && times.len() == config.pings.max(1) as usize;
+            Measurement::new(name, Some(passed), detail)
+        }
+        Ok(_) => Measurement::new(name, Some(false), format!("Motor {} does not answer", MOTOR_ID)),
+        Err(e) => Measurement::new(name, Some(false), e),
+    }
+}
+
+/// The round trips of the pings answered, in milliseconds.
+fn ping_times(config: &HardwareCheckConfig, port: &str, bus: MotorBus) -> Result<Vec<f64>, String> {
+    // Raw mode, with reads giving up after a tenth of a second without data.
+    let mut stty = Command::new("stty");
+    stty.arg("-F").arg(port).arg(BAUD_RATE.to_string()).args(["raw", "-echo", "min", "0", "time", "1"]);
+    let output = sandbox::on_host(stty).output().map_err(|e| format!("Failed to run stty: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let mut serial = OpenOptions::new().read(true).write(true).open(port).map_err(|e| format!("{}: {}", port, e))?;
+    let (ping, answer_length) = bus.ping(MOTOR_ID);
+    let mut times = Vec::new();
+    let mut buffer = [0u8; 64];
+    for _ in 0..config.pings.max(1) {
+        let started = Instant::now();
+        serial.write_all(&ping).map_err(|e| format!("{}: {}", port, e))?;
+        let mut received = 0;
+        while received < answer_length && started.elapsed() < PING_TIMEOUT {
+            received += serial.read(&mut buffer).map_err(|e| format!("{}: {}", port, e))?;
+        }
+        if received >= answer_length {
+            times.push(started.elapsed().as_secs_f64() * 1000.0);
+        }
+    }
+    Ok(times)
+}
+
+/// The write speed of the dataset disk against what the cameras need.
+fn disk_speed(config: &DiskCheckConfig, command: &str, dir: &Path) -> Measurement {
+    let name = "Dataset disk".to_string();
+    let required = config.required_mb_s.or_else(|| disk::required_throughput(command).map(|(r, _)| r));
+    match (disk::benchmark(config, dir), required) {
+        (Ok(speed), Some(required)) => {
+            let detail = format!("{:.0} MB/s written, {:.0} MB/s needed", speed, required);
+            Measurement::new(name, Some(speed >= required), detail)
+        }
+        (Ok(speed), None) => Measurement::new(name, None, format!("{:.0} MB/s written; no camera sizes", speed)),
+        (Err(e), _) => Measurement::new(name, Some(false), e),
+    }
+}
+
+fn run(
+    config: HardwareCheckConfig,
+    disk_config: DiskCheckConfig,
+    command: String,
+    dataset_dir: Option<PathBuf>,
+    sender: Sender<Measurement>,
+) {
+    let cameras: Vec<(Camera, String)> = preview::cameras(&command)
+        .into_iter()
+        .filter_map(|c| {
+            let device = c.device.clone()?;
+            Some((c, device))
+        })
+        .collect();
+    for (camera, device) in &cameras {
+        let _ = sender.send(camera_rate(&config, camera, device));
+    }
+    for measurement in usb_load(&config, &cameras) {
+        let _ = sender.send(measurement);
+    }
+    for (port, kind, bus) in arms(&command) {
+        let _ = sender.send(serial_latency(&config, &port, &kind, bus));
+    }
+    if let Some(dir) = dataset_dir {
+        let _ = sender.send(disk_speed(&disk_config, &command, &dir));
+    }
+}
+
+/// A hardware check running in the background, and its measurements so far.
+pub struct HardwareCheck {
+    running: Option<Receiver<Measurement>>,
+    pub measurements: Vec<Measurement>,
+    /// Whether a recording session begins once the check passes.
+    pub before_session: bool,
+}
+
+impl HardwareCheck {
+    /// Measures the cameras, USB buses and arms of the Record `command`, and
+    /// the disk `dataset_dir` is on, if given. The cameras and ports must not
+    /// be in use.
+    pub fn start(
+        config: &HardwareCheckConfig,
+        disk_config: DiskCheckConfig,
+        command: String,
+        dataset_dir: Option<PathBuf>,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let config = config.clone();
+        std::thread::spawn(move || run(config, disk_config, command, dataset_dir, sender));
+        Self { running: Some(receiver), measurements: Vec::new(), before_session: false }
+    }
+
+    /// Whether the check is still running; picks up its measurements.
+    pub fn running(&mut self) -> bool {
+        let Some(receiver) = &self.running else { return false };
+        loop {
+            match receiver.try_recv() {
+                Ok(measurement) => {
+                    match measurement.passed {
+                        Some(false) => warn!("Hardware check: {}: {}", measurement.name, measurement.detail),
+                        _ => info!("Hardware check: {}: {}", measurement.name, measurement.detail),
+                    }
+                    self.measurements.push(measurement);
+                }
+                Err(mpsc::TryRecvError::Empty) => return true,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.running = None;
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// Whether nothing measured fails its requirement.
+    pub fn passed(&self) -> bool {
+        self.measurements.iter().all(|m| m.passed != Some(false))
+    }
+
+    /// E.g. `2 of 7 checks failed: Camera 'wrist', USB bus 3`.
+    pub fn summary(&self) -> String {
+        let failed: Vec<&str> =
+            self.measurements.iter().filter(|m| m.passed == Some(false)).map(|m| m.name.as_str()).collect();
+        if self.measurements.is_empty() {
+            "Nothing to check: the Record command has no cameras or ports.".to_string()
+        } else if failed.is_empty() {
+            format!("All {} checks passed.", self.measurements.len())
+        } else {
+            format!("{} of {} checks failed: {}", failed.len(), self.measurements.len(), failed.join(", "))
+        }
+    }
+}
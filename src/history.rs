@@ -0,0 +1,556 @@
+use chrono::{DateTime, Local, NaiveDate};
+use tracing::{debug, error};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::evaluation::EvalResult;
+use crate::frames::FrameStats;
+use crate::snapshot::Snapshot;
+use crate::trackers::TrackerLink;
+use crate::ProcessType;
+
+/// A single launched process, as stored in the run history.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RunRecord {
+    pub process_type: ProcessType,
+    /// Who launched the run.
+    #[serde(default)]
+    pub user: String,
+    /// The operator selected in the GUI, if any.
+    #[serde(default)]
+    pub operator: Option<String>,
+    /// The command as it was handed to the terminal.
+    pub command: String,
+    pub started_at: DateTime<Local>,
+    /// `None` while the process is still running (or if the app crashed while it was).
+    #[serde(default)]
+    pub finished_at: Option<DateTime<Local>>,
+    /// Exit code of the process, if it exited normally.
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// Whether the process was stopped from the GUI.
+    #[serde(default)]
+    pub stopped: bool,
+    /// Whether the command was edited in the GUI for this run only.
+    #[serde(default)]
+    pub edited: bool,
+    /// The file the output of the run was captured to.
+    #[serde(default)]
+    pub log_file: Option<PathBuf>,
+    /// Experiment tracker dashboards found in the output.
+    #[serde(default)]
+    pub links: Vec<TrackerLink>,
+    /// For Evaluate runs: the outcome read from the output.
+    #[serde(default)]
+    pub evaluation: Option<EvalResult>,
+    /// For Replay and Evaluate runs: named numbers read from the output.
+    #[serde(default)]
+    pub metrics: BTreeMap<String, f64>,
+    /// For Record runs: the frame rate and dropped frames read from the output.
+    #[serde(default)]
+    pub frames: Option<FrameStats>,
+    /// For Record runs stopped after a fault: the line of output reporting it.
+    #[serde(default)]
+    pub fault: Option<String>,
+    /// The core dump kept after the command crashed.
+    #[serde(default)]
+    pub core_dump: Option<PathBuf>,
+    /// The commit and packages the run was launched with.
+    #[serde(default)]
+    pub snapshot: Option<Snapshot>,
+}
+
+impl RunRecord {
+    /// How the run ended, e.g. `ok` or `exit code 1`, with the frames it
+    /// dropped.
+    pub fn outcome(&self) -> String {
+        let outcome = match (self.finished_at, self.exit_code, self.stopped) {
+            _ if self.fault.is_some() => "fault".to_string(),
+            _ if self.core_dump.is_some() => "crashed".to_string(),
+            (None, _, _) => "running or interrupted".to_string(),
+            (_, _, true) => "stopped".to_string(),
+            (_, Some(0), _) => "ok".to_string(),
+            (_, Some(code), _) => format!("exit code {}", code),
+            (_, None, _) => "killed".to_string(),
+        };
+        match &self.frames {
+            Some(frames) if frames.dropped > 0 => format!("{}, {} frame(s) dropped", outcome, frames.dropped),
+            _ => outcome,
+        }
+    }
+}
+
+/// Output formats supported by the history export.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    /// The file extension conventionally used for this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Restricts which runs are included in an export. Dates are inclusive and
+/// compared against the local start date of each run.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+    pub process_type: Option<ProcessType>,
+}
+
+impl HistoryFilter {
+    fn matches(&self, run: &RunRecord) -> bool {
+        let date = run.started_at.date_naive();
+        self.since.is_none_or(|since| date >= since)
+            && self.until.is_none_or(|until| date <= until)
+            && self.process_type.is_none_or(|t| t == run.process_type)
+    }
+}
+
+/// The persistent store of every process launched from the app.
+pub struct History {
+    path: PathBuf,
+    runs: Vec<RunRecord>,
+}
+
+impl History {
+    /// Default location of the history file.
+    pub fn default_path() -> PathBuf {
+        crate::paths::data_dir().join("history.json")
+    }
+
+    /// Loads the history from `path`. A missing file yields an empty history;
+    /// a corrupt one is logged and also treated as empty so the GUI still starts.
+    pub fn load(path: PathBuf) -> Self {
+        let runs = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                error!("Failed to parse run history '{}': {}", path.display(), e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        debug!("Loaded {} run(s) from '{}'", runs.len(), path.display());
+        Self { path, runs }
+    }
+
+    pub fn runs(&self) -> &[RunRecord] {
+        &self.runs
+    }
+
+    /// Appends a newly started run and returns its index for later updates.
+    pub fn start(
+        &mut self,
+        process_type: ProcessType,
+        user: String,
+        operator: Option<String>,
+        command: String,
+        edited: bool,
+        log_file: Option<PathBuf>,
+    ) -> usize {
+        self.runs.push(RunRecord {
+            process_type,
+            user,
+            operator,
+            command,
+            started_at: Local::now(),
+            finished_at: None,
+            exit_code: None,
+            stopped: false,
+            edited,
+            log_file,
+            links: Vec::new(),
+            evaluation: None,
+            metrics: BTreeMap::new(),
+            frames: None,
+            fault: None,
+            core_dump: None,
+            snapshot: None,
+        });
+        self.save();
+        self.runs.len() - 1
+    }
+
+    /// Marks the run at `index` as finished.
+    pub fn finish(&mut self, index: usize, exit_code: Option<i32>, stopped: bool) {
+        if let Some(run) = self.runs.get_mut(index) {
+            run.finished_at = Some(Local::now());
+            run.exit_code = exit_code;
+            run.stopped = stopped;
+            self.save();
+        }
+    }
+
+    /// Adds the tracker links not yet known to the run at `index`.
+    pub fn add_links(&mut self, index: usize, links: Vec<TrackerLink>) {
+        let Some(run) = self.runs.get_mut(index) else { return };
+        let before = run.links.len();
+        for link in links {
+            if !run.links.contains(&link) {
+                run.links.push(link);
+            }
+        }
+        if run.links.len() > before {
+            self.save();
+        }
+    }
+
+    /// Stores what was read from the output of the run at `index`.
+    pub fn set_results(&mut self, index: usize, evaluation: Option<EvalResult>, metrics: BTreeMap<String, f64>) {
+        if let Some(run) = self.runs.get_mut(index) {
+            run.evaluation = evaluation;
+            run.metrics = metrics;
+            self.save();
+        }
+    }
+
+    /// Adds `metrics` to those of the run at `index`, replacing any of the same name.
+    pub fn add_metrics(&mut self, index: usize, metrics: BTreeMap<String, f64>) {
+        if let Some(run) = self.runs.get_mut(index) {
+            run.metrics.extend(metrics);
+            self.save();
+        }
+    }
+
+    /// Stores the frame statistics of the Record run at `index`.
+    pub fn set_frames(&mut self, index: usize, frames: FrameStats) {
+        if let Some(run) = self.runs.get_mut(index) {
+            run.frames = Some(frames);
+            self.save();
+        }
+    }
+
+    /// Marks the run at `index` as stopped after `fault`.
+    pub fn set_fault(&mut self, index: usize, fault: String) {
+        if let Some(run) = self.runs.get_mut(index) {
+            run.fault = Some(fault);
+            self.save();
+        }
+    }
+
+    /// Links the core dump kept for the crashed run at `index`.
+    pub fn set_core_dump(&mut self, index: usize, path: PathBuf) {
+        if let Some(run) = self.runs.get_mut(index) {
+            run.core_dump = Some(path);
+            self.save();
+        }
+    }
+
+    /// Stores the snapshot taken when the run at `index` was launched.
+    pub fn set_snapshot(&mut self, index: usize, snapshot: Snapshot) {
+        if let Some(run) = self.runs.get_mut(index) {
+            run.snapshot = Some(snapshot);
+            self.save();
+        }
+    }
+
+    /// Writes the history back to disk. Failures are only logged: losing a
+    /// history entry must never prevent a process from being launched.
+    fn save(&self) {
+        if let Some(parent) = self.path.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                error!("Failed to create '{}': {}", parent.display(), e);
+                return;
+            }
+        }
+        let result = serde_json::to_string_pretty(&self.runs)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(&self.path, json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            error!("Failed to save run history '{}': {}", self.path.display(), e);
+        }
+    }
+
+    /// Renders the runs matching `filter` in the requested format.
+    pub fn export(&self, filter: &HistoryFilter, format: ExportFormat) -> Result<String, String> {
+        let runs: Vec<&RunRecord> = self.runs.iter().filter(|r| filter.matches(r)).collect();
+        match format {
+            ExportFormat::Json => serde_json::to_string_pretty(&runs)
+                .map_err(|e| format!("Failed to serialize run history: {}", e)),
+            ExportFormat::Csv => Ok(to_csv(&runs)),
+        }
+    }
+
+    /// Exports the runs matching `filter` to `path` and returns how many were written.
+    pub fn export_to_file(
+        &self,
+        filter: &HistoryFilter,
+        format: ExportFormat,
+        path: &Path,
+    ) -> Result<usize, String> {
+        let contents = self.export(filter, format)?;
+        fs::write(path, contents)
+            .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+        Ok(self.runs.iter().filter(|r| filter.matches(r)).count())
+    }
+}
+
+fn to_csv(runs: &[&RunRecord]) -> String {
+    let mut out = String::from("process_type,user,operator,started_at,finished_at,duration_s,exit_code,stopped,edited,links,dropped_frames,mean_fps,git_commit,command\n");
+    for run in runs {
+        let duration = run
+            .finished_at
+            .map(|end| (end - run.started_at).num_milliseconds() as f64 / 1000.0)
+            .map(|d| d.to_string())
+            .unwrap_or_default();
+        let fields = [
+            format!("{:?}", run.process_type),
+            run.user.clone(),
+            run.operator.clone().unwrap_or_default(),
+            run.started_at.to_rfc3339(),
+            run.finished_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            duration,
+            run.exit_code.map(|c| c.to_string()).unwrap_or_default(),
+            run.stopped.to_string(),
+            run.edited.to_string(),
+            run.links.iter().map(|l| l.url.as_str()).collect::<Vec<_>>().join(" "),
+            run.frames.as_ref().map(|f| f.dropped.to_string()).unwrap_or_default(),
+            run.frames.as_ref().and_then(|f| f.mean_fps).map(|f| format!("{:.2}", f)).unwrap_or_default(),
+            run.snapshot.as_ref().and_then(|s| s.describe()).unwrap_or_default(),
+            run.command.clone(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&line.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Quotes a CSV field if it contains a separator, quote or newline (RFC 4180).
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Shows the last `count` runs, newest first, with their tracker links.
+pub fn recent_runs_ui(ui: &mut eframe::egui::Ui, history: &History, count: usize) {
+    use eframe::egui;
+
+    egui::Grid::new("recent_runs_grid").num_columns(3).striped(true).show(ui, |ui| {
+        for run in history.runs().iter().rev().take(count) {
+            let hover = match run.snapshot.as_ref().and_then(|s| s.describe()) {
+                Some(commit) => format!("{}\n\nCommit {}", run.command, commit),
+                None => run.command.clone(),
+            };
+            ui.label(run.started_at.format("%Y-%m-%d %H:%M").to_string()).on_hover_text(hover);
+            ui.label(format!("{:?} ({})", run.process_type, run.outcome()));
+            ui.horizontal(|ui| {
+                links_ui(ui, &run.links);
+                if let Some(core_dump) = &run.core_dump {
+                    let hover = format!("{}\nInspect it with e.g. `gdb <program> <core>`", core_dump.display());
+                    if ui.button("Core dump").on_hover_text(hover).clicked() {
+                        let folder = core_dump.parent().unwrap_or(core_dump);
+                        if let Err(e) = crate::paths::open_in_file_manager(folder) {
+                            error!("{}", e);
+                        }
+                    }
+                }
+            });
+            ui.end_row();
+        }
+    });
+}
+
+/// One button per tracker link, opening it in the browser.
+pub fn links_ui(ui: &mut eframe::egui::Ui, links: &[TrackerLink]) {
+    for link in links {
+        if ui.button(&link.label).on_hover_text(&link.url).clicked() {
+            ui.ctx().open_url(eframe::egui::OpenUrl::new_tab(&link.url));
+        }
+    }
+}
+
+/// State of the "Run history" export panel in the GUI.
+pub struct ExportPanel {
+    format: ExportFormat,
+    since: String,
+    until: String,
+    process_type: Option<ProcessType>,
+    output: String,
+    /// Outcome of the last export, shown below the button.
+    status: Option<Result<String, String>>,
+}
+
+impl Default for ExportPanel {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::Csv,
+            since: String::new(),
+            until: String::new(),
+            process_type: None,
+            output: "run-history.csv".to_string(),
+            status: None,
+        }
+    }
+}
+
+impl ExportPanel {
+    /// Parses the filter fields; empty date fields mean "unbounded".
+    fn filter(&self) -> Result<HistoryFilter, String> {
+        let parse_date = |s: &str, label: &str| {
+            let s = s.trim();
+            if s.is_empty() {
+                return Ok(None);
+            }
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .map(Some)
+                .map_err(|_| format!("Invalid '{}' date '{}', expected YYYY-MM-DD", label, s))
+        };
+        Ok(HistoryFilter {
+            since: parse_date(&self.since, "from")?,
+            until: parse_date(&self.until, "to")?,
+            process_type: self.process_type,
+        })
+    }
+
+    pub fn show(&mut self, ui: &mut eframe::egui::Ui, history: &History) {
+        use eframe::egui;
+
+        ui.label(format!("{} run(s) recorded.", history.runs().len()));
+        egui::Grid::new("history_export_grid").num_columns(2).show(ui, |ui| {
+            ui.label("From (YYYY-MM-DD):");
+            ui.text_edit_singleline(&mut self.since);
+            ui.end_row();
+
+            ui.label("To (YYYY-MM-DD):");
+            ui.text_edit_singleline(&mut self.until);
+            ui.end_row();
+
+            ui.label("Process type:");
+            egui::ComboBox::from_id_source("history_export_type")
+                .selected_text(match self.process_type {
+                    Some(t) => format!("{:?}", t),
+                    None => "All".to_string(),
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.process_type, None, "All");
+                    for t in ProcessType::ALL {
+                        ui.selectable_value(&mut self.process_type, Some(t), format!("{:?}", t));
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Format:");
+            ui.horizontal(|ui| {
+                for format in [ExportFormat::Csv, ExportFormat::Json] {
+                    let label = format.extension().to_uppercase();
+                    if ui.selectable_value(&mut self.format, format, label).changed() {
+                        // Keep the output file extension in sync with the chosen format.
+                        self.output = Path::new(&self.output)
+                            .with_extension(format.extension())
+                            .display()
+                            .to_string();
+                    }
+                }
+            });
+            ui.end_row();
+
+            ui.label("Output file:");
+            ui.text_edit_singleline(&mut self.output);
+            ui.end_row();
+        });
+
+        if ui.button("Export").clicked() {
+            let path = PathBuf::from(self.output.trim());
+            self.status = Some(self.filter().and_then(|filter| {
+                history
+                    .export_to_file(&filter, self.format, &path)
+                    .map(|n| format!("Exported {} run(s) to '{}'.", n, path.display()))
+            }));
+        }
+        match &self.status {
+            Some(Ok(msg)) => {
+                ui.label(msg);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(name: &str) -> History {
+        let path = std::env::temp_dir().join(format!("teleop-history-{}-{}.json", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        History::load(path)
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("plain text"), "plain text");
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("one\ntwo"), "\"one\ntwo\"");
+        assert_eq!(csv_field("one\r\ntwo"), "\"one\r\ntwo\"");
+    }
+
+    #[test]
+    fn runs_survive_saving_and_loading() {
+        let mut history = history("round-trip");
+        let index = history.start(
+            ProcessType::Record,
+            "ana".to_string(),
+            Some("bo".to_string()),
+            "record --task='a, \"b\"'".to_string(),
+            true,
+            None,
+        );
+        history.finish(index, Some(1), false);
+        history.set_fault(index, "servo overload".to_string());
+
+        let loaded = History::load(history.path.clone());
+        fs::remove_file(&history.path).unwrap();
+        let [run] = loaded.runs() else { panic!("expected one run, got {}", loaded.runs().len()) };
+        assert_eq!(run.process_type, ProcessType::Record);
+        assert_eq!(run.operator.as_deref(), Some("bo"));
+        assert_eq!(run.command, "record --task='a, \"b\"'");
+        assert_eq!(run.started_at, history.runs()[0].started_at);
+        assert_eq!(run.exit_code, Some(1));
+        assert!(run.edited && !run.stopped);
+        assert_eq!(run.outcome(), "fault");
+    }
+
+    #[test]
+    fn exports_hold_the_runs_matching_the_filter() {
+        let mut history = history("export");
+        history.start(ProcessType::Replay, "ana".to_string(), None, "replay".to_string(), false, None);
+        let command = "rec --a=1,\n2".to_string();
+        let index = history.start(ProcessType::Record, "ana".to_string(), None, command, false, None);
+        history.finish(index, Some(0), true);
+        fs::remove_file(&history.path).unwrap();
+        let filter = HistoryFilter { process_type: Some(ProcessType::Record), ..Default::default() };
+
+        let json = history.export(&filter, ExportFormat::Json).unwrap();
+        let runs: Vec<RunRecord> = serde_json::from_str(&json).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].command, "rec --a=1,\n2");
+        assert!(runs[0].stopped);
+
+        let csv = history.export(&filter, ExportFormat::Csv).unwrap();
+        let (header, rows) = csv.split_once('\n').unwrap();
+        assert_eq!(header.split(',').count(), 14);
+        assert!(rows.starts_with("Record,ana,,"));
+        assert!(rows.ends_with(",0,true,false,,,,,\"rec --a=1,\n2\"\n"));
+
+        let everything = history.export(&HistoryFilter::default(), ExportFormat::Csv).unwrap();
+        assert!(everything.contains("\nReplay,ana,"));
+    }
+}
@@ -0,0 +1,118 @@
+use serde::Deserialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use tracing::{debug, warn};
+
+use crate::{dataset, secrets};
+
+/// The `[hub]` config table: the Hugging Face users and organizations
+/// datasets go to, offered when composing `{repo_id}` so long repo ids need
+/// not be typed into the commands.
+#[derive(Deserialize, Clone, Debug)]
+pub struct HubConfig {
+    pub namespaces: Vec<String>,
+}
+
+/// The Hub token: the `HF_TOKEN` secret if the config maps one, otherwise
+/// `$HF_TOKEN` or the token `huggingface-cli login` saved.
+fn token(secret_env: &std::collections::BTreeMap<String, String>) -> Option<String> {
+    if let Some(name) = secret_env.get("HF_TOKEN") {
+        match secrets::get(name) {
+            Ok(Some(token)) => return Some(token),
+            Ok(None) => {}
+            Err(e) => warn!("{}", e),
+        }
+    }
+    if let Ok(token) = std::env::var("HF_TOKEN") {
+        return Some(token);
+    }
+    let hf_home = std::env::var_os("HF_HOME").map(PathBuf::from).unwrap_or_else(|| {
+        PathBuf::from(std::env::var_os("HOME").unwrap_or_default()).join(".cache/huggingface")
+    });
+    std::fs::read_to_string(hf_home.join("token")).ok().map(|t| t.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+/// Whether the dataset `repo_id` exists on the Hub (private ones only with a
+/// token that can see them).
+fn exists(repo_id: &str, token: Option<String>) -> Result<bool, String> {
+    let url = format!("https://huggingface.co/api/datasets/{}", repo_id);
+    let mut curl = Command::new("curl");
+    curl.args(["-sS", "--max-time", "10", "-o", "/dev/null", "-w", "%{http_code}"]);
+    // The header is read from stdin so the token does not show up in `ps`.
+    if token.is_some() {
+        curl.args(["-H", "@-"]);
+    }
+    let mut child = curl
+        .arg(&url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    if let (Some(token), Some(mut stdin)) = (token, child.stdin.take()) {
+        let _ = writeln!(stdin, "Authorization: Bearer {}", token);
+    }
+    let output = child.wait_with_output().map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Failed to reach the Hub: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let code = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    debug!("The Hub answered {} for '{}'", code, repo_id);
+    match code.as_str() {
+        "200" => Ok(true),
+        // The Hub answers 401 rather than 404 for a missing repo without a token.
+        "401" | "404" => Ok(false),
+        _ => Err(format!("The Hub answered with HTTP {}", code)),
+    }
+}
+
+/// Where a composed repo id already exists.
+#[derive(Debug, Clone, Default)]
+pub struct Existence {
+    pub local: bool,
+    pub hub: Option<Result<bool, String>>,
+}
+
+/// Looks up the repo id being composed, on disk right away and on the Hub in
+/// the background.
+#[derive(Default)]
+pub struct ExistenceCheck {
+    repo_id: String,
+    running: Option<Receiver<Result<bool, String>>>,
+    pub existence: Existence,
+}
+
+impl ExistenceCheck {
+    /// Starts looking up `repo_id`, unless it is the one looked up last.
+    pub fn start(&mut self, repo_id: &str, secret_env: &std::collections::BTreeMap<String, String>) {
+        if repo_id == self.repo_id || dataset::check_repo_id(repo_id).is_err() {
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        let token = token(secret_env);
+        let id = repo_id.to_string();
+        std::thread::spawn(move || {
+            let _ = sender.send(exists(&id, token));
+        });
+        self.repo_id = repo_id.to_string();
+        self.running = Some(receiver);
+        self.existence = Existence { local: dataset::local_path(repo_id, None).exists(), hub: None };
+    }
+
+    /// Whether the lookup of `repo_id` is done; picks up its outcome.
+    pub fn done(&mut self, repo_id: &str) -> bool {
+        if let Some(receiver) = &self.running {
+            match receiver.try_recv() {
+                Ok(outcome) => {
+                    self.existence.hub = Some(outcome);
+                    self.running = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => self.running = None,
+            }
+        }
+        repo_id == self.repo_id && self.running.is_none()
+    }
+}
@@ -0,0 +1,96 @@
+use eframe::egui;
+use regex::Regex;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Size of the overlay window.
+pub const SIZE: egui::Vec2 = egui::vec2(320.0, 44.0);
+
+/// Follows the episodes of the running Record process in its output, for the
+/// overlay.
+#[derive(Default)]
+pub struct EpisodeClock {
+    /// The episode `lerobot-record` announced last.
+    episode: Option<u64>,
+    since: Option<Instant>,
+}
+
+impl EpisodeClock {
+    /// Starts timing a new Record run.
+    pub fn start(&mut self) {
+        *self = Self { episode: None, since: Some(Instant::now()) };
+    }
+
+    /// Picks up `Recording episode N` as printed by `lerobot-record`.
+    pub fn feed(&mut self, line: &str) {
+        static PATTERN: OnceLock<Regex> = OnceLock::new();
+        let pattern = PATTERN.get_or_init(|| Regex::new(r"Recording episode (\d+)").unwrap());
+        if let Some(episode) = pattern.captures(line).and_then(|c| c[1].parse().ok()) {
+            self.episode = Some(episode);
+            self.since = Some(Instant::now());
+        }
+    }
+
+    pub fn episode(&self) -> Option<u64> {
+        self.episode
+    }
+
+    /// Time since the episode (or the run, before the first one) started.
+    pub fn elapsed(&self) -> Duration {
+        self.since.map(|s| s.elapsed()).unwrap_or_default()
+    }
+}
+
+/// What the overlay shows.
+pub struct HudState {
+    pub recording: bool,
+    /// `REC` while recording, otherwise e.g. the phase of the session.
+    pub label: String,
+    pub episode: Option<String>,
+    pub elapsed: Option<Duration>,
+}
+
+/// Shows the recording overlay: a small borderless window that stays on top,
+/// opened at `position` (in screen points) and dragged anywhere from there.
+/// Window managers that place windows themselves, such as Wayland
+/// compositors, may ignore the position.
+pub fn show(ctx: &egui::Context, state: &HudState, position: egui::Pos2) {
+    let builder = egui::ViewportBuilder::default()
+        .with_title("Recording")
+        .with_decorations(false)
+        .with_always_on_top()
+        .with_resizable(false)
+        .with_taskbar(false)
+        .with_inner_size(SIZE)
+        .with_position(position);
+    ctx.show_viewport_immediate(egui::ViewportId::from_hash_of("recording_hud"), builder, |ctx, _| {
+        let frame = egui::Frame::none().fill(egui::Color32::from_black_alpha(230)).inner_margin(8.0);
+        egui::CentralPanel::default().frame(frame).show(ctx, |ui| {
+            let drag = ui.interact(ui.max_rect(), egui::Id::new("recording_hud_drag"), egui::Sense::drag());
+            if drag.drag_started() {
+                ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+            }
+            ui.horizontal_centered(|ui| {
+                // Blinks once a second while recording.
+                let lit = !state.recording || ui.input(|i| i.time).fract() < 0.6;
+                let color = match (state.recording, lit) {
+                    (true, true) => egui::Color32::RED,
+                    (true, false) => egui::Color32::from_rgb(80, 0, 0),
+                    (false, _) => egui::Color32::GRAY,
+                };
+                let (dot, _) = ui.allocate_exact_size(egui::vec2(16.0, 16.0), egui::Sense::hover());
+                ui.painter().circle_filled(dot.center(), 7.0, color);
+                ui.label(egui::RichText::new(&state.label).strong().color(egui::Color32::WHITE));
+                if let Some(episode) = &state.episode {
+                    ui.label(egui::RichText::new(episode).color(egui::Color32::WHITE));
+                }
+                if let Some(elapsed) = state.elapsed {
+                    let seconds = elapsed.as_secs();
+                    let text = format!("{:02}:{:02}", seconds / 60, seconds % 60);
+                    ui.label(egui::RichText::new(text).monospace().color(egui::Color32::WHITE));
+                }
+            });
+        });
+    });
+    ctx.request_repaint_after(Duration::from_millis(250));
+}
@@ -0,0 +1,287 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tracing::info;
+
+use crate::config::{Config, Variant};
+use crate::params::ParamType;
+use crate::{command, placeholders, sandbox, ProcessType};
+
+/// A flag as documented by the `--help` of a LeRobot script (draccus, which
+/// formats it like argparse):
+///
+/// ```text
+///   --dataset.num_episodes int
+///                         Number of episodes to record. (default: 50)
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct HelpFlag {
+    /// Without the leading `--`, e.g. `dataset.num_episodes`.
+    pub name: String,
+    pub kind: ParamType,
+    /// The values of a `{a,b,c}` flag.
+    pub choices: Vec<String>,
+    pub default: Option<String>,
+    pub help: String,
+}
+
+/// The type of a flag from its metavar: `int`, `float`, `bool`, `{a,b}`, or
+/// unions such as `int|float`. Anything else is taken as a string.
+fn kind(metavar: &str) -> (ParamType, Vec<String>) {
+    if let Some(choices) = metavar.strip_prefix('{').and_then(|m| m.strip_suffix('}')) {
+        return (ParamType::String, choices.split(',').map(str::to_string).collect());
+    }
+    let types: Vec<&str> = metavar.split('|').collect();
+    let kind = if types.contains(&"float") {
+        ParamType::Float
+    } else if types.contains(&"int") {
+        ParamType::Int
+    } else if types == ["bool"] {
+        ParamType::Bool
+    } else {
+        ParamType::String
+    };
+    (kind, Vec::new())
+}
+
+/// The flags documented in `help`, the output of `--help`.
+pub fn parse_help(help: &str) -> Vec<HelpFlag> {
+    static DEFAULT: OnceLock<Regex> = OnceLock::new();
+    let default = DEFAULT.get_or_init(|| Regex::new(r"\(default: (.*)\)\s*$").unwrap());
+    let mut flags: Vec<HelpFlag> = Vec::new();
+    let mut continues = false;
+    for line in help.lines() {
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("--").filter(|_| line.starts_with(' ')) {
+            let (name, rest) = rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()));
+            // The metavar follows after one space, the help after two or more.
+            let (metavar, text) = match rest.strip_prefix(' ').filter(|r| !r.starts_with(' ') && !r.is_empty()) {
+                Some(rest) => rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len())),
+                None => ("", rest),
+            };
+            let (kind, choices) = kind(metavar);
+            flags.push(HelpFlag {
+                name: name.to_string(),
+                kind,
+                choices,
+                default: None,
+                help: text.trim().to_string(),
+            });
+            continues = true;
+        } else if continues && line.starts_with(' ') && !trimmed.is_empty() && !trimmed.starts_with('-') {
+            let flag = flags.last_mut().unwrap();
+            flag.help = format!("{} {}", flag.help, trimmed).trim().to_string();
+        } else {
+            continues = false;
+        }
+    }
+    for flag in &mut flags {
+        if let Some(captures) = default.captures(&flag.help) {
+            let value = captures[1].trim().to_string();
+            flag.default = match value.as_str() {
+                "None" => None,
+                "True" | "False" => Some(value.to_lowercase()),
+                _ => Some(value),
+            };
+            let start = captures.get(0).unwrap().start();
+            flag.help = flag.help[..start].trim().to_string();
+        }
+    }
+    flags
+}
+
+/// Runs the `--help` of the program `variant` runs, in its working directory
+/// and conda environment, and returns the flags it documents.
+pub fn help_flags(config: &Config, variant: &Variant) -> Result<(String, Vec<HelpFlag>), String> {
+    let defaults: HashMap<String, String> =
+        variant.params.iter().map(|p| (p.name.clone(), p.default_value())).collect();
+    let program = command::flag_program(&placeholders::substitute(&variant.command, &defaults))
+        .ok_or("The command passes no --flags to document.")?;
+    if let Some(name) = placeholders::names(&program).first() {
+        return Err(format!("'{}' uses {{{}}}, which is only filled in at launch.", program, name));
+    }
+    info!("Running '{} --help'", program);
+    let output = sandbox::bash(&command::resolve(config, variant, &format!("{} --help", program), &defaults))
+        .output()
+        .map_err(|e| format!("Failed to run bash: {}", e))?;
+    let flags = parse_help(&String::from_utf8_lossy(&output.stdout));
+    if flags.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "'{} --help' documented no flags ({}): {}",
+            program,
+            output.status,
+            stderr.lines().last().unwrap_or_default()
+        ));
+    }
+    Ok((program, flags))
+}
+
+/// The name of the `{placeholder}` of a flag: its last part, e.g.
+/// `num_episodes` for `dataset.num_episodes`, or the whole of it if that is
+/// taken or other flags end the same, e.g. `robot.port` and `teleop.port`.
+fn param_name(flag: &str, documented: &[HelpFlag], taken: &[String]) -> String {
+    let last = |name: &str| name.rsplit('.').next().unwrap_or(name).replace('-', "_");
+    let short = last(flag);
+    let ambiguous = documented.iter().any(|f| f.name != flag && last(&f.name) == short);
+    if ambiguous || taken.contains(&short) || placeholders::BUILTIN.contains(&short.as_str()) {
+        flag.replace(['.', '-'], "_")
+    } else {
+        short
+    }
+}
+
+/// `value` as a TOML value of the type of the parameter.
+fn toml_value(kind: ParamType, value: &str) -> Option<toml::Value> {
+    match kind {
+        ParamType::String => Some(toml::Value::String(value.to_string())),
+        ParamType::Int => value.parse().ok().map(toml::Value::Integer),
+        ParamType::Float => value.parse().ok().map(toml::Value::Float),
+        ParamType::Bool => value.parse().ok().map(toml::Value::Boolean),
+    }
+}
+
+/// Generates the form of variant `index` of `process_type` from the `--help`
+/// of its program: a parameter for each of `flags`, or for every flag the
+/// command sets to a plain value if empty, and the command with placeholders
+/// in their place. Returned as TOML to paste into the config.
+pub fn generate(config: &Config, process_type: ProcessType, index: usize, flags: &[String]) -> Result<String, String> {
+    let count = config.commands.variants(process_type).len();
+    if index >= count {
+        return Err(format!("commands.{} has {} variant(s)", process_type.key(), count));
+    }
+    let variant = config.commands.variant(process_type, index);
+    let (program, documented) = help_flags(config, variant)?;
+    let wanted: Vec<String> = if flags.is_empty() {
+        documented
+            .iter()
+            .filter(|f| {
+                let value = command::flag_value(&variant.command, &format!("--{}", f.name));
+                value.is_some_and(|v| placeholders::names(&v).is_empty())
+            })
+            .map(|f| f.name.clone())
+            .collect()
+    } else {
+        flags.iter().map(|f| f.trim_start_matches('-').to_string()).collect()
+    };
+    if wanted.is_empty() {
+        return Err("The command sets no documented flag to a plain value; name the flags to turn into parameters.".into());
+    }
+
+    let mut command = variant.command.clone();
+    let mut names: Vec<String> = variant.params.iter().map(|p| p.name.clone()).collect();
+    let mut params = String::new();
+    for name in &wanted {
+        let flag = documented
+            .iter()
+            .find(|f| f.name == *name)
+            .ok_or_else(|| format!("'{} --help' does not document --{}", program, name))?;
+        let param = param_name(name, &documented, &names);
+        let current = command::flag_value(&command, &format!("--{}", name));
+        command = command::set_or_add_flag(&command, &format!("--{}", name), &format!("{{{}}}", param));
+        params.push_str(&format!("\n[[commands.{}.params]]\n", process_type.key()));
+        if !flag.help.is_empty() {
+            params.push_str(&format!("# {}\n", flag.help));
+        }
+        params.push_str(&format!("name = {}\n", toml::Value::String(param.clone())));
+        params.push_str(&format!("type = \"{}\"\n", flag.kind.key()));
+        if let Some(default) = current.or(flag.default.clone()).and_then(|d| toml_value(flag.kind, &d)) {
+            params.push_str(&format!("default = {}\n", default));
+        }
+        if !flag.choices.is_empty() {
+            params.push_str(&format!("choices = {}\n", toml::Value::from(flag.choices.clone())));
+        }
+        names.push(param);
+    }
+    Ok(format!(
+        "# The form of commands.{}{}, generated from '{} --help'.\ncommand = {}\n{}",
+        process_type.key(),
+        if variant.name.is_empty() { String::new() } else { format!(" ({})", variant.name) },
+        program,
+        toml::Value::String(command),
+        params
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELP: &str = "\
+usage: lerobot-record [-h] [--config_path str] [--dataset str]
+
+options:
+  -h, --help            show this help message and exit
+  --config_path str     Path to a config file (default: None)
+
+RecordConfig ['dataset']:
+  --dataset.repo_id str
+                        Dataset identifier. By convention it should match
+                        '{hf_username}/{dataset_name}'. (default: None)
+  --dataset.num_episodes int
+                        Number of episodes to record. (default: 50)
+  --dataset.fps int|float
+                        Limit the frames per second. (default: 30)
+  --dataset.video bool  Encode frames in the dataset into video (default: True)
+  --robot.type {so100_follower,koch_follower}
+  --teleop.port str     Port of the leader arm (default: /dev/ttyACM1)
+  --robot.port str
+";
+
+    fn flag<'a>(flags: &'a [HelpFlag], name: &str) -> &'a HelpFlag {
+        flags.iter().find(|f| f.name == name).unwrap_or_else(|| panic!("--{} not parsed", name))
+    }
+
+    #[test]
+    fn flags_are_parsed_with_their_type_and_default() {
+        // `-h, --help` is not a flag of the config.
+        let flags = parse_help(HELP);
+        let names: Vec<&str> = flags.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "config_path",
+                "dataset.repo_id",
+                "dataset.num_episodes",
+                "dataset.fps",
+                "dataset.video",
+                "robot.type",
+                "teleop.port",
+                "robot.port"
+            ]
+        );
+        let repo_id = flag(&flags, "dataset.repo_id");
+        assert_eq!(repo_id.kind, ParamType::String);
+        assert_eq!(repo_id.default, None);
+        // Help continued on the next lines is joined.
+        assert_eq!(
+            repo_id.help,
+            "Dataset identifier. By convention it should match '{hf_username}/{dataset_name}'."
+        );
+        assert_eq!(flag(&flags, "dataset.num_episodes").default.as_deref(), Some("50"));
+        assert_eq!(flag(&flags, "dataset.fps").kind, ParamType::Float);
+        let video = flag(&flags, "dataset.video");
+        assert_eq!((video.kind, video.default.as_deref()), (ParamType::Bool, Some("true")));
+        assert_eq!(video.help, "Encode frames in the dataset into video");
+        assert_eq!(flag(&flags, "robot.type").choices, ["so100_follower", "koch_follower"]);
+        assert_eq!(flag(&flags, "teleop.port").default.as_deref(), Some("/dev/ttyACM1"));
+        assert_eq!(flag(&flags, "robot.port").help, "");
+    }
+
+    #[test]
+    fn param_names_are_short_unless_ambiguous() {
+        let flags = parse_help(HELP);
+        assert_eq!(param_name("dataset.num_episodes", &flags, &[]), "num_episodes");
+        assert_eq!(param_name("robot.port", &flags, &[]), "robot_port");
+        assert_eq!(param_name("dataset.fps", &flags, &[]), "dataset_fps");
+        assert_eq!(param_name("dataset.video", &flags, &["video".to_string()]), "dataset_video");
+    }
+
+    #[test]
+    fn values_are_typed_like_the_flag() {
+        assert_eq!(toml_value(ParamType::Int, "50"), Some(toml::Value::Integer(50)));
+        assert_eq!(toml_value(ParamType::Float, "30"), Some(toml::Value::Float(30.0)));
+        assert_eq!(toml_value(ParamType::Bool, "true"), Some(toml::Value::Boolean(true)));
+        assert_eq!(toml_value(ParamType::Int, "lots"), None);
+    }
+}
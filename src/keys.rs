@@ -0,0 +1,125 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::command::shell_quote;
+
+/// A key LeRobot's record loop reacts to, with a button label and what it does.
+pub struct ControlKey {
+    pub label: &'static str,
+    /// What a terminal sends for the key.
+    pub sequence: &'static str,
+    pub description: &'static str,
+}
+
+/// What a terminal sends for Esc.
+pub const ESCAPE: &str = "\x1b";
+
+/// The keyboard controls of `lerobot-record`.
+pub const RECORD_KEYS: &[ControlKey] = &[
+    ControlKey {
+        label: "Next →",
+        sequence: "\x1b[C",
+        description: "End the current episode or reset period early",
+    },
+    ControlKey {
+        label: "← Re-record",
+        sequence: "\x1b[D",
+        description: "Discard the current episode and record it again",
+    },
+    ControlKey {
+        label: "Stop (Esc)",
+        sequence: ESCAPE,
+        description: "Stop recording, saving the episodes recorded so far",
+    },
+];
+
+/// The standard input of a running command: a FIFO next to its log, which
+/// the command reads from and the app writes to. Unlike typing into its
+/// terminal window this works with any terminal, also on Wayland and through
+/// `flatpak-spawn`, and leaves the focus where it is.
+pub struct Input {
+    path: PathBuf,
+    fifo: File,
+}
+
+impl Input {
+    /// Creates the FIFO for the run logged to `log`.
+    pub fn create(log: &Path) -> Result<Self, String> {
+        let path = log.with_extension("stdin");
+        let _ = fs::remove_file(&path);
+        let status = Command::new("mkfifo")
+            .arg(&path)
+            .status()
+            .map_err(|e| format!("Failed to run mkfifo: {}", e))?;
+        if !status.success() {
+            return Err(format!("mkfifo failed to create '{}': {}", path.display(), status));
+        }
+        // Opening only the write end would block until the command opens the
+        // other one; opened for reading too, it never does.
+        let fifo = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
+        Ok(Self { path, fifo })
+    }
+
+    /// Wraps `command` so it reads its standard input from the FIFO.
+    pub fn redirect(&self, command: &str) -> String {
+        format!("({}) < {}", command, shell_quote(&self.path.display().to_string()))
+    }
+
+    /// Sends a key press, as the `sequence` the terminal would send for it.
+    pub fn send(&mut self, sequence: &str) -> Result<(), String> {
+        self.fifo
+            .write_all(sequence.as_bytes())
+            .map_err(|e| format!("Failed to write to the process's input: {}", e))
+    }
+
+    /// Writes `line` followed by a newline, as if the operator typed it in
+    /// the terminal, so prompts of the running script can be answered.
+    pub fn type_line(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.fifo, "{}", line).map_err(|e| format!("Failed to write to the process's input: {}", e))
+    }
+}
+
+impl Drop for Input {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_lines_reach_the_command() {
+        let log = std::env::temp_dir().join(format!("keys-test-{}.log", std::process::id()));
+        let mut input = Input::create(&log).unwrap();
+        input.type_line("y").unwrap();
+        let output = Command::new("bash")
+            .args(["-c", &input.redirect("read answer; echo \"answer: $answer\"")])
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "answer: y\n");
+    }
+
+    #[test]
+    fn keys_are_sent_as_terminal_sequences() {
+        let log = std::env::temp_dir().join(format!("keys-test-send-{}.log", std::process::id()));
+        let mut input = Input::create(&log).unwrap();
+        input.send(RECORD_KEYS[0].sequence).unwrap();
+        input.send(ESCAPE).unwrap();
+        let output = Command::new("bash")
+            .args(["-c", &input.redirect("head -c 4 | od -An -c")])
+            .output()
+            .unwrap();
+        let bytes: Vec<_> = String::from_utf8_lossy(&output.stdout).split_whitespace().map(str::to_string).collect();
+        assert_eq!(bytes, ["033", "[", "C", "033"]);
+        drop(input);
+        assert!(!log.with_extension("stdin").exists());
+    }
+}
@@ -0,0 +1,117 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Where serial port lock files live, in the UUCP convention that minicom,
+/// picocom and many other serial tools follow: `LCK..ttyACM0`, holding the
+/// PID of the owner.
+const LOCK_DIR: &str = "/var/lock";
+
+/// The lock files taken for the serial ports of a run, removed when it ends.
+#[derive(Default)]
+pub struct PortLocks {
+    files: Vec<PathBuf>,
+}
+
+impl PortLocks {
+    /// Locks `ports` for the process about to be launched, unless another
+    /// program holds a lock on one or has it open; then returns who does.
+    /// With `force`, ports in use are left alone instead. Ports that are not
+    /// on this machine are skipped, and so is locking where the lock folder
+    /// cannot be written: the lock is advisory and must not keep anyone from
+    /// launching.
+    pub fn acquire(ports: &[String], force: bool) -> Result<Self, Vec<String>> {
+        let mut locks = Self::default();
+        let mut problems = Vec::new();
+        for port in ports {
+            let Ok(device) = fs::canonicalize(port) else { continue };
+            let lock_file = lock_file(&device);
+            let user = match holder(&lock_file) {
+                Some(pid) => Some(format!("{} locked it", describe(pid))),
+                None => open_by(&device).map(|pid| format!("{} has it open", describe(pid))),
+            };
+            match user {
+                Some(user) if force => warn!("Launching although {} is in use: {}", port, user),
+                Some(user) => problems.push(format!("{} is in use: {}.", port, user)),
+                None => match create(&lock_file) {
+                    Ok(()) => locks.files.push(lock_file),
+                    Err(e) => debug!("Not locking {}: {}", port, e),
+                },
+            }
+        }
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        if !locks.files.is_empty() {
+            info!("Locked {}", locks.files.iter().map(|f| f.display().to_string()).collect::<Vec<_>>().join(", "));
+        }
+        Ok(locks)
+    }
+}
+
+impl Drop for PortLocks {
+    fn drop(&mut self) {
+        for file in &self.files {
+            if let Err(e) = fs::remove_file(file) {
+                warn!("Failed to remove the lock '{}': {}", file.display(), e);
+            }
+        }
+    }
+}
+
+/// The lock file of `device`, e.g. `/var/lock/LCK..ttyACM0`.
+fn lock_file(device: &Path) -> PathBuf {
+    let name = device.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    Path::new(LOCK_DIR).join(format!("LCK..{}", name))
+}
+
+/// The live process holding `lock_file`, other than this app. Stale locks of
+/// processes that are gone are removed.
+fn holder(lock_file: &Path) -> Option<u32> {
+    let contents = fs::read(lock_file).ok()?;
+    // Written as ASCII by most tools, as a 4-byte integer by some old ones.
+    let pid = match String::from_utf8_lossy(&contents).trim().parse() {
+        Ok(pid) => pid,
+        Err(_) => u32::from_ne_bytes(contents.get(..4)?.try_into().ok()?),
+    };
+    if pid == std::process::id() {
+        return None;
+    }
+    if Path::new("/proc").join(pid.to_string()).exists() {
+        return Some(pid);
+    }
+    info!("Removing the stale lock '{}' of PID {}", lock_file.display(), pid);
+    let _ = fs::remove_file(lock_file);
+    None
+}
+
+/// A process other than this app that has `device` open. Only the processes
+/// of this user can be looked into.
+fn open_by(device: &Path) -> Option<u32> {
+    let own = std::process::id();
+    fs::read_dir("/proc").ok()?.flatten().find_map(|entry| {
+        let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+        if pid == own {
+            return None;
+        }
+        let mut fds = fs::read_dir(entry.path().join("fd")).ok()?;
+        fds.any(|fd| fd.is_ok_and(|fd| fs::read_link(fd.path()).is_ok_and(|target| target == device)))
+            .then_some(pid)
+    })
+}
+
+/// E.g. `python (PID 4242)`.
+fn describe(pid: u32) -> String {
+    match fs::read_to_string(format!("/proc/{}/comm", pid)) {
+        Ok(name) => format!("{} (PID {})", name.trim(), pid),
+        Err(_) => format!("PID {}", pid),
+    }
+}
+
+/// Takes the lock in the name of this app, which outlives the run; a lock
+/// left behind by a crash is then recognized as stale.
+fn create(lock_file: &Path) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(lock_file)?;
+    writeln!(file, "{:>10}", std::process::id())
+}
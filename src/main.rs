@@ -1,77 +1,499 @@
-use clap::Parser;
+mod audit;
+mod checkpoints;
+mod clock;
+mod command;
+mod compare;
+mod companion;
+mod config;
+mod controller;
+mod cores;
+mod dataset;
+mod diagnostics;
+mod discovery;
+mod disk;
+mod evaluation;
+mod exclusivity;
+mod faults;
+mod frames;
+mod git;
+mod hardware;
+mod history;
+mod hub;
+mod hud;
+mod introspect;
+mod keys;
+mod locks;
+mod mqtt;
+mod output;
+mod palette;
+mod params;
+mod paths;
+mod placeholders;
+mod plugins;
+mod preview;
+mod probe;
+mod process;
+mod profile;
+mod realtime;
+mod ros;
+mod sandbox;
+mod saves;
+mod scripts;
+mod secrets;
+mod session;
+mod snapshot;
+mod stall;
+mod state;
+mod status;
+mod sweep;
+mod telemetry;
+mod toasts;
+mod trackers;
+mod updates;
+mod voice;
+mod web;
+
+use clap::{Parser, Subcommand};
 use eframe::egui;
-use serde::Deserialize;
-use std::fs;
-use log::{debug, error, info};
-use std::process::{Child, Command};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::{debug, error, info, warn};
+use std::process::Child;
 use std::sync::Arc;
 use std::path::PathBuf;
 
+use audit::{AuditEvent, AuditLog};
+use config::{Config, Diagnostic};
+use history::{ExportFormat, History, HistoryFilter};
+use state::AppState;
+use status::StatusEvent;
+
 /// Enum to represent the different types of processes we can run.
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
 enum ProcessType {
     Teleoperation,
     Record,
     Replay,
+    /// Uploading a dataset to the Hugging Face Hub.
+    Push,
+    /// Evaluating a policy; only available if configured.
+    Evaluate,
+    /// Training a policy; only available if configured.
+    Train,
+}
+
+impl ProcessType {
+    /// Every process type, in the order they are shown in the GUI.
+    const ALL: [ProcessType; 6] = [
+        ProcessType::Teleoperation,
+        ProcessType::Record,
+        ProcessType::Replay,
+        ProcessType::Push,
+        ProcessType::Evaluate,
+        ProcessType::Train,
+    ];
+
+    /// The process type whose config entry is named `key`.
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.key() == key)
+    }
+
+    /// The name of this process type's entry in the `[commands]` config table.
+    fn key(self) -> &'static str {
+        match self {
+            ProcessType::Teleoperation => "teleoperation",
+            ProcessType::Record => "record",
+            ProcessType::Replay => "replay",
+            ProcessType::Push => "push",
+            ProcessType::Evaluate => "evaluate",
+            ProcessType::Train => "train",
+        }
+    }
 }
 
-/// Struct to hold the command strings from config.toml.
-#[derive(Deserialize, Clone)]
-struct Commands {
-    #[serde(default)]
-    working_directory: String,
-    teleoperation: String,
-    record: String,
-    replay: String,
+/// Adjustments to the configured command for a single launch.
+#[derive(Debug, Clone, Default)]
+struct LaunchOptions {
+    /// Run against this dataset instead of the one in the configured command.
+    dataset: Option<dataset::RecentDataset>,
+    /// Add `--resume=true` so LeRobot appends to an existing dataset.
+    resume: bool,
+    /// Launch even if a program the command runs could not be found.
+    skip_program_check: bool,
+    /// Launch even if another program uses a serial port of the command.
+    skip_port_check: bool,
+    /// Launch Record even if the clocks of the machines involved disagree.
+    skip_clock_check: bool,
+    /// Launch Record even from a modified checkout or an unexpected branch.
+    skip_git_check: bool,
+    /// Launch Record as configured even if its dataset already exists,
+    /// instead of offering to resume it.
+    skip_dataset_check: bool,
+    /// Run this command instead of the configured one; set when the operator
+    /// edited the command for this run only.
+    command: Option<String>,
+    /// Flags to set (or add) in the command, e.g. for a single session episode.
+    flags: Vec<(String, String)>,
+    /// Placeholder values to use instead of the entered ones, e.g. for a sweep.
+    values: Vec<(String, String)>,
 }
 
-/// Struct for application-level settings from config.toml.
-#[derive(Deserialize, Clone, Default)]
-struct AppConfig {
-    /// The terminal emulator to use.
-    /// We use an Option so we can default if it's missing from the TOML file.
-    #[serde(default)]
-    terminal: Option<String>,
-    /// Path to the conda installation directory.
-    #[serde(default)]
-    conda_path: Option<String>,
+/// Why a running process is stopped, as recorded in the audit log.
+#[derive(Debug, Clone, Copy)]
+enum Stop<'a> {
+    /// Asked for by the operator or the app, e.g. `"Stop button"`.
+    Requested(&'a str),
+    /// An emergency stop, with its reason.
+    Emergency(&'a str),
 }
 
-/// Struct to represent the overall configuration.
-#[derive(Deserialize, Clone)]
-struct Config {
-    /// We use `serde(default)` so the app doesn't crash if the `[app]` table is missing.
-    #[serde(default)]
-    app: AppConfig,
-    commands: Commands,
+/// A recording held back because its dataset already exists, offering to
+/// resume it at the next episode instead.
+struct ResumeOffer {
+    /// Whether a recording session was being started, rather than a single Record.
+    session: bool,
+    options: LaunchOptions,
+    repo_id: String,
+    /// Episodes already in the dataset.
+    episodes: u64,
+    /// Whether the last run into this dataset never finished, e.g. because the app crashed.
+    interrupted: bool,
 }
 
+/// The LeRobot flag of the directory a training run writes to.
+const TRAIN_OUTPUT_FLAG: &str = "--output_dir";
+
+/// Label of the TensorBoard companion process.
+const TENSORBOARD: &str = "TensorBoard";
+
 /// Holds the application state.
 struct MyApp {
+    /// Where the configuration was loaded from.
+    config_path: PathBuf,
     /// The loaded configuration, wrapped in an Arc for efficient sharing.
-    config: Result<Arc<Config>, String>,
+    config: Result<Arc<Config>, Vec<Diagnostic>>,
+    /// Non-fatal problems found while validating the loaded configuration.
+    config_diagnostics: Vec<Diagnostic>,
     /// The currently running child process, if any. The tuple stores the process handle and its type.
     child_process: Option<(Child, ProcessType)>,
+    /// Every process launched so far, persisted across restarts.
+    history: History,
+    /// Index into `history` of the entry belonging to `child_process`.
+    current_run: Option<usize>,
+    /// State of the history export form.
+    export_panel: history::ExportPanel,
+    /// The OS user running the app, attached to runs and audit entries.
+    user: String,
+    /// Append-only record of config edits and emergency stops.
+    audit: AuditLog,
+    /// GUI state persisted between sessions, e.g. the selected operator.
+    state: AppState,
+    /// The last launch error, shown in the GUI until the next launch attempt.
+    last_error: Option<String>,
+    /// Set when a launch was refused by the pre-launch checks, to offer
+    /// launching it anyway (e.g. for programs that are shell aliases).
+    launch_anyway: Option<(ProcessType, LaunchOptions)>,
+    /// The checkout of the working directory, shown in the GUI.
+    git_status: git::StatusWatch,
+    /// The checkout recorded from anyway, so the operator is not asked again
+    /// for every episode.
+    git_accepted: Option<git::GitStatus>,
+    /// Where the repo id composed for `{repo_id}` already exists.
+    repo_check: hub::ExistenceCheck,
+    /// Dialog for managing the secrets stored in the OS keyring.
+    secrets_dialog: secrets::SecretsDialog,
+    /// Dataset selected by dropping its folder onto the window; used by
+    /// Replay and Push and to fill the dataset placeholders.
+    active_dataset: Option<(dataset::RecentDataset, dataset::DatasetInfo)>,
+    /// The Ctrl+K command palette.
+    palette: palette::CommandPalette,
+    /// A command being edited for a single run, opened from a button's context menu.
+    command_edit: Option<(ProcessType, String)>,
+    /// The structured recording session in progress, if any.
+    session: Option<session::Session>,
+    /// Shown when Record would run into an existing dataset.
+    resume_offer: Option<ResumeOffer>,
+    /// The dataset whose last episode is deleted once the operator confirms,
+    /// with its metadata as read when asked.
+    delete_episode: Option<(dataset::RecentDataset, Result<dataset::DatasetInfo, String>)>,
+    /// The dataset shown in the statistics panel, with its metadata as read
+    /// last; cleared to read it again.
+    dataset_stats: Option<(dataset::RecentDataset, Result<dataset::DatasetInfo, String>)>,
+    /// The live preview of the Record command's cameras, while shown.
+    preview: Option<preview::Preview>,
+    /// Where on the screen the camera preview was shown last; the recording
+    /// overlay opens there.
+    preview_rect: Option<egui::Rect>,
+    /// The episode Record is recording and for how long, for the overlay.
+    episode_clock: hud::EpisodeClock,
+    /// Whether the running process is paused (stopped with SIGSTOP).
+    paused: bool,
+    /// Text typed into the running-process view, sent to the process on Enter.
+    stdin_line: String,
+    /// Background processes such as TensorBoard, started next to the main one.
+    companions: Vec<companion::Companion>,
+    /// Follows the captured output of the running process.
+    output: Option<output::OutputTail>,
+    /// Follows the frame rate of the running Record process.
+    frames: Option<frames::FrameMonitor>,
+    /// Watches the output of the running Record process for hardware faults.
+    faults: Option<faults::FaultDetector>,
+    /// Watches the dataset of the running Record process for new data.
+    stall: Option<stall::StallWatch>,
+    /// The lock files on the serial ports of the running process.
+    port_locks: Option<locks::PortLocks>,
+    /// Notices the episodes the running Record process saves.
+    saves: Option<saves::SaveWatch>,
+    /// Short confirmations shown in the corner of the window.
+    toasts: toasts::Toasts,
+    /// Trained policies offered for `{checkpoint}`, newest first.
+    checkpoints: Vec<checkpoints::Checkpoint>,
+    /// The sweep (or A/B comparison) in progress, if any.
+    sweep: Option<sweep::Sweep>,
+    /// State of the A/B comparison form.
+    compare_panel: compare::ComparePanel,
+    /// The ROS e-stop subscription, or why it is not running. `None` until
+    /// started (again) after loading the config.
+    estop: Option<Result<ros::EstopListener, String>>,
+    /// The keyword spotter of `[voice]`, or why it is not running. `None`
+    /// until started (again) after loading the config.
+    voice: Option<Result<voice::VoiceListener, String>>,
+    /// The status page of `[web]`, or why it is not served. Started once, as
+    /// its address cannot be given up while the app runs.
+    web: Option<Result<web::Server, String>>,
+    /// Set by the operator to stop listening for voice commands.
+    voice_off: bool,
+    /// Forwards controller inputs to the teleop script while enabled.
+    controller: Option<controller::Bridge>,
+    /// The last connection test of each process type.
+    connection_tests: HashMap<ProcessType, probe::ConnectionTest>,
+    /// The last hardware check of the Record setup.
+    hardware_check: Option<hardware::HardwareCheck>,
+    /// Robot hosts found on the network for `{host}`.
+    discovery: discovery::Discovery,
+    /// Changes the app's own log level and file at runtime.
+    log_control: telemetry::LogControl,
+    /// The log file path being entered in the GUI.
+    log_file_edit: String,
+    /// The last diagnostics bundle written, or why it failed.
+    diagnostics_export: Option<Result<PathBuf, String>>,
+    /// The last profile archive written, or why it failed.
+    profile_export: Option<Result<PathBuf, String>>,
+    /// Tracing span of the running process, from launch to exit.
+    run_span: Option<tracing::Span>,
+    /// When the clocks of which hosts were last found to agree.
+    clock_checked: Option<(std::time::Instant, Vec<String>)>,
+    /// Finds the core dump of the current run if it crashes.
+    core_watch: Option<cores::Watch>,
+    /// The standard input of the running command, if it could be created.
+    input: Option<keys::Input>,
+    /// Core dumps of crashed runs being collected.
+    cores: cores::Collector,
+    /// Code and package snapshots of recent runs still being taken.
+    snapshots: snapshot::Snapshots,
+    /// Looks for newer releases if `[updates]` is configured.
+    updater: updates::Updater,
+    /// The plugins of the config and their state.
+    plugins: plugins::Plugins,
+    /// The workflow script running, if any.
+    script: Option<scripts::RunningScript>,
 }
 
 impl MyApp {
     /// Creates a new instance of the application, loading the configuration.
-    fn new(config_path: PathBuf) -> Self {
-        info!("Loading configuration from: {}", config_path.display());
-        let config = Self::load_config(config_path).map(Arc::new);
-        Self {
-            config,
+    fn new(config_path: PathBuf, log_control: telemetry::LogControl) -> Self {
+        let mut app = Self {
+            config_path,
+            config: Err(Vec::new()),
+            config_diagnostics: Vec::new(),
             child_process: None,
+            history: History::load(History::default_path()),
+            current_run: None,
+            export_panel: history::ExportPanel::default(),
+            user: audit::current_user(),
+            audit: AuditLog::new(AuditLog::default_path()),
+            state: AppState::load(),
+            last_error: None,
+            launch_anyway: None,
+            git_status: git::StatusWatch::default(),
+            git_accepted: None,
+            repo_check: hub::ExistenceCheck::default(),
+            secrets_dialog: secrets::SecretsDialog::default(),
+            active_dataset: None,
+            palette: palette::CommandPalette::default(),
+            command_edit: None,
+            session: None,
+            resume_offer: None,
+            delete_episode: None,
+            dataset_stats: None,
+            preview: None,
+            preview_rect: None,
+            episode_clock: hud::EpisodeClock::default(),
+            paused: false,
+            stdin_line: String::new(),
+            companions: Vec::new(),
+            output: None,
+            frames: None,
+            faults: None,
+            stall: None,
+            port_locks: None,
+            saves: None,
+            toasts: toasts::Toasts::default(),
+            checkpoints: Vec::new(),
+            sweep: None,
+            compare_panel: compare::ComparePanel::default(),
+            estop: None,
+            voice: None,
+            web: None,
+            voice_off: false,
+            controller: None,
+            connection_tests: HashMap::new(),
+            hardware_check: None,
+            discovery: discovery::Discovery::default(),
+            clock_checked: None,
+            core_watch: None,
+            input: None,
+            cores: cores::Collector::default(),
+            snapshots: snapshot::Snapshots::default(),
+            updater: updates::Updater::default(),
+            plugins: plugins::Plugins::default(),
+            script: None,
+            log_control,
+            log_file_edit: String::new(),
+            diagnostics_export: None,
+            profile_export: None,
+            run_span: None,
+        };
+        app.reload_config();
+        app.offer_resume_after_crash();
+        app
+    }
+
+    /// Offers to resume recording if the last run was a Record that never
+    /// finished, i.e. the app was closed or crashed while it was running.
+    fn offer_resume_after_crash(&mut self) {
+        let Some(run) = self.history.runs().last() else { return };
+        if run.process_type != ProcessType::Record || run.finished_at.is_some() {
+            return;
+        }
+        let Some(dataset) = dataset::from_command(&run.command) else { return };
+        let Ok(info) = dataset::read_info(&dataset.local_path()) else { return };
+        self.resume_offer = Some(ResumeOffer {
+            session: false,
+            options: LaunchOptions { dataset: Some(dataset.clone()), ..Default::default() },
+            repo_id: dataset.repo_id,
+            episodes: info.total_episodes,
+            interrupted: true,
+        });
+    }
+
+    /// A new dataset for the next recording named by `[dataset_naming]`, if
+    /// set, from a repo id that no local dataset, recent dataset or past run has.
+    fn new_dataset(&self) -> Result<Option<dataset::RecentDataset>, String> {
+        let Some(naming) = self.config.as_ref().ok().and_then(|c| c.dataset_naming.as_ref()) else { return Ok(None) };
+        let (command, mut values) = self.prepare_command(ProcessType::Record, &LaunchOptions::default());
+        values.insert("user".to_string(), self.user.clone());
+        if let Some(task) = command::flag_value(&command, session::TASK_FLAG) {
+            values.insert("task".to_string(), task);
+        }
+        let used: Vec<String> = self
+            .history
+            .runs()
+            .iter()
+            .filter_map(|run| dataset::from_command(&run.command))
+            .chain(self.state.recent_datasets.iter().cloned())
+            .map(|d| d.repo_id)
+            .collect();
+        let taken = |repo_id: &str| used.iter().any(|u| u == repo_id) || dataset::local_path(repo_id, None).exists();
+        let repo_id = dataset::new_name(naming, &values, taken)?;
+        info!("Named the new dataset '{}'", repo_id);
+        Ok(Some(dataset::RecentDataset { repo_id, root: None, last_used: chrono::Local::now() }))
+    }
+
+    /// The dataset the Record command launched with `options` would record
+    /// into and how many episodes it has, if it already exists.
+    fn existing_dataset(&self, options: &LaunchOptions) -> Option<(dataset::RecentDataset, u64)> {
+        let (command, _) = self.prepare_command(ProcessType::Record, options);
+        let dataset = dataset::from_command(&command)?;
+        let path = dataset.local_path();
+        if !path.exists() {
+            return None;
+        }
+        let episodes = dataset::read_info(&path).map(|i| i.total_episodes).unwrap_or(0);
+        Some((dataset, episodes))
+    }
+
+    /// (Re)loads the configuration from `config_path`.
+    fn reload_config(&mut self) {
+        info!("Loading configuration from: {}", self.config_path.display());
+        let (config, config_diagnostics) = match config::load(&self.config_path, &self.user, &self.audit) {
+            Ok((config, diagnostics)) => (Ok(Arc::new(config)), diagnostics),
+            Err(diagnostics) => (Err(diagnostics), Vec::new()),
+        };
+        if let Ok(config) = &config {
+            if let Err(e) = self.log_control.apply(&config.logging) {
+                error!("{}", e);
+            }
+            self.plugins = plugins::Plugins::load(&config.plugins);
+            self.state.remember_config(&self.config_path);
+            self.state.save();
+        }
+        self.log_file_edit = self.log_control.file().map(|f| f.display().to_string()).unwrap_or_default();
+        for d in config.as_ref().err().unwrap_or(&config_diagnostics) {
+            error!("Config {}", d);
+        }
+        self.config = config;
+        self.config_diagnostics = config_diagnostics;
+        self.scan_checkpoints();
+        self.estop = None;
+        self.voice = None;
+    }
+
+    /// Loads the config file at `path` instead of the current one, e.g. the one
+    /// of another rig.
+    fn switch_config(&mut self, path: PathBuf) {
+        if !self.idle() {
+            return;
         }
+        self.config_path = path;
+        self.reload_config();
     }
 
-    /// Loads configuration from the specified path. Returns a `Result` indicating
-    /// success or failure, with an error message if loading fails.
-    /// The `config_path` is consumed to ensure it's used.
-    fn load_config(config_path: PathBuf) -> Result<Config, String> {
-        let config_str = fs::read_to_string(&config_path)
-            .map_err(|e| format!("Failed to read config file '{}': {}", config_path.display(), e))?;
-        toml::from_str(&config_str).map_err(|e| format!("Failed to parse config.toml: {}", e))
+    /// Writes the config and GUI state to a profile archive, to set up another
+    /// station the same way.
+    fn export_profile(&mut self) {
+        let path = profile::default_path();
+        let result = profile::export(&self.config_path, &self.state, &path).map(|()| path);
+        if let Err(e) = &result {
+            error!("{}", e);
+        }
+        self.profile_export = Some(result);
+    }
+
+    /// Sets this station up from the profile archive at `archive` and loads
+    /// its config.
+    fn import_profile(&mut self, archive: &std::path::Path) {
+        if !self.idle() {
+            self.last_error = Some("Stop what is running before importing a profile.".to_string());
+            return;
+        }
+        match profile::import(archive, &mut self.state) {
+            Ok(config_path) => {
+                self.state.save();
+                self.last_error = None;
+                self.switch_config(config_path);
+            }
+            Err(e) => self.last_error = Some(e),
+        }
+    }
+
+    /// Looks for trained policies again, e.g. after training.
+    fn scan_checkpoints(&mut self) {
+        self.checkpoints = match &self.config {
+            Ok(config) => checkpoints::scan(config),
+            Err(_) => Vec::new(),
+        };
+        debug!("Found {} checkpoint(s)", self.checkpoints.len());
     }
 }
 
@@ -86,185 +508,3149 @@ impl Default for MyApp {
 }
 
 impl MyApp {
-    /// Spawns a process in a new terminal window.
-    fn spawn_process(&mut self, process_type: ProcessType) {
+    /// Spawns a process in a new terminal window. Unless told otherwise by
+    /// `options`, the launch is refused if a program the command runs cannot be found.
+    #[tracing::instrument(skip(self, options))]
+    fn spawn_process(&mut self, process_type: ProcessType, mut options: LaunchOptions) {
         // If a process is already running, do nothing.
         debug!("Attempting to spawn process of type: {:?}", process_type);
         if self.child_process.is_some() || self.config.is_err() {
             return;
         }
+        if self.hardware_check.as_mut().is_some_and(|c| c.running()) {
+            self.last_error = Some("Wait for the hardware check, which holds the cameras and ports.".to_string());
+            return;
+        }
+        if let Some(refusal) = self.exclusivity_refusal(process_type.key(), &format!("{:?}", process_type)) {
+            self.last_error = Some(refusal);
+            return;
+        }
         let config = self.config.as_ref().unwrap().clone();
+        self.last_error = None;
+        self.launch_anyway = None;
+        // The process may need the cameras the preview holds open.
+        self.preview = None;
 
-        // Get the specific command for the process type from the loaded config.
-        let specific_command = match process_type {
-            ProcessType::Teleoperation => &config.commands.teleoperation,
-            ProcessType::Record => &config.commands.record,
-            ProcessType::Replay => &config.commands.replay,
-        };
+        // Each recording outside a session gets a dataset of its own.
+        if process_type == ProcessType::Record
+            && options.dataset.is_none()
+            && !options.resume
+            && options.command.is_none()
+        {
+            match self.new_dataset() {
+                Ok(dataset) => options.dataset = dataset,
+                Err(e) => {
+                    self.last_error = Some(e);
+                    return;
+                }
+            }
+        }
 
-        // Combine the prefix and the specific command.
-        let full_command = if !config.commands.working_directory.is_empty() {
-            format!("cd {} && {}", config.commands.working_directory, specific_command)
-        } else {
-            specific_command.to_string()
+        let (mut specific_command, mut values) = self.prepare_command(process_type, &options);
+        let edited = options.command.is_some();
+        let variant = config.commands.variant(process_type, self.selected_variant(process_type));
+        if specific_command.trim().is_empty() {
+            self.last_error = Some(format!("No {} command is configured.", process_type.key()));
+            return;
+        }
+
+        // Helper commands run only now, not for the preview drawn every frame.
+        if !placeholders::helpers(&specific_command).is_empty() {
+            let working_directory = paths::expand(variant.working_directory(&config.commands));
+            let dir = Some(std::path::Path::new(&working_directory)).filter(|d| d.is_dir());
+            if let Err(e) = placeholders::evaluate(&specific_command, dir, &mut values) {
+                self.last_error = Some(e);
+                return;
+            }
+            specific_command = placeholders::substitute(&specific_command, &values);
+        }
+
+        // Refuse to launch rather than passing a literal `{operator}` etc. to the script.
+        let unfilled = placeholders::names(&specific_command);
+        if !unfilled.is_empty() {
+            let hint = match unfilled[0].as_str() {
+                "operator" => "select an operator first",
+                "repo_id" | "dataset_path" if config.hub.is_some() => "enter a dataset name or drop a dataset folder onto the window",
+                "repo_id" | "dataset_path" => "drop a dataset folder onto the window or use the recent datasets list",
+                "checkpoint" => "no trained checkpoint was found; train a policy or check [checkpoints]",
+                "host" => "pick a robot host first",
+                _ => "check the config",
+            };
+            self.last_error = Some(format!("This command uses {{{}}}; {}.", unfilled[0], hint));
+            return;
+        }
+
+        for param in &variant.params {
+            if let Err(e) = param.check(values.get(&param.name).map_or("", String::as_str)) {
+                self.last_error = Some(e);
+                return;
+            }
+        }
+
+        // LeRobot refuses to record into an existing dataset without `--resume`.
+        if process_type == ProcessType::Record
+            && !options.resume
+            && !options.skip_dataset_check
+            && options.command.is_none()
+        {
+            if let Some((dataset, episodes)) = self.existing_dataset(&options) {
+                self.resume_offer = Some(ResumeOffer {
+                    session: false,
+                    options,
+                    repo_id: dataset.repo_id,
+                    episodes,
+                    interrupted: false,
+                });
+                return;
+            }
+        }
+
+        // `cd` into a missing directory would only fail inside the terminal.
+        let working_directory = paths::expand(variant.working_directory(&config.commands));
+        if !working_directory.is_empty() && !std::path::Path::new(&working_directory).is_dir() {
+            self.last_error = Some(format!("Working directory '{}' does not exist.", working_directory));
+            return;
+        }
+
+        // A terminal that flashes and closes is much harder to diagnose than this.
+        if !options.skip_program_check {
+            let missing = command::missing_programs(&config, variant, &specific_command);
+            if !missing.is_empty() {
+                self.last_error = Some(format!("Command not found: {}", missing.join(", ")));
+                self.launch_anyway = Some((process_type, LaunchOptions { skip_program_check: true, ..options }));
+                return;
+            }
+        }
+
+        // Without the privileges, chrt would refuse to start the command in the terminal.
+        if let Some(realtime) = &variant.realtime {
+            if let Err(e) = realtime.check() {
+                self.last_error = Some(e);
+                return;
+            }
+        }
+
+        // Two programs driving the same arm fight over it.
+        let port_locks = match locks::PortLocks::acquire(&probe::ports(&specific_command), options.skip_port_check) {
+            Ok(port_locks) => port_locks,
+            Err(problems) => {
+                self.last_error = Some(problems.join("\n"));
+                self.launch_anyway = Some((process_type, LaunchOptions { skip_port_check: true, ..options }));
+                return;
+            }
         };
 
-        // Construct a shell-script that first sources conda, then runs the command.
-        // This is the most reliable way to ensure the 'conda' command is available.
-        let conda_init_command = if let Some(conda_path) = &config.app.conda_path {
-            if !conda_path.is_empty() {
-                format!("source {}/etc/profile.d/conda.sh && ", conda_path)
-            } else {
-                "".to_string()
+        // Misaligned timestamps are not noticed until the dataset is used.
+        if process_type == ProcessType::Record && !options.skip_clock_check {
+            if let Some(clock_check) = &config.clock_check {
+                let hosts = self.clock_hosts(&config);
+                let recent = self
+                    .clock_checked
+                    .as_ref()
+                    .is_some_and(|(at, checked)| *checked == hosts && at.elapsed() < clock::CHECK_INTERVAL);
+                if !recent {
+                    let problems = clock::check(clock_check, &hosts);
+                    if !problems.is_empty() {
+                        self.last_error = Some(problems.join("\n"));
+                        self.launch_anyway = Some((process_type, LaunchOptions { skip_clock_check: true, ..options }));
+                        return;
+                    }
+                    self.clock_checked = Some((std::time::Instant::now(), hosts));
+                }
+            }
+        }
+
+        // Otherwise nobody can tell later which code recorded the dataset.
+        if process_type == ProcessType::Record {
+            if let Some(git_config) = &config.git {
+                let status = git::status(&working_directory);
+                if options.skip_git_check {
+                    self.git_accepted = status.clone().ok();
+                }
+                let accepted = options.skip_git_check
+                    || (!git_config.block && status.is_ok() && self.git_accepted.as_ref() == status.as_ref().ok());
+                let problems = match &status {
+                    Ok(status) => status.problems(git_config),
+                    Err(e) => vec![e.clone()],
+                };
+                if !accepted && !problems.is_empty() {
+                    self.last_error = Some(problems.join("\n"));
+                    if !git_config.block {
+                        self.launch_anyway = Some((process_type, LaunchOptions { skip_git_check: true, ..options }));
+                    }
+                    return;
+                }
+            }
+        }
+
+        // Secrets are passed through the environment so they never show up in
+        // the command line, the terminal or the run history.
+        let secret_env = match secrets::resolve_env(&config.secrets) {
+            Ok(env) => env,
+            Err(e) => {
+                self.last_error = Some(e);
+                return;
             }
-        } else {
-            "".to_string()
         };
 
-        let command_with_conda_init = format!("{}{}", conda_init_command, full_command);
+        let scheduled = match &variant.realtime {
+            Some(realtime) => realtime.wrap(&specific_command),
+            None => specific_command.clone(),
+        };
+        let command_with_conda_init = command::resolve(&config, variant, &scheduled, &values);
         debug!("Command with conda init: '{}'", command_with_conda_init);
-        // This command is for Linux systems with xterm.
-        // You might need to change 'xterm' to your terminal emulator of choice (e.g., 'gnome-terminal').
-        // For other OSes:
-        // - macOS: "osascript", "-e", &format!("tell app \"Terminal\" to do script \"{}\"", command_str)
-        // - Windows: "cmd", "/C", &format!("start {}", command_str)
-        // Use the terminal from config, or default to "konsole".
-        let terminal = config
-            .app
-            .terminal
-            .as_deref()
-            .unwrap_or("konsole");
-        debug!("Using terminal: '{}'", terminal);
- 
+        let terminal = command::terminal(&config);
+        // The output is also written to a log file, which the app reads to
+        // find e.g. experiment tracker links.
+        let started_at = chrono::Local::now();
+        let log_file = output::log_path(process_type, started_at);
+        if let Some(parent) = log_file.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                error!("Failed to create '{}': {}", parent.display(), e);
+            }
+        }
         // To ensure the terminal is interactive and stays open, we construct a command for `bash -ic`.
         // - The `-i` flag makes the shell interactive, which helps with real-time output and sourcing profiles.
         // - The command is wrapped in a subshell `(...)` to ensure that `read` executes even if the main command fails.
         // - `read` waits for user input (Enter key) before closing the terminal.
+        let core_watch = config.core_dumps.as_ref().map(|core_dumps| {
+            let working_directory = match working_directory.as_str() {
+                "" => std::env::current_dir().unwrap_or_default(),
+                dir => PathBuf::from(dir),
+            };
+            cores::Watch::new(core_dumps, &log_file, working_directory, started_at)
+        });
+        let command_to_capture = match &core_watch {
+            Some(watch) => watch.wrap(&command_with_conda_init),
+            None => command_with_conda_init.clone(),
+        };
+        // Without it the command reads the terminal, and prompts can only be
+        // answered there.
+        let input = keys::Input::create(&log_file).map_err(|e| error!("{}", e)).ok();
+        let command_to_capture = match &input {
+            Some(input) => input.redirect(&command_to_capture),
+            None => command_to_capture,
+        };
         let final_shell_command = format!(
             "({}); echo -e \"\\n\\n[INFO] Command finished. Press Enter to close this terminal.\"; read",
-            command_with_conda_init
+            output::capture(&command_to_capture, &log_file)
         );
         debug!("Final shell command: '{}'", final_shell_command);
-        let child = Command::new(terminal)
-            .arg("-e")
-            .arg(format!("bash -ic '{}'", final_shell_command))
-            .spawn();
+        // Within the current session phase, if any.
+        let run_span = tracing::info_span!(
+            parent: self.session.as_ref().and_then(|s| s.phase_span.id()),
+            "run",
+            process_type = ?process_type,
+            pid = tracing::field::Empty,
+            exit_code = tracing::field::Empty,
+            stopped = tracing::field::Empty,
+        );
+        let mut terminal_command =
+            command::in_terminal(&config, &format!("bash -ic {}", command::shell_quote(&final_shell_command)));
+        terminal_command
+            .envs(secret_env)
+            .envs(telemetry::traceparent(&run_span).map(|t| ("TRACEPARENT", t)))
+            // Python buffers its output when it goes to a pipe instead of the terminal.
+            .env("PYTHONUNBUFFERED", "1");
+        let child = sandbox::on_host(terminal_command).spawn();
 
         match child {
             Ok(child_handle) => { // Process spawned successfully
                 info!("Successfully spawned {:?} process with PID: {}", process_type, child_handle.id());
+                run_span.record("pid", child_handle.id());
+                self.run_span = Some(run_span);
+                let dataset = dataset::from_command(&specific_command).or(options.dataset);
+                self.stall = match (process_type, &config.stall_check, &dataset) {
+                    (ProcessType::Record, Some(stall_check), Some(dataset)) => {
+                        Some(stall::StallWatch::new(stall_check, dataset.local_path()))
+                    }
+                    _ => None,
+                };
+                self.port_locks = Some(port_locks);
+                self.saves = match (process_type, &dataset) {
+                    (ProcessType::Record, Some(dataset)) => Some(saves::SaveWatch::new(dataset.local_path())),
+                    _ => None,
+                };
+                if let Some(dataset) = dataset {
+                    dataset::remember(&mut self.state.recent_datasets, dataset);
+                    self.state.save();
+                }
+                self.current_run = Some(self.history.start(
+                    process_type,
+                    self.user.clone(),
+                    self.operator().map(str::to_string),
+                    command_with_conda_init,
+                    edited,
+                    Some(log_file.clone()),
+                ));
+                if let Some(index) = self.current_run {
+                    self.snapshots.start(index, &config, variant);
+                }
+                self.output = Some(output::OutputTail::new(log_file));
+                self.episode_clock.start();
+                self.core_watch = core_watch;
+                self.input = input;
+                self.frames = match process_type {
+                    ProcessType::Record => frames::FrameMonitor::new(&config.frames, &specific_command)
+                        .map_err(|e| error!("{}", e))
+                        .ok(),
+                    _ => None,
+                };
+                self.faults = match process_type {
+                    ProcessType::Record => {
+                        faults::FaultDetector::new(&config.faults).map_err(|e| error!("{}", e)).ok().flatten()
+                    }
+                    _ => None,
+                };
+                self.publish(StatusEvent::ProcessStarted { process_type, pid: child_handle.id() });
                 self.child_process = Some((child_handle, process_type));
             }
             Err(e) => {
                 error!("Failed to spawn {:?} process: {}", process_type, e);
-                // Consider showing this error in the GUI for the user.
+                self.last_error = Some(format!("Failed to start terminal '{}': {}", terminal, e));
+            }
+        }
+    }
+
+    /// The command `options` would launch for `process_type`, with the dataset
+    /// and resume overrides applied and the known placeholders filled in, plus
+    /// the placeholder values used.
+    fn prepare_command(&self, process_type: ProcessType, options: &LaunchOptions) -> (String, HashMap<String, String>) {
+        let mut values = self.placeholder_values();
+        if let Some(dataset) = &options.dataset {
+            values.insert("repo_id".to_string(), dataset.repo_id.clone());
+            values.insert("dataset_path".to_string(), dataset.local_path().display().to_string());
+        }
+        let Ok(config) = &self.config else { return (String::new(), values) };
+        let variant = config.commands.variant(process_type, self.selected_variant(process_type));
+        for param in &variant.params {
+            values.insert(param.name.clone(), self.param_value(process_type, param));
+        }
+        values.extend(options.values.iter().cloned());
+        if let Some(command) = &options.command {
+            return (placeholders::substitute(command, &values), values);
+        }
+        let mut specific_command = variant.command.clone();
+        if let Some(dataset) = &options.dataset {
+            if let Some(command) = command::set_flag(&specific_command, dataset::REPO_ID_FLAG, &dataset.repo_id) {
+                specific_command = command;
+            }
+            if let Some(root) = &dataset.root {
+                specific_command = command::set_flag(&specific_command, dataset::ROOT_FLAG, root)
+                    .or_else(|| command::insert_flag_after(&specific_command, dataset::REPO_ID_FLAG, dataset::ROOT_FLAG, root))
+                    .unwrap_or(specific_command);
+            }
+        }
+        if options.resume {
+            specific_command = command::set_or_add_flag(&specific_command, "--resume", "true");
+        }
+        for (flag, value) in &options.flags {
+            specific_command = command::set_or_add_flag(&specific_command, flag, value);
+        }
+        (placeholders::substitute(&specific_command, &values), values)
+    }
+
+    /// Shows the full shell command a plain launch of `process_type` would run,
+    /// including conda activation and `cd`, to catch config mistakes early.
+    fn command_preview_ui(&self, ui: &mut egui::Ui, process_type: ProcessType) {
+        let Ok(config) = &self.config else { return };
+        let options = self.default_launch_options(process_type);
+        let (command, values) = self.prepare_command(process_type, &options);
+        let variant = config.commands.variant(process_type, self.selected_variant(process_type));
+        let resolved = command::resolve(config, variant, &command, &values);
+        ui.label(egui::RichText::new(resolved.trim()).monospace());
+        let unfilled = placeholders::names(&command);
+        if !unfilled.is_empty() {
+            let names: Vec<String> = unfilled.iter().map(|n| format!("{{{}}}", n)).collect();
+            ui.colored_label(egui::Color32::YELLOW, format!("Not filled yet: {}", names.join(", ")));
+        }
+        if let Some(naming) = config.dataset_naming.as_ref().filter(|_| process_type == ProcessType::Record) {
+            if options.dataset.is_none() {
+                ui.weak(format!("Records into a new dataset named after '{}'.", naming.template));
             }
         }
+        if !config.secrets.is_empty() {
+            let names: Vec<&str> = config.secrets.keys().map(String::as_str).collect();
+            ui.weak(format!("Secrets set in the environment: {}", names.join(", ")));
+        }
+        ui.weak("Right-click to edit the command for this run.");
     }
 
-    /// Kills the running process.
-    fn kill_process(&mut self) {
-        if let Some((mut child, _)) = self.child_process.take() {
+    /// Kills the running process, auditing why.
+    #[tracing::instrument(skip(self))]
+    fn kill_process(&mut self, stop: Stop) {
+        if let Some((mut child, process_type)) = self.child_process.take() {
             info!("Attempting to kill process with PID: {}", child.id());
-            if let Err(e) = child.kill() {
+            let pid = child.id();
+            let event = match stop {
+                Stop::Requested(reason) => AuditEvent::Stopped { process_type, pid, reason: reason.to_string() },
+                Stop::Emergency(reason) => AuditEvent::EmergencyStop { process_type, pid, reason: reason.to_string() },
+            };
+            self.audit.record(&self.user, self.operator(), event);
+            // Stopped processes would not react to the terminal going away.
+            if self.paused {
+                let _ = process::signal_children(child.id(), "CONT");
+            }
+            if let Err(e) = sandbox::kill(&mut child) {
                 error!("Failed to kill process with PID {}: {}", child.id(), e);
             }
             // We can also wait for the process to ensure it's cleaned up,
             // but for killing it, this is often sufficient.
+            self.finish_run(true);
         }
     }
-}
 
-impl eframe::App for MyApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Teleop Record Replay");
-            ui.separator();
+    /// Whether nothing is running or about to launch a process.
+    fn idle(&self) -> bool {
+        self.child_process.is_none() && self.session.is_none() && self.sweep.is_none() && self.script.is_none()
+    }
 
-            // Display an error message if the configuration failed to load.
-            if let Err(e) = &self.config {
-                ui.colored_label(egui::Color32::RED, e);
-                return;
-            }
+    /// The variant the working directory and terminal buttons are for: that
+    /// of the running process, or else the selected Record variant.
+    fn shell_variant<'a>(&self, config: &'a Config) -> &'a config::Variant {
+        let process_type = self.child_process.as_ref().map_or(ProcessType::Record, |(_, p)| *p);
+        config.commands.variant(process_type, self.selected_variant(process_type))
+    }
 
-            if let Some((child, process_type)) = &mut self.child_process {
-                // Check if the process has finished.
-                match child.try_wait() {
-                    Ok(Some(_status)) => self.child_process = None, // Process finished.
-                    Ok(None) => { // Process is still running.
-                        // Process is still running.
-                        ui.label(format!("{:?} is running...", process_type));
-                        if ui.button("Stop").clicked() {
-                            self.kill_process();
-                        }
-                    }
-                    // An error occurred while trying to check the process status.
-                    // This could indicate the process is no longer valid or other system issues.
-                    Err(e) => {
-                        eprintln!("Error waiting for child process: {}", e);
-                        self.child_process = None;
+    /// The selected operator, if any.
+    fn operator(&self) -> Option<&str> {
+        Some(self.state.operator.trim()).filter(|o| !o.is_empty())
+    }
+
+    /// Index of the command variant selected for `process_type`; the first
+    /// one unless another was picked in the GUI.
+    fn selected_variant(&self, process_type: ProcessType) -> usize {
+        let Ok(config) = &self.config else { return 0 };
+        let Some(name) = self.state.variants.get(process_type.key()) else { return 0 };
+        config
+            .commands
+            .variants(process_type)
+            .iter()
+            .position(|v| v.name == *name)
+            .unwrap_or(0)
+    }
+
+    /// Selects the variant named `name` for `process_type` and remembers it.
+    fn select_variant(&mut self, process_type: ProcessType, name: String) {
+        self.state.variants.insert(process_type.key().to_string(), name);
+        self.state.save();
+    }
+
+    /// The value entered for `param` of `process_type`, or its default.
+    fn param_value(&self, process_type: ProcessType, param: &params::Param) -> String {
+        self.state
+            .params
+            .get(&format!("{}.{}", process_type.key(), param.name))
+            .cloned()
+            .unwrap_or_else(|| param.default_value())
+    }
+
+    /// Shows the form for the parameters of the selected variant of `process_type`.
+    fn params_ui(&mut self, ui: &mut egui::Ui, config: &Config, process_type: ProcessType) {
+        let variant = config.commands.variant(process_type, self.selected_variant(process_type));
+        if variant.params.is_empty() {
+            return;
+        }
+        ui.push_id(process_type.key(), |ui| {
+            egui::Grid::new("params_grid").num_columns(2).show(ui, |ui| {
+                for param in &variant.params {
+                    let mut value = self.param_value(process_type, param);
+                    ui.label(param.label());
+                    if param.ui(ui, &mut value) {
+                        self.state.params.insert(format!("{}.{}", process_type.key(), param.name), value);
+                        self.state.save();
                     }
+                    ui.end_row();
                 }
-            } else {
-                // No process is running, show the main buttons. We'll use a vertical layout
-                // and add some spacing to make the UI look clean.
-                ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
-                    ui.add_space(20.0); // Add some space from the top separator
+            });
+        });
+    }
 
-                    // Define a larger font and size for the buttons
-                    let button_font = egui::FontId::proportional(20.0);
-                    let button_size = egui::vec2(220.0, 50.0);
+    /// Values for the placeholders that can appear in commands.
+    fn placeholder_values(&self) -> HashMap<String, String> {
+        let mut values = HashMap::new();
+        if let Some(operator) = self.operator() {
+            values.insert("operator".to_string(), operator.to_string());
+        }
+        if let Some(repo_id) = self.composed_repo_id() {
+            values.insert("dataset_path".to_string(), dataset::local_path(&repo_id, None).display().to_string());
+            values.insert("repo_id".to_string(), repo_id);
+        }
+        if let Some((dataset, _)) = &self.active_dataset {
+            values.insert("repo_id".to_string(), dataset.repo_id.clone());
+            values.insert("dataset_path".to_string(), dataset.local_path().display().to_string());
+        }
+        if let Some(checkpoint) = self.selected_checkpoint() {
+            values.insert("checkpoint".to_string(), checkpoint.value.clone());
+        }
+        if !self.state.host.is_empty() {
+            values.insert("host".to_string(), self.state.host.clone());
+        }
+        values.insert("fps".to_string(), self.state.fps().to_string());
+        values
+    }
+
+    /// The repo id composed in the GUI, if `[hub]` is configured and it is valid.
+    fn composed_repo_id(&self) -> Option<String> {
+        self.config.as_ref().ok()?.hub.as_ref()?;
+        let repo_id = format!("{}/{}", self.state.repo_namespace, self.state.repo_name.trim());
+        dataset::check_repo_id(&repo_id).ok().map(|()| repo_id)
+    }
 
-                    // --- Teleoperation Button ---
-                    let teleop_button = egui::Button::new(
-                        egui::RichText::new("Teleoperation").font(button_font.clone()),
-                    )
-                    .min_size(button_size);
+    /// The checkpoint filled into `{checkpoint}`: the one picked in the GUI if
+    /// it is still there, otherwise the newest.
+    fn selected_checkpoint(&self) -> Option<&checkpoints::Checkpoint> {
+        self.checkpoints
+            .iter()
+            .find(|c| self.state.checkpoint.as_ref() == Some(&c.value))
+            .or(self.checkpoints.first())
+    }
 
-                    if ui.add(teleop_button).clicked() {
-                        self.spawn_process(ProcessType::Teleoperation);
-                    }
-                    ui.add_space(15.0); // Spacing between buttons
+    /// Starts the sweep named `name` from the config.
+    fn start_sweep(&mut self, name: &str) {
+        let Ok(config) = &self.config else { return };
+        let Some(sweep_config) = config.sweeps.iter().find(|s| s.name == name) else { return };
+        let Some(process_type) = ProcessType::from_key(&sweep_config.process) else { return };
+        let sweep = sweep::Sweep::new(sweep_config, process_type);
+        info!("Starting sweep '{}' with {} run(s)", sweep.name, sweep.runs.len());
+        self.sweep = Some(sweep);
+    }
 
-                    // --- Record Button ---
-                    let record_button =
-                        egui::Button::new(egui::RichText::new("Record").font(button_font.clone()))
-                            .min_size(button_size);
+    /// Starts the script named `name` from the config.
+    fn start_script(&mut self, name: &str) {
+        let Ok(config) = &self.config else { return };
+        let Some(script_config) = config.scripts.iter().find(|s| s.name == name) else { return };
+        match scripts::RunningScript::start(script_config) {
+            Ok(script) => self.script = Some(script),
+            Err(e) => self.last_error = Some(e),
+        }
+    }
 
-                    if ui.add(record_button).clicked() {
-                        self.spawn_process(ProcessType::Record);
+    /// Serves the running script: reports how the run it waits for ended,
+    /// and launches the next one it asks for once nothing else runs.
+    fn advance_script(&mut self) {
+        let Some(script) = &mut self.script else { return };
+        script.poll();
+        if self.child_process.is_none() {
+            if let Some((index, reply)) = script.run.take() {
+                let result = self.history.runs().get(index).map(scripts::RunResult::of);
+                let _ = reply.send(result.ok_or_else(|| "The run is missing from the history".to_string()));
+            }
+            if let Some((_, reply)) = script.ready.take() {
+                let _ = reply.send(Err("The process ended before printing its ready line".to_string()));
+            }
+        }
+        let Some(request) = script.next_request() else { return };
+        let (process_type, values, reply) = match request {
+            scripts::Request::Prompt { question, confirm, reply } => {
+                script.prompt = Some(scripts::Prompt::new(question, confirm, reply));
+                return;
+            }
+            scripts::Request::Wait { reply } => {
+                match script.launched {
+                    Some(index) if self.child_process.is_some() && self.current_run == Some(index) => {
+                        script.run = Some((index, reply));
                     }
-                    ui.add_space(15.0); // Spacing between buttons
+                    Some(index) => {
+                        let result = self.history.runs().get(index).map(scripts::RunResult::of);
+                        let _ = reply.send(result.ok_or_else(|| "The run is missing from the history".to_string()));
+                    }
+                    None => {
+                        let _ = reply.send(Err("The script has not started a process".to_string()));
+                    }
+                }
+                return;
+            }
+            request if self.child_process.is_some() || self.session.is_some() || self.sweep.is_some() => {
+                script.defer(request);
+                return;
+            }
+            scripts::Request::Run { process_type, values, reply } => {
+                (process_type, values, scripts::Reply::Ended(reply))
+            }
+            scripts::Request::Start { process_type, values, reply } => {
+                let ready_pattern = self.config.as_ref().ok().and_then(|config| {
+                    let variant = config.commands.variant(process_type, self.selected_variant(process_type));
+                    variant.ready_pattern.as_deref().and_then(|p| regex::Regex::new(p).ok())
+                });
+                let Some(pattern) = ready_pattern else {
+                    let _ = reply.send(Err(format!("The {:?} command has no ready_pattern", process_type)));
+                    return;
+                };
+                (process_type, values, scripts::Reply::Ready(pattern, reply))
+            }
+        };
+        let (flags, values) = values.into_iter().partition(|(key, _)| key.starts_with("--"));
+        let options = LaunchOptions { flags, values, ..self.default_launch_options(process_type) };
+        self.spawn_process(process_type, options);
+        let error = self.last_error.take();
+        let dataset_exists = self.resume_offer.take().is_some();
+        let Some(script) = &mut self.script else { return };
+        match self.current_run.filter(|_| self.child_process.is_some()) {
+            Some(index) => {
+                script.launched = Some(index);
+                match reply {
+                    scripts::Reply::Ended(reply) => script.run = Some((index, reply)),
+                    scripts::Reply::Ready(pattern, reply) => script.ready = Some((pattern, reply)),
+                }
+            }
+            None => {
+                let reason = match error {
+                    Some(e) => e,
+                    None if dataset_exists => "the dataset already exists".to_string(),
+                    None => "not launched".to_string(),
+                };
+                reply.fail(format!("{:?} was not launched: {}", process_type, reason));
+            }
+        }
+    }
 
-                    // --- Replay Button ---
-                    let replay_button =
-                        egui::Button::new(egui::RichText::new("Replay").font(button_font))
-                            .min_size(button_size);
+    /// Shows the scripts of the config, and what the running one printed and asks.
+    fn scripts_ui(&mut self, ui: &mut egui::Ui, config: &Config) {
+        let idle = self.idle();
+        let mut start = None;
+        for script in &config.scripts {
+            if ui.add_enabled(idle, egui::Button::new(format!("Run '{}'", script.name))).clicked() {
+                start = Some(script.name.clone());
+            }
+        }
+        if let Some(name) = start {
+            self.start_script(&name);
+        }
+    }
 
-                    if ui.add(replay_button).clicked() {
-                        self.spawn_process(ProcessType::Replay);
+    /// Shows the running script: what it printed, its question for the
+    /// operator, and how it ended.
+    fn script_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(script) = &mut self.script else { return };
+        let status = match &script.outcome {
+            None => "running".to_string(),
+            Some(Ok(())) => "finished".to_string(),
+            Some(Err(e)) => format!("failed: {}", e),
+        };
+        ui.heading(format!("Script '{}': {}", script.name, status));
+        egui::ScrollArea::vertical().max_height(150.0).stick_to_bottom(true).show(ui, |ui| {
+            for line in &script.log {
+                ui.monospace(line);
+            }
+        });
+        let mut answer = None;
+        if let Some(prompt) = &mut script.prompt {
+            ui.label(egui::RichText::new(&prompt.question).size(18.0));
+            ui.horizontal(|ui| {
+                if prompt.confirm {
+                    if ui.button("Yes").clicked() {
+                        answer = Some(Some(String::new()));
                     }
-                });
+                    if ui.button("No").clicked() {
+                        answer = Some(None);
+                    }
+                } else {
+                    let input = ui.text_edit_singleline(&mut prompt.answer);
+                    let entered = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if ui.button("OK").clicked() || entered {
+                        answer = Some(Some(prompt.answer.clone()));
+                    }
+                    if ui.button("Cancel").clicked() {
+                        answer = Some(None);
+                    }
+                }
+            });
+        }
+        if let Some(answer) = answer {
+            if let Some(prompt) = script.prompt.take() {
+                prompt.answer(answer);
+            }
+        }
+        let mut close = false;
+        ui.horizontal(|ui| {
+            if script.finished() {
+                close = ui.button("Close").clicked();
+            } else if ui
+                .button("Stop script")
+                .on_hover_text("The current run carries on; stop it with its Stop button")
+                .clicked()
+            {
+                script.stop();
             }
         });
+        if !script.finished() {
+            // Requests of the script are served from the GUI update.
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(200));
+        }
+        if close {
+            self.script = None;
+        }
     }
-}
 
-/// Command-line arguments for the application.
-#[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-struct Args {
-    /// Path to the configuration TOML file.
-    #[arg(short, long, default_value = "config.toml")]
-    config: PathBuf,
-}
+    /// Launches the next run of the sweep once the previous one is done.
+    fn advance_sweep(&mut self) {
+        if self.child_process.is_some() {
+            return;
+        }
+        let Some(sweep) = &mut self.sweep else { return };
+        let process_type = sweep.process_type;
+        let Some(run) = sweep.next_pending() else { return };
+        let (flags, values) = run.values.iter().cloned().partition(|(key, _)| key.starts_with("--"));
+        let options = LaunchOptions { flags, values, ..self.default_launch_options(process_type) };
+        self.spawn_process(process_type, options);
+        let launched = self.child_process.is_some();
+        let error = self.last_error.take();
+        let dataset_exists = self.resume_offer.take().is_some();
+        let Some(run) = self.sweep.as_mut().and_then(|s| s.next_pending()) else { return };
+        if launched {
+            run.status = sweep::RunStatus::Running;
+            run.history_index = self.current_run;
+        } else {
+            let reason = match error {
+                Some(e) => e,
+                None if dataset_exists => "the dataset already exists".to_string(),
+                None => "not launched".to_string(),
+            };
+            run.status = sweep::RunStatus::Failed(reason);
+        }
+    }
 
-fn main() -> Result<(), eframe::Error> {
-    // Initialize the logger. This allows debug messages to be printed to the console.
-    env_logger::init();
+    /// Tells the outside world about `event`, if configured to.
+    fn publish(&self, event: StatusEvent) {
+        let Ok(config) = &self.config else { return };
+        if config.mqtt.is_none() && config.ros.is_none() {
+            return;
+        }
+        let message = status::StatusMessage {
+            timestamp: chrono::Local::now(),
+            host: status::hostname(),
+            operator: self.operator(),
+            event: &event,
+        };
+        let payload = match serde_json::to_string(&message) {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("Failed to serialize {:?}: {}", event, e);
+                return;
+            }
+        };
+        if let Some(mqtt) = &config.mqtt {
+            // The process state is retained so dashboards show it right away.
+            mqtt::publish(mqtt, event.topic(), &payload, event.topic() == "process");
+        }
+        if let Some(ros) = &config.ros {
+            ros::publish(ros, &payload);
+        }
+    }
 
-    // Parse command-line arguments.
-    let args = Args::parse();
+    /// Links the core dumps collected since the last frame from their runs.
+    fn poll_core_dumps(&mut self, ctx: &egui::Context) {
+        for (index, path) in self.cores.poll() {
+            self.history.set_core_dump(index, path);
+        }
+        if self.cores.collecting() {
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        }
+    }
+
+    /// Stores the snapshots taken since the last frame with their runs.
+    fn poll_snapshots(&mut self, ctx: &egui::Context) {
+        for (index, snapshot) in self.snapshots.poll() {
+            self.history.set_snapshot(index, snapshot);
+        }
+        if self.snapshots.taking() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(500));
+        }
+    }
+
+    /// Adds the metrics read by plugin parsers to their runs.
+    fn poll_plugins(&mut self, ctx: &egui::Context) {
+        for (index, metrics) in self.plugins.poll_parsed() {
+            self.history.add_metrics(index, metrics);
+        }
+        if self.plugins.busy() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(500));
+        }
+    }
+
+    /// Starts, restarts or stops the controller bridge as its settings ask.
+    fn poll_controller(&mut self, ctx: &egui::Context) {
+        let settings = &self.state.controller;
+        if !settings.enabled || settings.device.is_empty() {
+            self.controller = None;
+            return;
+        }
+        if self.controller.as_ref().is_some_and(|b| b.device != settings.device) {
+            self.controller = None;
+        }
+        let bridge = self.controller.get_or_insert_with(|| controller::Bridge::start(settings, ctx));
+        if bridge.poll(&mut self.state.controller) {
+            bridge.update(&self.state.controller);
+            self.state.save();
+        }
+    }
+
+    /// Keeps the ROS e-stop subscription running and stops everything when
+    /// it fires.
+    fn poll_estop(&mut self, ctx: &egui::Context) {
+        let Ok(config) = self.config.clone() else { return };
+        let Some((ros_config, topic)) = config.ros.as_ref().and_then(|r| Some((r, r.estop_topic.as_ref()?))) else {
+            return;
+        };
+        let estop = self.estop.get_or_insert_with(|| ros::EstopListener::start(ros_config, topic, ctx.clone()));
+        let Ok(listener) = estop else { return };
+        let triggered = listener.triggered();
+        if !listener.running() {
+            error!("Stopped listening for emergency stops on '{}'", topic);
+            *estop = Err(format!("ros2 topic echo {} exited", topic));
+        }
+        if triggered {
+            self.emergency_stop(&format!("Emergency stop from ROS topic '{}'.", topic));
+        }
+    }
+
+    /// Stops the running process and whatever would launch the next one.
+    fn emergency_stop(&mut self, reason: &str) {
+        info!("{}", reason);
+        if let Some(sweep) = &mut self.sweep {
+            sweep.abort();
+        }
+        if let Some(script) = &mut self.script {
+            script.stop();
+        }
+        self.end_session(Stop::Emergency(reason));
+        self.kill_process(Stop::Emergency(reason));
+        self.last_error = Some(reason.to_string());
+    }
+
+    /// Shows whether emergency stops from ROS are being listened for.
+    fn estop_ui(&self, ui: &mut egui::Ui) {
+        match &self.estop {
+            Some(Ok(listener)) => {
+                ui.weak(format!("E-stop: listening on ROS topic '{}'", listener.topic));
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, format!("E-stop not active: {}", e));
+            }
+            None => {}
+        }
+    }
+
+    /// Starts the status page server once `[web]` is configured; called every
+    /// frame. The server reads the run history itself, so there is nothing to
+    /// drain here, and a failed start is kept to be shown rather than retried.
+    fn poll_web(&mut self) {
+        let Ok(config) = &self.config else { return };
+        let Some(web_config) = &config.web else { return };
+        if self.web.is_none() {
+            self.web = Some(web::Server::start(web_config, History::default_path()));
+        }
+    }
+
+    /// Shows where the status page is served.
+    fn web_ui(&self, ui: &mut egui::Ui) {
+        match &self.web {
+            Some(Ok(server)) => {
+                let ip = server.address.ip();
+                let host = if ip.is_unspecified() { status::hostname() } else { ip.to_string() };
+                ui.weak(format!("Status page: http://{}:{}/", host, server.address.port()));
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+            None => {}
+        }
+    }
+
+    /// Starts the keyword spotter once `[voice]` is configured, or stops it
+    /// while voice commands are switched off; called every frame. Acts on the
+    /// last command heard since the previous frame, and keeps an error to
+    /// show if the spotter has exited.
+    fn poll_voice(&mut self, ctx: &egui::Context) {
+        let Ok(config) = self.config.clone() else { return };
+        let Some(voice_config) = &config.voice else { return };
+        if self.voice_off {
+            self.voice = None;
+            return;
+        }
+        let voice = self.voice.get_or_insert_with(|| voice::VoiceListener::start(voice_config, ctx.clone()));
+        let Ok(listener) = voice else { return };
+        let heard = listener.heard();
+        if !listener.running() {
+            error!("Stopped listening for voice commands");
+            *voice = Err("the keyword spotter exited".to_string());
+        }
+        match heard {
+            Some(voice::VoiceCommand::Start) => self.voice_start(),
+            Some(voice::VoiceCommand::Stop) => self.voice_stop(),
+            None => {}
+        }
+    }
+
+    /// Starts recording on a voice command: ends the reset or countdown of a
+    /// recording session, or launches Record if nothing runs.
+    fn voice_start(&mut self) {
+        info!("Voice command: start");
+        if let Some(session) = &mut self.session {
+            if matches!(session.phase, session::Phase::Reset | session::Phase::Countdown) {
+                session.ready();
+            }
+        } else if self.idle() {
+            self.spawn_process(ProcessType::Record, self.default_launch_options(ProcessType::Record));
+        }
+    }
+
+    /// Stops on a voice command: Record ends its episode as if Esc was
+    /// pressed, keeping what was recorded; anything else is stopped.
+    fn voice_stop(&mut self) {
+        info!("Voice command: stop");
+        match &self.child_process {
+            Some((_, ProcessType::Record)) => {
+                let sent = match &mut self.input {
+                    Some(input) => input.send(keys::ESCAPE),
+                    None => Err("The process reads its terminal; stopping it instead.".to_string()),
+                };
+                if let Err(e) = sent {
+                    self.last_error = Some(e);
+                    self.kill_process(Stop::Requested("voice command"));
+                }
+            }
+            Some(_) => self.kill_process(Stop::Requested("voice command")),
+            None => {}
+        }
+    }
+
+    /// Shows whether voice commands are listened for, and lets the operator
+    /// turn them off, e.g. while talking to someone.
+    fn voice_ui(&mut self, ui: &mut egui::Ui, config: &Config) {
+        let Some(voice_config) = &config.voice else { return };
+        ui.horizontal(|ui| {
+            let mut on = !self.voice_off;
+            if ui.checkbox(&mut on, "Voice commands").changed() {
+                self.voice_off = !on;
+            }
+            match &self.voice {
+                Some(Ok(listener)) => {
+                    let quoted =
+                        |phrases: &[String]| phrases.iter().map(|p| format!("'{}'", p)).collect::<Vec<_>>().join(" or ");
+                    ui.weak(format!("Say {} or {}", quoted(&voice_config.start), quoted(&voice_config.stop)));
+                    if let Some(last) = listener.last {
+                        ui.weak(format!("(heard: {:?})", last));
+                    }
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(egui::Color32::RED, format!("Not listening: {}", e));
+                }
+                None => {}
+            }
+        });
+    }
+
+    /// Shows the A/B comparison form and starts the comparison it asks for.
+    fn compare_ui(&mut self, ui: &mut egui::Ui, config: &Config) {
+        let idle = self.idle();
+        let Some((process_type, [a, b])) = self.compare_panel.show(ui, config, &self.checkpoints, idle) else { return };
+        let mut sweep = sweep::Sweep::from_grid("A/B comparison".to_string(), process_type, vec![vec![a], vec![b]]);
+        sweep.comparison = true;
+        self.sweep = Some(sweep);
+    }
+
+    /// Lists the sweeps from the config with a button to run each.
+    fn sweeps_ui(&mut self, ui: &mut egui::Ui, config: &Config) {
+        let idle = self.idle();
+        let mut start = None;
+        for sweep in &config.sweeps {
+            let label = format!("Run '{}' ({} {} run(s))", sweep.name, sweep.grid().len(), sweep.process);
+            if ui.add_enabled(idle, egui::Button::new(label)).clicked() {
+                start = Some(sweep.name.clone());
+            }
+        }
+        if let Some(name) = start {
+            self.start_sweep(&name);
+        }
+    }
+
+    /// Shows the runs of the sweep in progress with their status and results.
+    fn sweep_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(sweep) = &mut self.sweep else { return };
+        let finished = sweep.runs.iter().filter(|r| !matches!(r.status, sweep::RunStatus::Pending | sweep::RunStatus::Running)).count();
+        let mut close = false;
+        ui.heading(format!("Sweep '{}': {} of {} run(s) done", sweep.name, finished, sweep.runs.len()));
+        egui::Grid::new("sweep_grid").num_columns(4).striped(true).show(ui, |ui| {
+            for header in ["#", "Values", "Status", "Result"] {
+                ui.strong(header);
+            }
+            ui.end_row();
+            for (index, run) in sweep.runs.iter().enumerate() {
+                if sweep.comparison {
+                    ui.label(if index == 0 { "A" } else { "B" });
+                } else {
+                    ui.label((index + 1).to_string());
+                }
+                let values: Vec<String> = run.values.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                ui.label(values.join(" "));
+                ui.label(run.status.describe());
+                let result = run
+                    .history_index
+                    .and_then(|i| self.history.runs().get(i))
+                    .and_then(|r| r.evaluation.as_ref())
+                    .map(|e| format!("{}/{} ({:.1} %)", e.successes, e.episodes(), e.success_rate()));
+                ui.label(result.unwrap_or_default());
+                ui.end_row();
+            }
+        });
+        let results = evaluation::aggregate(
+            sweep
+                .runs
+                .iter()
+                .filter_map(|r| r.history_index)
+                .filter_map(|i| self.history.runs().get(i)?.evaluation.as_ref()),
+        );
+        if sweep.comparison && sweep.done() {
+            let runs: Vec<_> = sweep.runs.iter().filter_map(|r| self.history.runs().get(r.history_index?)).collect();
+            if let [a, b] = runs[..] {
+                compare::diff_ui(ui, a, b);
+            }
+        } else if !results.is_empty() {
+            ui.label("Aggregated results:");
+            evaluation::results_table(ui, "sweep_results_grid", &results);
+        }
+        if !sweep.done() {
+            // The next run is launched from the GUI update.
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(500));
+        }
+        ui.horizontal(|ui| {
+            if sweep.done() {
+                close = ui.button("Close").clicked();
+            } else if ui
+                .button("Abort remaining runs")
+                .on_hover_text("The current run carries on; stop it with its Stop button")
+                .clicked()
+            {
+                sweep.abort();
+            }
+        });
+        if close {
+            self.sweep = None;
+        }
+    }
+
+    /// Shows the checkpoint dropdown if the selected command of `process_type`
+    /// uses `{checkpoint}`.
+    fn checkpoint_ui(&mut self, ui: &mut egui::Ui, config: &Config, process_type: ProcessType, width: f32) {
+        let variant = config.commands.variant(process_type, self.selected_variant(process_type));
+        if !placeholders::names(&variant.command).iter().any(|n| n == "checkpoint") {
+            return;
+        }
+        let selected = self.selected_checkpoint().map(|c| c.value.clone());
+        let mut chosen = None;
+        ui.horizontal(|ui| {
+            let text = self.selected_checkpoint().map_or("No checkpoints found", |c| c.label.as_str());
+            egui::ComboBox::from_id_source(("checkpoint", process_type.key()))
+                .width(width)
+                .selected_text(text)
+                .show_ui(ui, |ui| {
+                    for checkpoint in &self.checkpoints {
+                        if ui.selectable_label(selected.as_ref() == Some(&checkpoint.value), &checkpoint.label).clicked() {
+                            chosen = Some(checkpoint.value.clone());
+                        }
+                    }
+                })
+                .response
+                .on_hover_text(format!("Checkpoints in '{}'", checkpoints::directory(config).display()));
+            if ui.small_button("Refresh").on_hover_text("Look for new checkpoints").clicked() {
+                self.scan_checkpoints();
+            }
+        });
+        if let Some(value) = chosen {
+            self.state.checkpoint = Some(value);
+            self.state.save();
+        }
+    }
+
+    /// Shows the operator selector: a free text field plus a menu of the
+    /// operators listed in the config.
+    fn operator_ui(&mut self, ui: &mut egui::Ui, operators: &[String]) {
+        ui.horizontal(|ui| {
+            ui.label("Operator:");
+            let mut changed = ui
+                .add(egui::TextEdit::singleline(&mut self.state.operator).hint_text("name"))
+                .lost_focus();
+            if !operators.is_empty() {
+                egui::ComboBox::from_id_source("operator_select")
+                    .selected_text("Select")
+                    .show_ui(ui, |ui| {
+                        for name in operators {
+                            if ui.selectable_label(self.state.operator == *name, name).clicked() {
+                                self.state.operator = name.clone();
+                                changed = true;
+                            }
+                        }
+                    });
+            }
+            if changed {
+                self.state.save();
+            }
+        });
+    }
+
+    /// The machines whose clocks are compared before recording: the robot
+    /// host and those listed in `[clock_check]`.
+    fn clock_hosts(&self, config: &Config) -> Vec<String> {
+        let mut hosts = Vec::new();
+        if config.hosts.is_some() && !self.state.host.is_empty() {
+            hosts.push(self.state.host.clone());
+        }
+        for host in config.clock_check.iter().flat_map(|c| &c.hosts) {
+            if !hosts.contains(host) {
+                hosts.push(host.clone());
+            }
+        }
+        hosts
+    }
+
+    /// The app's own log level and file, changed on the fly until the config
+    /// is reloaded.
+    fn logging_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Level:");
+            let current = self.log_control.level();
+            egui::ComboBox::from_id_source("log_level").selected_text(&current).show_ui(ui, |ui| {
+                for level in telemetry::LEVELS {
+                    if ui.selectable_label(current == level, level).clicked() {
+                        if let Err(e) = self.log_control.set_level(level) {
+                            self.last_error = Some(e);
+                        }
+                    }
+                }
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Also write to:");
+            ui.add(egui::TextEdit::singleline(&mut self.log_file_edit).hint_text("log file (terminal only if empty)"));
+            if ui.button("Apply").clicked() {
+                let path = self.log_file_edit.trim();
+                let path = (!path.is_empty()).then(|| PathBuf::from(paths::expand(path)));
+                if let Err(e) = self.log_control.set_file(path.as_deref()) {
+                    self.last_error = Some(e);
+                }
+            }
+        });
+        ui.separator();
+        ui.horizontal(|ui| {
+            let hover = "Zip the logs, recent run output, config, run history and platform \
+                         info for a bug report, with credentials redacted";
+            if ui.button("Export diagnostics").on_hover_text(hover).clicked() {
+                self.export_diagnostics();
+            }
+            match &self.diagnostics_export {
+                Some(Ok(path)) => {
+                    ui.label(path.display().to_string());
+                    if ui.button("Open folder").clicked() {
+                        self.open_folder(Some(diagnostics::default_dir()));
+                    }
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+                None => {}
+            }
+        });
+    }
+
+    /// Writes a diagnostics bundle for a bug report.
+    fn export_diagnostics(&mut self) {
+        let sources = diagnostics::Sources {
+            config_path: &self.config_path,
+            history: &self.history,
+            app_logs: self.log_control.files(),
+        };
+        let result = diagnostics::export(&sources, &diagnostics::default_dir());
+        match &result {
+            Ok(path) => info!("Wrote diagnostics to '{}'", path.display()),
+            Err(e) => error!("{}", e),
+        }
+        self.diagnostics_export = Some(result);
+    }
+
+    /// Shows the rate filled into `{fps}`, so teleoperation, recording and
+    /// replay run at the same rate.
+    fn fps_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Control rate:");
+            let mut fps = self.state.fps();
+            let response = ui.add(egui::DragValue::new(&mut fps).clamp_range(1..=1000).suffix(" fps"));
+            if response.changed() {
+                self.state.fps = Some(fps);
+            }
+            if response.drag_stopped() || response.lost_focus() {
+                self.state.save();
+            }
+        });
+    }
+
+    /// Composes `{repo_id}` from a configured namespace and a name, and tells
+    /// whether that dataset exists yet.
+    fn repo_id_ui(&mut self, ui: &mut egui::Ui, config: &Config, hub: &hub::HubConfig) {
+        let mut editing = false;
+        ui.horizontal(|ui| {
+            ui.label("Dataset:");
+            let selected = match self.state.repo_namespace.as_str() {
+                "" => "namespace",
+                namespace => namespace,
+            };
+            egui::ComboBox::from_id_source("repo_namespace").selected_text(selected).show_ui(ui, |ui| {
+                for namespace in &hub.namespaces {
+                    if ui.selectable_value(&mut self.state.repo_namespace, namespace.clone(), namespace).changed() {
+                        self.state.save();
+                    }
+                }
+            });
+            ui.label("/");
+            let response =
+                ui.add(egui::TextEdit::singleline(&mut self.state.repo_name).hint_text("name").desired_width(240.0));
+            if response.lost_focus() {
+                self.state.save();
+            }
+            editing = response.has_focus();
+        });
+        if self.state.repo_namespace.is_empty() || self.state.repo_name.trim().is_empty() {
+            return;
+        }
+        let repo_id = format!("{}/{}", self.state.repo_namespace, self.state.repo_name.trim());
+        if let Err(e) = dataset::check_repo_id(&repo_id) {
+            ui.colored_label(egui::Color32::RED, e);
+            return;
+        }
+        // Looked up once typing stops, not for every key pressed.
+        if editing {
+            return;
+        }
+        self.repo_check.start(&repo_id, &config.secrets);
+        if !self.repo_check.done(&repo_id) {
+            ui.weak("Looking for the dataset...");
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(200));
+            return;
+        }
+        let existence = &self.repo_check.existence;
+        let on_hub = match &existence.hub {
+            Some(Ok(on_hub)) => *on_hub,
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::YELLOW, format!("Could not check the Hub: {}", e));
+                false
+            }
+            None => false,
+        };
+        match (existence.local, on_hub) {
+            (false, false) => ui.weak("A new dataset"),
+            (true, false) => ui.weak("Exists locally; Record offers to resume it"),
+            (false, true) => ui.weak("Exists on the Hub"),
+            (true, true) => ui.weak("Exists locally and on the Hub"),
+        };
+    }
+
+    /// Shows the robot host picker: the hosts found over mDNS and the known
+    /// ones from the config, plus free entry.
+    fn host_ui(&mut self, ui: &mut egui::Ui, hosts: &discovery::HostsConfig) {
+        self.discovery.start(&hosts.service, ui.ctx());
+        self.discovery.poll();
+        ui.horizontal(|ui| {
+            ui.label("Robot host:");
+            let mut changed = ui
+                .add(egui::TextEdit::singleline(&mut self.state.host).hint_text("address"))
+                .lost_focus();
+            let offered = self
+                .discovery
+                .hosts
+                .iter()
+                .map(|h| (format!("{} ({})", h.name, h.address), h.address.clone()))
+                .chain(hosts.known.iter().map(|h| (h.clone(), h.clone())));
+            egui::ComboBox::from_id_source("host_select")
+                .selected_text("Select")
+                .show_ui(ui, |ui| {
+                    for (label, address) in offered {
+                        if ui.selectable_label(self.state.host == address, label).clicked() {
+                            self.state.host = address;
+                            changed = true;
+                        }
+                    }
+                });
+            if self.discovery.searching() {
+                ui.spinner();
+            } else if ui
+                .small_button("Search")
+                .on_hover_text(format!("Look for {} services", hosts.service))
+                .clicked()
+            {
+                self.discovery.refresh(&hosts.service, ui.ctx());
+            }
+            if let Some(e) = &self.discovery.error {
+                ui.colored_label(egui::Color32::YELLOW, e);
+            }
+            if changed {
+                self.state.save();
+            }
+        });
+    }
+
+    /// The config file in use, with a menu to switch to a recently used one.
+    fn config_menu_ui(&mut self, ui: &mut egui::Ui) {
+        let name = self.config_path.file_name().unwrap_or(self.config_path.as_os_str()).to_string_lossy();
+        let current = self.config_path.canonicalize().unwrap_or_else(|_| self.config_path.clone());
+        let idle = self.idle();
+        let mut switch = None;
+        let mut open_folder = false;
+        ui.menu_button(format!("Config: {}", name), |ui| {
+            ui.label(egui::RichText::new(current.display().to_string()).weak());
+            ui.separator();
+            let others: Vec<&PathBuf> = self.state.recent_configs.iter().filter(|p| **p != current).collect();
+            if others.is_empty() {
+                ui.label("Config files opened with --config show up here.");
+            }
+            for path in others {
+                let button = ui
+                    .add_enabled(idle && path.exists(), egui::Button::new(path.display().to_string()))
+                    .on_disabled_hover_text(if idle { "The file is gone" } else { "Stop what is running first" });
+                if button.clicked() {
+                    switch = Some(path.clone());
+                    ui.close_menu();
+                }
+            }
+            ui.separator();
+            let hover = "Zip the config files and the GUI state (operator, variants, parameters, ...) \
+                         to set up another station; import it there by dropping it onto the window";
+            if ui.button("Export profile").on_hover_text(hover).clicked() {
+                self.export_profile();
+            }
+            match &self.profile_export {
+                Some(Ok(path)) => {
+                    ui.label(path.display().to_string());
+                    if ui.button("Open folder").clicked() {
+                        open_folder = true;
+                        ui.close_menu();
+                    }
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+                None => {}
+            }
+        });
+        if open_folder {
+            self.open_folder(Some(profile::default_dir()));
+        }
+        if let Some(path) = switch {
+            self.switch_config(path);
+        }
+    }
+
+    /// Lists the recently used datasets with shortcuts to work with each of them.
+    fn recent_datasets_ui(&mut self, ui: &mut egui::Ui) {
+        if self.state.recent_datasets.is_empty() {
+            ui.label("Datasets used by Record and Replay will show up here.");
+            return;
+        }
+        let idle = self.child_process.is_none();
+        let can_delete = self.idle();
+        let [record, replay, push] = [ProcessType::Record, ProcessType::Replay, ProcessType::Push]
+            .map(|t| self.config.as_ref().is_ok_and(|c| c.shows(t)));
+        let mut launch = None;
+        let mut delete = None;
+        egui::Grid::new("recent_datasets_grid").num_columns(2).striped(true).show(ui, |ui| {
+            for dataset in &self.state.recent_datasets {
+                ui.label(&dataset.repo_id).on_hover_text(format!(
+                    "{}\nLast used {}",
+                    dataset.local_path().display(),
+                    dataset.last_used.format("%Y-%m-%d %H:%M")
+                ));
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(idle, |ui| {
+                        if record && ui.button("Record more").clicked() {
+                            // LeRobot refuses to record into an existing dataset unless resuming.
+                            let resume = dataset.local_path().exists();
+                            launch = Some((ProcessType::Record, dataset.clone(), resume));
+                        }
+                        if replay && ui.button("Replay").clicked() {
+                            launch = Some((ProcessType::Replay, dataset.clone(), false));
+                        }
+                        if push && ui.button("Push").clicked() {
+                            launch = Some((ProcessType::Push, dataset.clone(), false));
+                        }
+                    });
+                    // Not while a session, sweep or script may record into it next.
+                    ui.add_enabled_ui(can_delete, |ui| {
+                        if ui.button("Delete last episode").on_hover_text("E.g. a bad demonstration").clicked() {
+                            delete = Some(dataset.clone());
+                        }
+                    });
+                    if ui.button("Open folder").clicked() {
+                        if let Err(e) = paths::open_in_file_manager(&dataset.local_path()) {
+                            self.last_error = Some(e);
+                        }
+                    }
+                });
+                ui.end_row();
+            }
+        });
+        if let Some(dataset) = delete {
+            let info = dataset::read_info(&dataset.local_path());
+            self.delete_episode = Some((dataset, info));
+        }
+        if let Some((process_type, dataset, resume)) = launch {
+            self.spawn_process(process_type, LaunchOptions { dataset: Some(dataset), resume, ..Default::default() });
+        }
+    }
+
+    /// Shows the size of the active dataset, or else of the one used last,
+    /// to follow the progress towards a collection target.
+    fn dataset_stats_ui(&mut self, ui: &mut egui::Ui) {
+        let selected = match &self.active_dataset {
+            Some((dataset, _)) => dataset.clone(),
+            None => match self.state.recent_datasets.first() {
+                Some(dataset) => dataset.clone(),
+                None => {
+                    ui.label("Drop a dataset folder onto the window or record one to see its statistics.");
+                    return;
+                }
+            },
+        };
+        let stale = self
+            .dataset_stats
+            .as_ref()
+            .is_none_or(|(shown, _)| shown.repo_id != selected.repo_id || shown.root != selected.root);
+        if stale {
+            let info = dataset::read_info(&selected.local_path());
+            self.dataset_stats = Some((selected, info));
+        }
+        let Some((dataset, info)) = &self.dataset_stats else { return };
+        let mut refresh = false;
+        ui.horizontal(|ui| {
+            ui.strong(&dataset.repo_id).on_hover_text(dataset.local_path().display().to_string());
+            refresh = ui.small_button("Refresh").clicked();
+        });
+        if refresh {
+            self.dataset_stats = None;
+            return;
+        }
+        let info = match info {
+            Ok(info) => info,
+            Err(e) => {
+                ui.weak(e);
+                return;
+            }
+        };
+        let minutes = |seconds: f64| format!("{} min {:02} s", (seconds / 60.0) as u64, (seconds % 60.0) as u64);
+        egui::Grid::new("dataset_stats_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Episodes");
+            ui.label(info.total_episodes.to_string());
+            ui.end_row();
+            ui.label("Frames");
+            ui.label(format!("{} at {} fps", info.total_frames, info.fps));
+            ui.end_row();
+            if let Some(seconds) = info.duration_s() {
+                ui.label("Duration");
+                ui.label(minutes(seconds));
+                ui.end_row();
+            }
+            if let Some(frames) = info.mean_episode_frames() {
+                ui.label("Average episode");
+                match info.duration_s() {
+                    Some(_) => ui.label(format!("{:.0} frames, {:.1} s", frames, frames / info.fps)),
+                    None => ui.label(format!("{:.0} frames", frames)),
+                };
+                ui.end_row();
+            }
+            for camera in info.camera_info() {
+                ui.label(format!("Camera {}", camera.name));
+                let resolution = match (camera.width, camera.height) {
+                    (Some(width), Some(height)) => format!("{}x{}", width, height),
+                    _ => "unknown resolution".to_string(),
+                };
+                let fps = camera.fps.map(|f| format!(" at {} fps", f)).unwrap_or_default();
+                ui.label(format!("{}{}", resolution, fps));
+                ui.end_row();
+            }
+        });
+    }
+
+    /// Shows the cameras of the Record command side by side, to check their
+    /// framing before recording.
+    fn camera_preview_ui(&mut self, ui: &mut egui::Ui, config: &Config) {
+        let variant = config.commands.variant(ProcessType::Record, self.selected_variant(ProcessType::Record));
+        let cameras = preview::cameras(&placeholders::substitute(&variant.command, &self.placeholder_values()));
+        if self.preview.as_ref().is_some_and(|p| !p.shows(&cameras)) {
+            self.preview = None;
+        }
+        ui.horizontal(|ui| {
+            if self.preview.is_some() {
+                if ui.button("Stop preview").clicked() {
+                    self.preview = None;
+                }
+            } else {
+                let start = ui
+                    .add_enabled(self.idle(), egui::Button::new("Start preview"))
+                    .on_disabled_hover_text("The cameras are in use while a process runs");
+                if start.clicked() {
+                    self.preview = Some(preview::Preview::start(cameras.clone(), ui.ctx()));
+                }
+            }
+            if preview::layout_ui(ui, &mut self.state.preview_layout) {
+                self.state.save();
+            }
+        });
+        if let Some(preview) = &mut self.preview {
+            let rect = preview.ui(ui, self.state.preview_layout);
+            let window = ui.ctx().input(|i| i.viewport().inner_rect);
+            self.preview_rect = window.map(|w| rect.translate(w.min.to_vec2()));
+        }
+    }
+
+    /// Measures the cameras, USB buses, arms and dataset disk of the Record
+    /// command; with `before_session`, a session begins if all pass.
+    fn start_hardware_check(&mut self, before_session: bool) {
+        let Ok(config) = &self.config else { return };
+        let disk_config = config.disk_check.clone().unwrap_or_default();
+        let (command, _) = self.prepare_command(ProcessType::Record, &self.default_launch_options(ProcessType::Record));
+        // The dataset may not exist yet; its disk is that of the closest folder that does.
+        let dataset_dir = dataset::from_command(&command)
+            .and_then(|d| d.local_path().ancestors().find(|p| p.is_dir()).map(std::path::Path::to_path_buf));
+        let mut check = hardware::HardwareCheck::start(&config.hardware_check, disk_config, command, dataset_dir);
+        check.before_session = before_session;
+        self.last_error = None;
+        // The check needs the cameras the preview holds open.
+        self.preview = None;
+        self.hardware_check = Some(check);
+    }
+
+    /// Picks up the measurements of a running hardware check, and begins the
+    /// session waiting for it once it passes.
+    fn poll_hardware_check(&mut self, ctx: &egui::Context) {
+        let Some(check) = &mut self.hardware_check else { return };
+        if check.running() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(200));
+            return;
+        }
+        if !std::mem::take(&mut check.before_session) {
+            return;
+        }
+        if check.passed() {
+            self.open_session();
+        } else {
+            self.last_error = Some(format!("The session did not start. {}", check.summary()));
+        }
+    }
+
+    /// Shows the button running the hardware check, and its measurements.
+    fn hardware_check_ui(&mut self, ui: &mut egui::Ui) {
+        let running = self.hardware_check.as_mut().is_some_and(|c| c.running());
+        ui.horizontal(|ui| {
+            let run = ui
+                .add_enabled(self.idle() && !running, egui::Button::new("Run hardware check"))
+                .on_hover_text("Measure camera frame rates, USB load, arm latency and disk speed")
+                .on_disabled_hover_text("The cameras and ports are in use");
+            if run.clicked() {
+                self.start_hardware_check(false);
+            }
+            if running {
+                ui.spinner();
+            }
+        });
+        let Some(check) = &self.hardware_check else { return };
+        let mut start_anyway = false;
+        egui::Grid::new("hardware_check").striped(true).show(ui, |ui| {
+            for measurement in &check.measurements {
+                match measurement.passed {
+                    Some(true) => ui.colored_label(egui::Color32::GREEN, "✔"),
+                    Some(false) => ui.colored_label(egui::Color32::RED, "✖"),
+                    None => ui.weak("–"),
+                };
+                ui.label(&measurement.name);
+                ui.label(&measurement.detail);
+                ui.end_row();
+            }
+        });
+        if !running {
+            let color = if check.passed() { egui::Color32::GREEN } else { egui::Color32::RED };
+            ui.horizontal(|ui| {
+                ui.colored_label(color, check.summary());
+                if !check.passed() && self.idle() {
+                    start_anyway = ui.button("Start session anyway").clicked();
+                }
+            });
+        }
+        if start_anyway {
+            self.last_error = None;
+            self.open_session();
+        }
+    }
+
+    /// Shows the recording overlay while Record runs or a session goes on,
+    /// where the camera preview was or else at the top right of the window.
+    fn hud(&self, ctx: &egui::Context) {
+        if self.state.hud_off {
+            return;
+        }
+        let recording = matches!(self.child_process, Some((_, ProcessType::Record)));
+        let session = self.session.as_ref().filter(|s| s.phase != session::Phase::Done);
+        if !recording && session.is_none() {
+            return;
+        }
+        let episode = match session {
+            Some(s) => Some(format!("Episode {} of {}", (s.kept + 1).min(s.settings.episodes), s.settings.episodes)),
+            None => self.episode_clock.episode().map(|e| format!("Episode {}", e)),
+        };
+        let state = hud::HudState {
+            recording,
+            label: match session {
+                Some(s) if !recording => s.phase.name().to_string(),
+                _ => "REC".to_string(),
+            },
+            episode,
+            elapsed: recording.then(|| self.episode_clock.elapsed()),
+        };
+        let window = ctx.input(|i| i.viewport().inner_rect);
+        let position = match (self.preview_rect, window) {
+            (Some(preview), _) => preview.left_top() + egui::vec2(8.0, 8.0),
+            (None, Some(window)) => window.right_top() + egui::vec2(-hud::SIZE.x - 16.0, 16.0),
+            (None, None) => egui::pos2(16.0, 16.0),
+        };
+        hud::show(ctx, &state, position);
+    }
+
+    /// Asks before deleting the last episode of the chosen dataset, and
+    /// deletes it.
+    fn delete_episode_ui(&mut self, ui: &mut egui::Ui) {
+        let Some((dataset, info)) = &self.delete_episode else { return };
+        let mut confirmed = false;
+        let mut cancel = false;
+        ui.group(|ui| {
+            match info {
+                Ok(info) if info.total_episodes > 0 => {
+                    ui.label(format!(
+                        "Delete episode {} of '{}' ({} in total)? Its files are removed for good; \
+                         a copy already pushed to the Hub is not touched.",
+                        info.total_episodes - 1,
+                        dataset.repo_id,
+                        info.total_episodes
+                    ));
+                }
+                Ok(_) => {
+                    ui.label(format!("'{}' has no episodes left.", dataset.repo_id));
+                }
+                Err(e) => {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+            }
+            ui.horizontal(|ui| {
+                confirmed = ui.button("Delete").clicked();
+                cancel = ui.button("Cancel").clicked();
+            });
+        });
+        if cancel {
+            self.delete_episode = None;
+        }
+        if !confirmed {
+            return;
+        }
+        // Whatever runs next may write into the same dataset.
+        if !self.idle() {
+            self.last_error = Some("Deleting an episode has to wait until nothing is running.".to_string());
+            return;
+        }
+        let Some((dataset, _)) = self.delete_episode.take() else { return };
+        match dataset::delete_last_episode(&dataset.local_path()) {
+            Ok(deleted) => self.episode_deleted(&dataset, deleted),
+            Err(e) => self.last_error = Some(e),
+        }
+    }
+
+    /// Records that the last episode of `dataset` was deleted.
+    fn episode_deleted(&mut self, dataset: &dataset::RecentDataset, deleted: dataset::DeletedEpisode) {
+        info!("Deleted episode {} ({} frames) of '{}'", deleted.index, deleted.frames, dataset.repo_id);
+        self.audit.record(
+            &self.user,
+            self.operator(),
+            AuditEvent::EpisodeDeleted {
+                repo_id: dataset.repo_id.clone(),
+                episode: deleted.index,
+                frames: deleted.frames,
+            },
+        );
+        if self.active_dataset.as_ref().is_some_and(|(active, _)| active.repo_id == dataset.repo_id) {
+            self.active_dataset = dataset::from_folder(&dataset.local_path()).ok();
+        }
+        self.dataset_stats = None;
+    }
+
+    /// The folder Record writes to: the dataset named in the configured record
+    /// command or, if it has none, the most recently used dataset. Falls back to
+    /// the closest existing parent so the button is useful before the first recording.
+    fn dataset_folder(&self) -> Option<PathBuf> {
+        let config = self.config.as_ref().ok()?;
+        let record_command = &config.commands.variant(ProcessType::Record, self.selected_variant(ProcessType::Record)).command;
+        let record_command = placeholders::substitute(record_command, &self.placeholder_values());
+        let dataset = dataset::from_command(&record_command)
+            .filter(|d| placeholders::names(&d.repo_id).is_empty())
+            .or_else(|| self.state.recent_datasets.first().cloned())?;
+        let path = dataset.local_path();
+        path.ancestors().find(|p| p.is_dir()).map(PathBuf::from)
+    }
+
+    /// Opens `folder` in the file manager, reporting problems in the GUI.
+    fn open_folder(&mut self, folder: Option<PathBuf>) {
+        let result = match folder {
+            Some(folder) => paths::open_in_file_manager(&folder),
+            None => Err("No dataset is configured or has been used yet.".to_string()),
+        };
+        if let Err(e) = result {
+            self.last_error = Some(e);
+        }
+    }
+
+    /// Launch options for the plain launch of `process_type`: Replay and Push
+    /// use the active dataset, if one is selected.
+    fn default_launch_options(&self, process_type: ProcessType) -> LaunchOptions {
+        let dataset = match process_type {
+            ProcessType::Replay | ProcessType::Push => self.active_dataset.as_ref().map(|(d, _)| d.clone()),
+            ProcessType::Record => self.session.as_ref().and_then(|s| s.dataset.clone()),
+            _ => None,
+        };
+        LaunchOptions { dataset, ..Default::default() }
+    }
+
+    /// Every action currently available in the command palette.
+    fn palette_actions(&self) -> Vec<(String, palette::PaletteAction)> {
+        use palette::PaletteAction;
+
+        let Ok(config) = &self.config else { return Vec::new() };
+        let mut actions = Vec::new();
+        if self.child_process.is_some() {
+            actions.push(("Stop running process".to_string(), PaletteAction::Stop));
+        } else if self.session.is_none() && self.sweep.is_none() && self.script.is_none() {
+            for process_type in ProcessType::ALL.into_iter().filter(|t| config.shows(*t)) {
+                actions.push((format!("Launch {:?}", process_type), PaletteAction::Launch(process_type)));
+            }
+            if config.shows(ProcessType::Record) {
+                actions.push(("Start recording session".to_string(), PaletteAction::StartSession));
+            }
+            for sweep in &config.sweeps {
+                actions.push((format!("Run sweep: {}", sweep.name), PaletteAction::StartSweep(sweep.name.clone())));
+            }
+            for script in &config.scripts {
+                actions.push((format!("Run script: {}", script.name), PaletteAction::RunScript(script.name.clone())));
+            }
+        }
+        for (process_type, _, variant) in config.commands.all_variants() {
+            if config.commands.variants(process_type).len() > 1 {
+                actions.push((
+                    format!("{:?} variant: {}", process_type, variant.name),
+                    PaletteAction::SelectVariant(process_type, variant.name.clone()),
+                ));
+            }
+        }
+        for operator in &config.app.operators {
+            actions.push((format!("Switch operator: {}", operator), PaletteAction::SelectOperator(operator.clone())));
+        }
+        if self.idle() {
+            let current = self.config_path.canonicalize().unwrap_or_else(|_| self.config_path.clone());
+            for path in self.state.recent_configs.iter().filter(|p| **p != current && p.exists()) {
+                actions.push((format!("Switch config: {}", path.display()), PaletteAction::SwitchConfig(path.clone())));
+            }
+        }
+        for dataset in &self.state.recent_datasets {
+            let repo_id = dataset.repo_id.clone();
+            actions.push((format!("Select dataset: {}", repo_id), PaletteAction::SelectDataset(repo_id.clone())));
+            actions.push((format!("Open dataset folder: {}", repo_id), PaletteAction::OpenDatasetFolder(repo_id)));
+        }
+        if config.commands.is_configured(ProcessType::Train) && !self.tensorboard_running() {
+            actions.push(("Launch TensorBoard".to_string(), PaletteAction::TensorBoard));
+        }
+        actions.push(("Open working directory".to_string(), PaletteAction::OpenWorkingDirectory));
+        actions.push(("Open terminal".to_string(), PaletteAction::OpenTerminal));
+        actions.push(("Open logs folder".to_string(), PaletteAction::OpenLogs));
+        actions.push(("Export diagnostics".to_string(), PaletteAction::ExportDiagnostics));
+        for (plugin, action, label) in self.plugins.actions() {
+            actions.push((label, PaletteAction::PluginAction(plugin, action)));
+        }
+        actions.push(("Secrets...".to_string(), PaletteAction::Secrets));
+        actions
+    }
+
+    /// Does what the action picked in the command palette names, as the
+    /// matching button would. Does nothing while the config fails to load.
+    fn run_palette_action(&mut self, action: palette::PaletteAction) {
+        use palette::PaletteAction;
+
+        let Ok(config) = self.config.clone() else { return };
+        let recent = |repo_id: &str| self.state.recent_datasets.iter().find(|d| d.repo_id == repo_id).cloned();
+        match action {
+            PaletteAction::Launch(process_type) => {
+                self.spawn_process(process_type, self.default_launch_options(process_type))
+            }
+            PaletteAction::Stop => self.kill_process(Stop::Requested("command palette")),
+            PaletteAction::StartSession => self.start_session(),
+            PaletteAction::StartSweep(name) => self.start_sweep(&name),
+            PaletteAction::RunScript(name) => self.start_script(&name),
+            PaletteAction::SelectVariant(process_type, name) => self.select_variant(process_type, name),
+            PaletteAction::SelectOperator(operator) => {
+                self.state.operator = operator;
+                self.state.save();
+            }
+            PaletteAction::SwitchConfig(path) => self.switch_config(path),
+            PaletteAction::SelectDataset(repo_id) => {
+                if let Some(dataset) = recent(&repo_id) {
+                    match dataset::read_info(&dataset.local_path()) {
+                        Ok(info) => self.active_dataset = Some((dataset, info)),
+                        Err(e) => self.last_error = Some(e),
+                    }
+                }
+            }
+            PaletteAction::OpenDatasetFolder(repo_id) => {
+                let folder = recent(&repo_id).map(|d| d.local_path());
+                self.open_folder(folder);
+            }
+            PaletteAction::OpenWorkingDirectory => {
+                let working_directory = paths::expand(self.shell_variant(&config).working_directory(&config.commands));
+                self.open_folder(Some(PathBuf::from(working_directory)).filter(|p| !p.as_os_str().is_empty()));
+            }
+            PaletteAction::OpenTerminal => {
+                if let Err(e) = command::open_shell(&config, self.shell_variant(&config)) {
+                    self.last_error = Some(e);
+                }
+            }
+            PaletteAction::OpenLogs => self.open_folder(Some(paths::data_dir())),
+            PaletteAction::PluginAction(plugin, action) => {
+                let values = self.placeholder_values();
+                self.plugins.run_action(plugin, action, &values);
+            }
+            PaletteAction::ExportDiagnostics => {
+                self.export_diagnostics();
+                if let Some(Ok(_)) = &self.diagnostics_export {
+                    self.open_folder(Some(diagnostics::default_dir()));
+                }
+            }
+            PaletteAction::Secrets => self.secrets_dialog.open = true,
+            PaletteAction::TensorBoard => self.launch_tensorboard(),
+        }
+    }
+
+    /// Makes a dataset folder dropped onto the window the active dataset.
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let (hovering, dropped) = ctx.input(|i| {
+            let dropped: Vec<PathBuf> = i.raw.dropped_files.iter().filter_map(|f| f.path.clone()).collect();
+            (!i.raw.hovered_files.is_empty(), dropped)
+        });
+        if hovering {
+            egui::Area::new(egui::Id::new("drop_hint"))
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    let hint = "Drop a dataset folder to select it, or a profile to import it";
+                    ui.label(egui::RichText::new(hint).heading());
+                });
+        }
+        // Only the first dropped item is used; a dataset is a single folder.
+        let Some(folder) = dropped.into_iter().next() else { return };
+        if profile::is_archive(&folder) {
+            self.import_profile(&folder);
+            return;
+        }
+        match dataset::from_folder(&folder) {
+            Ok((dataset, info)) => {
+                info!("Selected dataset '{}' from '{}'", dataset.repo_id, folder.display());
+                dataset::remember(&mut self.state.recent_datasets, dataset.clone());
+                self.state.save();
+                self.active_dataset = Some((dataset, info));
+                self.last_error = None;
+            }
+            Err(e) => self.last_error = Some(e),
+        }
+    }
+
+    /// Shows the branch and commit Record would run from, in yellow if
+    /// recording from it would be refused.
+    fn git_ui(&mut self, ui: &mut egui::Ui, config: &Config) {
+        let variant = config.commands.variant(ProcessType::Record, self.selected_variant(ProcessType::Record));
+        self.git_status.poll(&paths::expand(variant.working_directory(&config.commands)), ui.ctx());
+        let Some(status) = &self.git_status.last else { return };
+        ui.horizontal(|ui| match (status, &config.git) {
+            (Ok(status), git_config) => {
+                ui.label("Code:");
+                let problems = git_config.as_ref().map(|g| status.problems(g)).unwrap_or_default();
+                if problems.is_empty() {
+                    ui.monospace(status.label()).on_hover_text(&status.commit);
+                } else {
+                    ui.colored_label(egui::Color32::YELLOW, status.label()).on_hover_text(problems.join("\n"));
+                }
+            }
+            // Only worth mentioning if recordings are expected to come from a checkout.
+            (Err(e), Some(_)) => {
+                ui.colored_label(egui::Color32::YELLOW, e);
+            }
+            (Err(_), None) => {}
+        });
+    }
+
+    /// Shows the dataset selected by drag and drop, if any.
+    fn active_dataset_ui(&mut self, ui: &mut egui::Ui) {
+        let Some((dataset, info)) = &self.active_dataset else { return };
+        let mut clear = false;
+        ui.horizontal(|ui| {
+            ui.label(format!("Active dataset: {}", dataset.repo_id)).on_hover_text(format!(
+                "{}\nFormat {}\nCameras: {}",
+                dataset.local_path().display(),
+                info.codebase_version,
+                info.cameras().join(", ")
+            ));
+            ui.weak(format!(
+                "{} episodes, {} frames at {} fps{}",
+                info.total_episodes,
+                info.total_frames,
+                info.fps,
+                info.robot_type.as_deref().map(|r| format!(", {}", r)).unwrap_or_default()
+            ));
+            clear = ui.small_button("Clear").clicked();
+        });
+        if clear {
+            self.active_dataset = None;
+        }
+    }
+
+    /// Shows a dropdown to pick the command variant of `process_type`, if
+    /// more than one is configured.
+    fn variant_ui(&mut self, ui: &mut egui::Ui, config: &Config, process_type: ProcessType, width: f32) {
+        let variants = config.commands.variants(process_type);
+        if variants.len() < 2 {
+            return;
+        }
+        let selected = self.selected_variant(process_type);
+        let mut chosen = None;
+        egui::ComboBox::from_id_source(("variant", process_type.key()))
+            .width(width)
+            .selected_text(&variants[selected].name)
+            .show_ui(ui, |ui| {
+                for (index, variant) in variants.iter().enumerate() {
+                    if ui.selectable_label(index == selected, &variant.name).clicked() {
+                        chosen = Some(variant.name.clone());
+                    }
+                }
+            });
+        if let Some(name) = chosen {
+            self.select_variant(process_type, name);
+        }
+    }
+
+    /// Shows "Test connection" for the selected variant of `process_type`, if
+    /// it has a probe or passes serial ports, and how the last test went.
+    fn connection_test_ui(&mut self, ui: &mut egui::Ui, config: &Config, process_type: ProcessType) {
+        let variant = config.commands.variant(process_type, self.selected_variant(process_type));
+        let (command, values) = self.prepare_command(process_type, &self.default_launch_options(process_type));
+        let ports = probe::ports(&command);
+        if variant.probe.is_none() && ports.is_empty() {
+            return;
+        }
+        let running = self.connection_tests.get_mut(&process_type).is_some_and(|t| t.running());
+        ui.horizontal(|ui| {
+            let hover = match &variant.probe {
+                Some(probe) => format!("Run '{}'", probe),
+                None => format!("Open {}", ports.join(", ")),
+            };
+            // The ports are busy while something runs.
+            let enabled = !running && self.child_process.is_none();
+            if ui.add_enabled(enabled, egui::Button::new("Test connection")).on_hover_text(hover).clicked() {
+                let probe = variant.probe.as_ref().map(|p| command::resolve(config, variant, p, &values));
+                self.connection_tests.insert(process_type, probe::ConnectionTest::start(probe, ports));
+            }
+            if running {
+                ui.spinner();
+                ui.ctx().request_repaint_after(std::time::Duration::from_millis(200));
+            }
+            match self.connection_tests.get(&process_type).and_then(|t| t.outcome.as_ref()) {
+                Some(Ok(message)) => {
+                    ui.colored_label(egui::Color32::GREEN, format!("✔ {}", message));
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(egui::Color32::RED, format!("✖ {}", e));
+                }
+                None => {}
+            }
+        });
+    }
+
+    /// Shows the command being edited for a single run, with buttons to launch
+    /// it or discard the edit. The config is left untouched.
+    fn command_edit_ui(&mut self, ui: &mut egui::Ui) {
+        let Some((process_type, command)) = &mut self.command_edit else { return };
+        let process_type = *process_type;
+        ui.label(format!("{:?} command for this run only:", process_type));
+        ui.add(
+            egui::TextEdit::multiline(command)
+                .code_editor()
+                .desired_rows(3)
+                .desired_width(f32::INFINITY),
+        );
+        let (launch, save, cancel) = ui
+            .horizontal(|ui| {
+                (
+                    ui.button("Launch edited").clicked(),
+                    ui.button("Save as default")
+                        .on_hover_text("Write this command back to the config file")
+                        .clicked(),
+                    ui.button("Cancel").clicked(),
+                )
+            })
+            .inner;
+        let command = command.clone();
+        if launch {
+            let options = self.default_launch_options(process_type);
+            // Launching the command unchanged is not an edit.
+            let edited = (command != self.prepare_command(process_type, &options).0).then(|| command.clone());
+            self.spawn_process(process_type, LaunchOptions { command: edited, ..options });
+        }
+        if save {
+            self.save_command(process_type, &command);
+        }
+        if cancel || self.child_process.is_some() {
+            self.command_edit = None;
+        }
+    }
+
+    /// Makes `command` the configured command for `process_type`. Values the
+    /// app filled in are turned back into placeholders first, so the saved
+    /// command keeps working for the next operator or dataset.
+    fn save_command(&mut self, process_type: ProcessType, command: &str) {
+        let Ok(config) = self.config.clone() else { return };
+        let (_, values) = self.prepare_command(process_type, &self.default_launch_options(process_type));
+        let variant = self.selected_variant(process_type);
+        let template = &config.commands.variant(process_type, variant).command;
+        let command = placeholders::unsubstitute(command, template, &values);
+        let index = (config.commands.variants(process_type).len() > 1).then_some(variant);
+        match config::save_command(&self.config_path, process_type, index, &command) {
+            Ok(contents) => {
+                info!("Saved {:?} command to '{}'", process_type, self.config_path.display());
+                self.audit.config_saved(&self.user, self.operator(), &self.config_path, &contents);
+                self.reload_config();
+                self.last_error = None;
+            }
+            Err(e) => self.last_error = Some(e),
+        }
+    }
+
+    /// Pauses or resumes the running process by sending SIGSTOP or SIGCONT to
+    /// everything running in its terminal.
+    fn set_paused(&mut self, paused: bool) {
+        let Some((child, process_type)) = &self.child_process else { return };
+        let process_type = *process_type;
+        match process::signal_children(child.id(), if paused { "STOP" } else { "CONT" }) {
+            Ok(()) => {
+                info!("{} process with PID {}", if paused { "Paused" } else { "Resumed" }, child.id());
+                self.paused = paused;
+                self.publish(if paused {
+                    StatusEvent::ProcessPaused { process_type }
+                } else {
+                    StatusEvent::ProcessResumed { process_type }
+                });
+            }
+            Err(e) => self.last_error = Some(e),
+        }
+    }
+
+    /// The directory the running or most recent Train run writes to, taken
+    /// from its `--output_dir` flag, or LeRobot's default parent directory of
+    /// training outputs. Relative to the working directory of Train.
+    fn training_output_dir(&self) -> String {
+        let runs = self.history.runs();
+        let run = match (&self.child_process, self.current_run) {
+            (Some((_, ProcessType::Train)), Some(index)) => runs.get(index),
+            _ => runs.iter().rev().find(|r| r.process_type == ProcessType::Train),
+        };
+        run.and_then(|r| command::flag_value(&r.command, TRAIN_OUTPUT_FLAG))
+            .filter(|dir| placeholders::names(dir).is_empty())
+            .unwrap_or_else(|| "outputs/train".to_string())
+    }
+
+    /// Why the process or companion `key` (shown as `label`) may not start
+    /// next to what runs, by the `[exclusivity]` rules, if it may not.
+    fn exclusivity_refusal(&mut self, key: &str, label: &str) -> Option<String> {
+        let Ok(config) = &self.config else { return None };
+        self.companions.retain_mut(|c| c.running());
+        let mut running: Vec<(String, String)> =
+            self.companions.iter().map(|c| (c.label.to_lowercase(), c.label.clone())).collect();
+        if let Some((_, process_type)) = &self.child_process {
+            running.push((process_type.key().to_string(), format!("{:?}", process_type)));
+        }
+        let running: Vec<(&str, &str)> = running.iter().map(|(k, l)| (k.as_str(), l.as_str())).collect();
+        let refusal = config.exclusivity.refusal((key, label), &running)?;
+        info!("Refusing to start {}: {}", label, refusal);
+        Some(refusal)
+    }
+
+    /// Whether TensorBoard was started from the app and is still running.
+    fn tensorboard_running(&self) -> bool {
+        self.companions.iter().any(|c| c.label == TENSORBOARD)
+    }
+
+    /// Starts TensorBoard on the output of the training run, in the working
+    /// directory and conda environment of the Train command.
+    fn launch_tensorboard(&mut self) {
+        let Ok(config) = self.config.clone() else { return };
+        if self.tensorboard_running() {
+            return;
+        }
+        if let Some(refusal) = self.exclusivity_refusal(&TENSORBOARD.to_lowercase(), TENSORBOARD) {
+            self.last_error = Some(refusal);
+            return;
+        }
+        let variant = config.commands.variant(ProcessType::Train, self.selected_variant(ProcessType::Train));
+        let port = config.app.tensorboard_port;
+        let tensorboard = format!(
+            "tensorboard --logdir {} --port {}",
+            command::shell_quote(&self.training_output_dir()),
+            port
+        );
+        let shell_command = command::resolve(&config, variant, &tensorboard, &HashMap::new());
+        let url = format!("http://localhost:{}/", port);
+        match companion::Companion::spawn(TENSORBOARD, &shell_command, Some(url)) {
+            Ok(companion) => self.companions.push(companion),
+            Err(e) => self.last_error = Some(e),
+        }
+    }
+
+    /// The button starting TensorBoard on the training output, disabled while it runs.
+    fn tensorboard_button(&mut self, ui: &mut egui::Ui) {
+        let hover = format!("Start TensorBoard on '{}'", self.training_output_dir());
+        let button = ui.add_enabled(!self.tensorboard_running(), egui::Button::new("TensorBoard"));
+        if button.on_hover_text(hover).clicked() {
+            self.launch_tensorboard();
+        }
+    }
+
+    /// Lists the running background processes with their links, and forgets
+    /// the ones that exited.
+    fn companions_ui(&mut self, ui: &mut egui::Ui) {
+        self.companions.retain_mut(|c| c.running());
+        if self.companions.is_empty() {
+            return;
+        }
+        ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
+        let mut stop = None;
+        for (index, companion) in self.companions.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{} is running", companion.label));
+                if let Some(url) = &companion.url {
+                    ui.hyperlink(url);
+                }
+                if ui.small_button("Stop").clicked() {
+                    stop = Some(index);
+                }
+            });
+        }
+        if let Some(index) = stop {
+            self.companions.remove(index).stop();
+        }
+    }
+
+    /// Reads the new output of the running process: remembers the experiment
+    /// tracker links in it, follows the frame rate and stops on a fault.
+    fn poll_output(&mut self) {
+        let (Some(tail), Some(index)) = (&mut self.output, self.current_run) else { return };
+        let lines = tail.poll();
+        if let Some(script) = &mut self.script {
+            script.feed(&lines);
+        }
+        lines.iter().for_each(|line| self.episode_clock.feed(line));
+        if let Some(stall) = &mut self.stall {
+            lines.iter().for_each(|line| stall.feed(line));
+        }
+        if let Some(frames) = &mut self.frames {
+            lines.iter().for_each(|line| frames.feed(line));
+        }
+        let links: Vec<_> = lines.iter().flat_map(|line| trackers::find(line)).collect();
+        if !links.is_empty() {
+            self.history.add_links(index, links);
+        }
+        let fault = self.faults.as_ref().and_then(|f| f.find(&lines)).map(str::to_string);
+        if let Some(fault) = fault {
+            self.stop_on_fault(index, fault);
+        }
+    }
+
+    /// Stops the running Record process gracefully after `fault` showed up in
+    /// its output, and marks the run at `index` (and the episode) as failed.
+    fn stop_on_fault(&mut self, index: usize, fault: String) {
+        let Ok(config) = &self.config else { return };
+        let Some((child, _)) = &self.child_process else { return };
+        error!("Fault detected, stopping Record: {}", fault);
+        // Only the first fault counts; the process is stopping already.
+        self.faults = None;
+        if self.paused {
+            let _ = process::signal_children(child.id(), "CONT");
+        }
+        if let Err(e) = process::signal_children(child.id(), &config.faults.signal) {
+            error!("{}", e);
+        }
+        self.last_error = Some(format!("Stopped Record after a fault: {}", fault));
+        self.history.set_fault(index, fault);
+    }
+
+    /// Reads the outcome and metrics of the finished Replay or Evaluate run at
+    /// `index` from its output.
+    fn record_results(&mut self, index: usize) {
+        let Ok(config) = &self.config else { return };
+        let Some(run) = self.history.runs().get(index) else { return };
+        if !matches!(run.process_type, ProcessType::Replay | ProcessType::Evaluate) {
+            return;
+        }
+        let Some(output) = run.log_file.as_ref().and_then(|f| std::fs::read_to_string(f).ok()) else { return };
+        let output = output::strip_ansi(&output);
+        let evaluation = match run.process_type {
+            ProcessType::Evaluate => evaluation::parse(&config.evaluation, &run.command, &output),
+            _ => Ok(None),
+        };
+        match evaluation.and_then(|e| Ok((e, evaluation::metrics(&config.evaluation, &output)?))) {
+            Ok((evaluation, metrics)) => {
+                if let Some(result) = &evaluation {
+                    info!("Evaluation: {} of {} episodes succeeded", result.successes, result.episodes());
+                }
+                self.history.set_results(index, evaluation, metrics);
+            }
+            Err(e) => self.last_error = Some(e),
+        }
+    }
+
+    /// Closes the history entry of the current run, if any.
+    fn finish_run(&mut self, stopped: bool) {
+        // The terminal exits with a status of its own; the command's is
+        // written next to its log.
+        let log_file = self.current_run.and_then(|i| self.history.runs().get(i)).and_then(|r| r.log_file.clone());
+        let exit_code = log_file.and_then(|f| output::exit_status(&f)).filter(|_| !stopped);
+        // The run's span ends here, with what happens on the way.
+        let run_span = self.run_span.take().unwrap_or_else(tracing::Span::none);
+        run_span.record("stopped", stopped);
+        if let Some(code) = exit_code {
+            run_span.record("exit_code", code);
+        }
+        let _entered = run_span.enter();
+        self.paused = false;
+        self.poll_output();
+        self.output = None;
+        self.input = None;
+        let frames = self.frames.take();
+        self.faults = None;
+        self.stall = None;
+        self.port_locks = None;
+        // The last episode is saved just before Record exits.
+        if let Some(saved) = self.saves.take().and_then(|mut s| s.check()) {
+            self.toasts.push(saved.describe());
+        }
+        let faulted = self.current_run.and_then(|i| self.history.runs().get(i)).is_some_and(|r| r.fault.is_some());
+        let core_watch = self.core_watch.take();
+        if let Some(index) = self.current_run.take() {
+            self.history.finish(index, exit_code, stopped);
+            if let Some((watch, signal)) = core_watch.zip(cores::Watch::signal(exit_code)) {
+                warn!("The command was killed by signal {}; looking for its core dump", signal);
+                self.cores.start(index, watch);
+            }
+            if let Some(frames) = frames {
+                self.history.set_frames(index, frames.stats);
+            }
+            if let Some(run) = self.history.runs().get(index) {
+                self.publish(StatusEvent::ProcessFinished { process_type: run.process_type, exit_code, stopped });
+            }
+            self.record_results(index);
+            let values = self.placeholder_values();
+            if let Some(run) = self.history.runs().get(index) {
+                self.plugins.parse(index, run, &values);
+            }
+            if self.history.runs().get(index).is_some_and(|r| r.process_type == ProcessType::Train) {
+                self.scan_checkpoints();
+            }
+            if self.history.runs().get(index).is_some_and(|r| r.process_type == ProcessType::Record) {
+                self.dataset_stats = None;
+            }
+        }
+        if let Some(run) = self.sweep.as_mut().and_then(|s| s.running()) {
+            run.status = sweep::RunStatus::Finished(exit_code);
+        }
+        let next = self.session.as_mut().and_then(|s| s.recording_finished(exit_code, stopped || faulted));
+        if let Some(next) = next {
+            self.enter_phase(next);
+        }
+    }
+
+    /// Starts a recording session with the `[session]` settings from the config.
+    fn start_session(&mut self) {
+        if self.hardware_check.as_mut().is_some_and(|c| c.running()) {
+            return;
+        }
+        if self.config.as_ref().is_ok_and(|c| c.hardware_check.before_session) {
+            self.start_hardware_check(true);
+            return;
+        }
+        self.open_session();
+    }
+
+    /// Starts a recording session without checking the hardware first,
+    /// offering to resume the dataset if it already has episodes.
+    fn open_session(&mut self) {
+        let options = self.default_launch_options(ProcessType::Record);
+        let named = self.config.as_ref().is_ok_and(|c| c.dataset_naming.is_some());
+        if let Some((dataset, episodes)) = self.existing_dataset(&options).filter(|(_, n)| !named && *n > 0) {
+            self.resume_offer = Some(ResumeOffer {
+                session: true,
+                options,
+                repo_id: dataset.repo_id,
+                episodes,
+                interrupted: false,
+            });
+            return;
+        }
+        self.begin_session(0);
+    }
+
+    /// The button starting a recording session, with its target.
+    fn start_session_ui(&mut self, ui: &mut egui::Ui, config: &Config) {
+        let mut episodes = self.state.session_episodes.unwrap_or(config.session.episodes);
+        ui.horizontal(|ui| {
+            if ui
+                .button("Start session")
+                .on_hover_text("Record episode by episode with reset, countdown and review phases")
+                .clicked()
+            {
+                self.start_session();
+            }
+            let target = egui::DragValue::new(&mut episodes).clamp_range(1..=u32::MAX).suffix(" episodes");
+            if ui.add(target).on_hover_text("Episodes to keep before the session is done").changed() {
+                self.state.session_episodes = Some(episodes);
+                self.state.save();
+            }
+        });
+    }
+
+    /// Starts the recording session with `kept` episodes already counted.
+    fn begin_session(&mut self, kept: u32) {
+        let Ok(config) = &self.config else { return };
+        let mut settings = config.session.clone();
+        settings.episodes = self.state.session_episodes.unwrap_or(settings.episodes);
+        let mut session = session::Session::new(settings);
+        session.kept = kept;
+        session.dataset = match self.new_dataset() {
+            Ok(dataset) => dataset,
+            Err(e) => {
+                self.last_error = Some(e);
+                return;
+            }
+        };
+        let options = LaunchOptions { dataset: session.dataset.clone(), ..Default::default() };
+        let (command, _) = self.prepare_command(ProcessType::Record, &options);
+        // Resumed episodes count towards the targets of their tasks as well.
+        if let Some(dataset) = dataset::from_command(&command).filter(|_| kept > 0) {
+            session.kept_by_task = dataset::episode_tasks(&dataset.local_path());
+        }
+        // Reset as long as `lerobot-record` itself would between episodes.
+        if session.settings.reset.seconds.is_none() {
+            let reset_time = command::flag_value(&command, session::RESET_TIME_FLAG).and_then(|s| s.parse().ok());
+            session.settings.reset.seconds = reset_time.filter(|s: &f64| *s > 0.0);
+        }
+        if let Some(disk_check) = &config.disk_check {
+            if let Some(dataset) = dataset::from_command(&command) {
+                session.warnings.extend(disk::check(disk_check, &command, &dataset.local_path()));
+            }
+        }
+        self.session = Some(session);
+        self.enter_phase(session::Phase::Reset);
+    }
+
+    /// Shows the offer to resume recording into an existing dataset.
+    fn resume_offer_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(offer) = &self.resume_offer else { return };
+        let mut choice = None;
+        let mut cancel = false;
+        ui.group(|ui| {
+            let reason = if offer.interrupted { "The last recording was interrupted. " } else { "" };
+            ui.label(format!(
+                "{}Dataset '{}' already has {} episode(s).",
+                reason, offer.repo_id, offer.episodes
+            ));
+            ui.horizontal(|ui| {
+                let next = offer.episodes + 1;
+                if offer.session {
+                    if ui
+                        .button(format!("Resume session at episode {}", next))
+                        .on_hover_text("Count the existing episodes towards this session")
+                        .clicked()
+                    {
+                        choice = Some(true);
+                    }
+                    if ui.button("New session (append)").clicked() {
+                        choice = Some(false);
+                    }
+                } else {
+                    if ui.button(format!("Resume at episode {}", next)).clicked() {
+                        choice = Some(true);
+                    }
+                    if ui
+                        .button("Launch as configured")
+                        .on_hover_text("E.g. if the command removes the dataset first")
+                        .clicked()
+                    {
+                        choice = Some(false);
+                    }
+                }
+                cancel = ui.button("Cancel").clicked();
+            });
+        });
+        if cancel {
+            self.resume_offer = None;
+        }
+        let Some(resume) = choice else { return };
+        let Some(offer) = self.resume_offer.take() else { return };
+        if offer.session {
+            self.begin_session(if resume { u32::try_from(offer.episodes).unwrap_or(u32::MAX) } else { 0 });
+        } else if resume {
+            self.spawn_process(ProcessType::Record, LaunchOptions { resume: true, ..offer.options });
+        } else {
+            self.spawn_process(ProcessType::Record, LaunchOptions { skip_dataset_check: true, ..offer.options });
+        }
+    }
+
+    /// Moves the session to `phase`; entering the recording phase launches
+    /// Record for a single episode.
+    #[tracing::instrument(skip(self))]
+    fn enter_phase(&mut self, phase: session::Phase) {
+        let Ok(config) = self.config.clone() else { return };
+        let variant = config.commands.variant(ProcessType::Record, self.selected_variant(ProcessType::Record));
+        let (_, mut values) = self.prepare_command(ProcessType::Record, &self.default_launch_options(ProcessType::Record));
+        let Some(session) = &mut self.session else { return };
+        if let Some(index) = session.episode_index {
+            values.insert("episode_index".to_string(), index.to_string());
+        }
+        if let Err(e) = session.enter(phase, &config, variant, &values) {
+            self.last_error = Some(e);
+        }
+        if phase != session::Phase::Recording {
+            self.publish_phase();
+            return;
+        }
+        let mut options = LaunchOptions { flags: session.record_flags(), ..self.default_launch_options(ProcessType::Record) };
+        // Every episode after the first one of a new dataset is appended to it.
+        let (command, _) = self.prepare_command(ProcessType::Record, &options);
+        let local_path = dataset::from_command(&command).map(|d| d.local_path());
+        options.resume = local_path.as_ref().is_some_and(|p| p.exists());
+        let episode_index = local_path.map(|p| dataset::read_info(&p).map(|i| i.total_episodes).unwrap_or(0));
+        self.spawn_process(ProcessType::Record, options);
+        let Some(session) = &mut self.session else { return };
+        if self.child_process.is_some() {
+            session.recording_started(&command, episode_index);
+        } else {
+            let _ = session.enter(session::Phase::Review { success: false }, &config, variant, &values);
+            session.can_rerecord = true;
+        }
+        self.publish_phase();
+    }
+
+    /// Re-records the last episode of the session. Unless a cleanup command
+    /// takes care of it, the episode is first deleted from the dataset if it
+    /// was saved, so the new recording gets its index.
+    fn rerecord_episode(&mut self) {
+        let Some(session) = &mut self.session else { return };
+        let episode_index = session.episode_index.filter(|_| session.settings.cleanup.is_none());
+        let next = session.rerecord();
+        if let Some(index) = episode_index {
+            self.delete_rerecorded_episode(index);
+        }
+        self.enter_phase(next);
+    }
+
+    /// Deletes episode `index` of the session's dataset if it is the last one.
+    fn delete_rerecorded_episode(&mut self, index: u64) {
+        let (command, _) = self.prepare_command(ProcessType::Record, &self.default_launch_options(ProcessType::Record));
+        let Some(dataset) = dataset::from_command(&command) else { return };
+        let path = dataset.local_path();
+        // A failed recording may not have saved it, and then there is nothing to delete.
+        if !dataset::read_info(&path).is_ok_and(|info| info.total_episodes == index + 1) {
+            return;
+        }
+        match dataset::delete_last_episode(&path) {
+            Ok(deleted) => self.episode_deleted(&dataset, deleted),
+            Err(e) => self.last_error = Some(format!("Failed to delete the episode to re-record: {}", e)),
+        }
+    }
+
+    /// Publishes the current phase of the recording session.
+    fn publish_phase(&self) {
+        let Some(session) = &self.session else { return };
+        self.publish(StatusEvent::SessionPhase {
+            phase: session.phase.name().to_string(),
+            episode_index: session.episode_index,
+            kept: session.kept,
+            discarded: session.discarded,
+            episodes: session.settings.episodes,
+        });
+    }
+
+    /// Ends the recording session, stopping an episode being recorded for
+    /// the reason in `stop`.
+    fn end_session(&mut self, stop: Stop) {
+        let recording = self.session.as_ref().is_some_and(|s| s.phase == session::Phase::Recording);
+        if let Some(session) = self.session.take() {
+            self.publish(StatusEvent::SessionEnded { kept: session.kept, discarded: session.discarded });
+        }
+        if recording {
+            self.kill_process(stop);
+        }
+    }
+
+    /// Shows the current phase of the recording session and its controls.
+    fn session_ui(&mut self, ui: &mut egui::Ui) {
+        use session::Phase;
+
+        let Some(session) = &mut self.session else { return };
+        ui.ctx().request_repaint_after(std::time::Duration::from_millis(100));
+        let mut next = session.poll();
+        let mut end = false;
+        let mut rerecord = false;
+        let mut target_changed = false;
+        ui.vertical_centered(|ui| {
+            ui.heading(session.phase.name());
+            ui.label(format!(
+                "Episode {} of {} ({} kept, {} discarded)",
+                (session.kept + 1).min(session.settings.episodes),
+                session.settings.episodes,
+                session.kept,
+                session.discarded
+            ));
+            target_changed = session.progress_ui(ui);
+            if let Some(dataset) = &session.dataset {
+                ui.weak(format!("Dataset '{}'", dataset.repo_id));
+            }
+            if let (Phase::Recording | Phase::Review { .. }, Some(index)) = (session.phase, session.episode_index) {
+                ui.weak(format!("Dataset episode index {}", index));
+            }
+            for warning in &session.warnings {
+                ui.colored_label(egui::Color32::YELLOW, warning);
+            }
+            match (session.phase, session.remaining()) {
+                (Phase::Countdown, Some(remaining)) => {
+                    ui.label(egui::RichText::new(format!("{}", remaining.ceil())).size(48.0));
+                }
+                (Phase::Reset, Some(remaining)) => {
+                    let total = session.settings.reset.seconds.unwrap_or(remaining);
+                    let fraction = 1.0 - remaining / total.max(f64::EPSILON);
+                    let text = format!("Next episode in {:.0} s", remaining.ceil());
+                    ui.add(egui::ProgressBar::new(fraction as f32).text(text));
+                }
+                (_, Some(remaining)) => {
+                    let total = match session.phase {
+                        Phase::Recording => session.settings.recording.seconds,
+                        _ => session.settings.review.seconds,
+                    };
+                    let fraction = total.map_or(0.0, |t| 1.0 - remaining / t.max(f64::EPSILON));
+                    ui.add(egui::ProgressBar::new(fraction as f32).text(format!("{:.0} s left", remaining)));
+                }
+                _ => {}
+            }
+            if session.phase_command_running() {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Waiting for the phase command to finish...");
+                });
+            }
+            match session.phase {
+                Phase::Reset => {
+                    ui.horizontal(|ui| {
+                        if ui.button("Ready").clicked() {
+                            session.ready();
+                        }
+                        if session.can_rerecord && rerecord_button(ui, session).clicked() {
+                            rerecord = true;
+                        }
+                    });
+                }
+                Phase::Countdown => {
+                    if ui.button("Start now").clicked() {
+                        session.ready();
+                    }
+                }
+                Phase::Recording | Phase::Cleanup => {}
+                Phase::Review { success } => {
+                    let last_record = self.history.runs().iter().rev().find(|r| r.process_type == ProcessType::Record);
+                    if let Some(fault) = last_record.and_then(|r| r.fault.as_ref()) {
+                        ui.colored_label(egui::Color32::RED, format!("Recording was stopped after a fault: {}", fault));
+                    } else if !success {
+                        ui.colored_label(egui::Color32::RED, "Recording the episode failed.");
+                    }
+                    if let Some(frames) = last_record.and_then(|r| r.frames.as_ref()) {
+                        frames::describe_drops(ui, frames.dropped);
+                    }
+                    if session.validating() {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Validating the episode...");
+                        });
+                    } else if let Some(validation) = &session.validation {
+                        if validation.passed {
+                            ui.colored_label(egui::Color32::GREEN, "Validation passed");
+                        } else {
+                            ui.colored_label(egui::Color32::RED, "Validation failed");
+                        }
+                        for message in &validation.messages {
+                            ui.label(message);
+                        }
+                    }
+                    let can_keep = success && session.validation_passed();
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(can_keep, egui::Button::new("Keep")).clicked() {
+                            next = Some(session.review(true));
+                        }
+                        if ui
+                            .button("Discard")
+                            .on_hover_text("Not counted towards the session. The recorded data is not deleted.")
+                            .clicked()
+                        {
+                            next = Some(session.review(false));
+                        }
+                        if rerecord_button(ui, session).clicked() {
+                            rerecord = true;
+                        }
+                    });
+                }
+                Phase::Done => {}
+            }
+            let label = if session.phase == Phase::Done { "Close" } else { "End session" };
+            end = ui.button(label).clicked();
+        });
+        for reached in session.take_reached() {
+            info!("{}", reached);
+            self.toasts.push(reached);
+        }
+        if target_changed {
+            self.state.session_episodes = Some(session.settings.episodes);
+            self.state.save();
+        }
+        if end {
+            self.end_session(Stop::Requested("session ended"));
+        } else if rerecord {
+            self.rerecord_episode();
+        } else if let Some(next) = next {
+            self.enter_phase(next);
+        }
+    }
+}
+
+impl eframe::App for MyApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.handle_dropped_files(ctx);
+        self.poll_estop(ctx);
+        self.poll_voice(ctx);
+        self.poll_web();
+        self.poll_hardware_check(ctx);
+        self.poll_controller(ctx);
+        self.poll_core_dumps(ctx);
+        self.poll_snapshots(ctx);
+        self.poll_plugins(ctx);
+        let actions = self.palette_actions();
+        if let Some(action) = self.palette.show(ctx, &actions) {
+            self.run_palette_action(action);
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading("Teleop Record Replay");
+                self.config_menu_ui(ui);
+            });
+            ui.separator();
+
+            // Display an error message if the configuration failed to load.
+            let config = match &self.config {
+                Ok(config) => config.clone(),
+                Err(diagnostics) => {
+                    ui.colored_label(egui::Color32::RED, "The configuration could not be loaded:");
+                    config::diagnostics_ui(ui, diagnostics);
+                    return;
+                }
+            };
+
+            if !self.config_diagnostics.is_empty() {
+                let title = format!("Config problems ({})", self.config_diagnostics.len());
+                egui::CollapsingHeader::new(title)
+                    .default_open(config::has_errors(&self.config_diagnostics))
+                    .show(ui, |ui| config::diagnostics_ui(ui, &self.config_diagnostics));
+            }
+
+            if let Some(updates) = &config.updates {
+                self.updater.start(updates, ctx);
+                self.updater.ui(ui);
+            }
+
+            self.operator_ui(ui, &config.app.operators);
+            if let Some(hosts) = &config.hosts {
+                self.host_ui(ui, hosts);
+            }
+            let uses_fps = |v: &config::Variant| placeholders::names(&v.command).iter().any(|n| n == "fps");
+            if config.commands.all_variants().any(|(_, _, v)| uses_fps(v)) {
+                self.fps_ui(ui);
+            }
+            if let Some(hub) = &config.hub {
+                self.repo_id_ui(ui, &config, hub);
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Secrets...").clicked() {
+                    self.secrets_dialog.open = true;
+                }
+                if ui.button("Open dataset folder").clicked() {
+                    self.open_folder(self.dataset_folder());
+                }
+                if ui.button("Open terminal").on_hover_text("A shell in the working directory with the conda environment activated").clicked() {
+                    if let Err(e) = command::open_shell(&config, self.shell_variant(&config)) {
+                        self.last_error = Some(e);
+                    }
+                }
+                let working_directory = paths::expand(self.shell_variant(&config).working_directory(&config.commands));
+                let button = ui.add_enabled(!working_directory.is_empty(), egui::Button::new("Open working directory"));
+                if button.clicked() {
+                    self.open_folder(Some(PathBuf::from(working_directory)));
+                }
+                let mut hud = !self.state.hud_off;
+                let toggle = ui
+                    .checkbox(&mut hud, "Recording overlay")
+                    .on_hover_text("A small window on top showing the recording state, episode and time");
+                if toggle.changed() {
+                    self.state.hud_off = !hud;
+                    self.state.save();
+                }
+            });
+            self.git_ui(ui, &config);
+            self.active_dataset_ui(ui);
+            self.estop_ui(ui);
+            self.web_ui(ui);
+            self.voice_ui(ui, &config);
+            match self.secrets_dialog.show(ctx, &config.secrets) {
+                Some(secrets::SecretChange::Updated(name)) => {
+                    self.audit.record(&self.user, self.operator(), AuditEvent::SecretUpdated { name })
+                }
+                Some(secrets::SecretChange::Deleted(name)) => {
+                    self.audit.record(&self.user, self.operator(), AuditEvent::SecretDeleted { name })
+                }
+                None => {}
+            }
+            if let Some(e) = &self.last_error {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+            if let Some((process_type, options)) = self.launch_anyway.clone() {
+                if ui.button(format!("Launch {:?} anyway", process_type)).clicked() {
+                    self.spawn_process(process_type, options);
+                }
+            }
+            if self.child_process.is_none() {
+                self.resume_offer_ui(ui);
+                self.delete_episode_ui(ui);
+            }
+            ui.separator();
+
+            self.session_ui(ui);
+            self.advance_sweep();
+            self.sweep_ui(ui);
+            self.advance_script();
+            self.script_ui(ui);
+            if let Some((child, process_type)) = &mut self.child_process {
+                // Check if the process has finished.
+                match child.try_wait() {
+                    Ok(Some(_)) => { // Process finished.
+                        self.child_process = None;
+                        self.finish_run(false);
+                    }
+                    Ok(None) => { // Process is still running.
+                        // Process is still running.
+                        let process_type = *process_type;
+                        self.poll_output();
+                        ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
+                        if self.paused {
+                            ui.label(
+                                egui::RichText::new(format!("{:?} is PAUSED", process_type))
+                                    .size(24.0)
+                                    .color(egui::Color32::YELLOW),
+                            );
+                        } else {
+                            ui.label(format!("{:?} is running...", process_type));
+                        }
+                        if let Some(run) = self.current_run.and_then(|i| self.history.runs().get(i)) {
+                            ui.horizontal(|ui| history::links_ui(ui, &run.links));
+                        }
+                        if let Some(frames) = &self.frames {
+                            frames.ui(ui);
+                        }
+                        if let Some(stall) = &mut self.stall {
+                            stall.poll(self.paused, ui.ctx());
+                            stall.ui(ui);
+                        }
+                        if let Some(saved) = self.saves.as_mut().and_then(|s| s.poll()) {
+                            self.toasts.push(saved.describe());
+                        }
+                        if process_type == ProcessType::Record {
+                            ui.horizontal(|ui| {
+                                for key in keys::RECORD_KEYS {
+                                    let button = ui.add_enabled(self.input.is_some(), egui::Button::new(key.label));
+                                    if button.on_hover_text(key.description).clicked() {
+                                        if let Some(Err(e)) = self.input.as_mut().map(|i| i.send(key.sequence)) {
+                                            self.last_error = Some(e);
+                                        }
+                                    }
+                                }
+                            });
+                        }
+                        ui.horizontal(|ui| {
+                            let (label, hover) = if self.paused {
+                                ("Resume", "Continue the process where it was paused")
+                            } else {
+                                ("Pause", "Freeze the process, e.g. while someone enters the workspace")
+                            };
+                            if ui.button(label).on_hover_text(hover).clicked() {
+                                self.set_paused(!self.paused);
+                            }
+                            if ui.button("Stop").clicked() {
+                                self.kill_process(Stop::Requested("Stop button"));
+                            }
+                            if process_type == ProcessType::Train {
+                                self.tensorboard_button(ui);
+                            }
+                        });
+                        let input = ui.add(
+                            egui::TextEdit::singleline(&mut self.stdin_line)
+                                .hint_text("Answer a prompt, e.g. y (Enter to send)"),
+                        );
+                        if input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                            let line = std::mem::take(&mut self.stdin_line);
+                            let sent = match &mut self.input {
+                                Some(input) => input.type_line(&line),
+                                None => Err("The process reads its terminal; answer there.".to_string()),
+                            };
+                            if let Err(e) = sent {
+                                self.last_error = Some(e);
+                            }
+                            input.request_focus();
+                        }
+                    }
+                    // An error occurred while trying to check the process status.
+                    // This could indicate the process is no longer valid or other system issues.
+                    Err(e) => {
+                        eprintln!("Error waiting for child process: {}", e);
+                        self.child_process = None;
+                        self.finish_run(false);
+                    }
+                }
+            } else if self.session.is_none() && self.sweep.is_none() && self.script.is_none() {
+                // No process is running, show the main buttons. We'll use a vertical layout
+                // and add some spacing to make the UI look clean.
+                ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+                    ui.add_space(20.0); // Add some space from the top separator
+
+                    // Define a larger font and size for the buttons
+                    let button_font = egui::FontId::proportional(20.0);
+                    let button_size = egui::vec2(220.0, 50.0);
+
+                    for (group, (label, process_types)) in config.button_groups().into_iter().enumerate() {
+                        if group > 0 {
+                            ui.separator();
+                            ui.add_space(10.0);
+                        }
+                        if let Some(label) = label {
+                            ui.label(egui::RichText::new(label).heading());
+                            ui.add_space(5.0);
+                        }
+                        for process_type in process_types {
+                            let button = egui::Button::new(
+                                egui::RichText::new(format!("{:?}", process_type)).font(button_font.clone()),
+                            )
+                            .min_size(button_size);
+
+                            let response =
+                                ui.add(button).on_hover_ui(|ui| self.command_preview_ui(ui, process_type));
+                            if response.clicked() {
+                                self.spawn_process(process_type, self.default_launch_options(process_type));
+                            }
+                            self.variant_ui(ui, &config, process_type, button_size.x);
+                            self.checkpoint_ui(ui, &config, process_type, button_size.x);
+                            self.params_ui(ui, &config, process_type);
+                            self.connection_test_ui(ui, &config, process_type);
+                            if process_type == ProcessType::Record {
+                                self.start_session_ui(ui, &config);
+                            }
+                            if process_type == ProcessType::Train {
+                                self.tensorboard_button(ui);
+                            }
+                            response.context_menu(|ui| {
+                                if ui.button("Edit command for this run...").clicked() {
+                                    let options = self.default_launch_options(process_type);
+                                    let (command, _) = self.prepare_command(process_type, &options);
+                                    self.command_edit = Some((process_type, command));
+                                    ui.close_menu();
+                                }
+                            });
+                            if self.command_edit.as_ref().is_some_and(|(t, _)| *t == process_type) {
+                                self.command_edit_ui(ui);
+                            }
+                            ui.add_space(15.0); // Spacing between buttons
+                        }
+                    }
+                });
+            }
+
+            self.companions_ui(ui);
+
+            ui.add_space(20.0);
+            let record = config.commands.variant(ProcessType::Record, self.selected_variant(ProcessType::Record));
+            if !preview::cameras(&record.command).is_empty() {
+                let shown = egui::CollapsingHeader::new("Camera preview")
+                    .show(ui, |ui| self.camera_preview_ui(ui, &config))
+                    .body_returned
+                    .is_some();
+                // Nobody looks at a collapsed preview; it would only hold the cameras.
+                if !shown {
+                    self.preview = None;
+                }
+            }
+            egui::CollapsingHeader::new("Hardware check").show(ui, |ui| self.hardware_check_ui(ui));
+            egui::CollapsingHeader::new("Recent datasets").show(ui, |ui| {
+                self.recent_datasets_ui(ui);
+            });
+            egui::CollapsingHeader::new("Dataset statistics").show(ui, |ui| {
+                self.dataset_stats_ui(ui);
+            });
+            if config.commands.is_configured(ProcessType::Evaluate) {
+                egui::CollapsingHeader::new("Evaluation results").show(ui, |ui| {
+                    evaluation::results_ui(ui, &self.history);
+                });
+            }
+            egui::CollapsingHeader::new("A/B comparison").show(ui, |ui| self.compare_ui(ui, &config));
+            if !config.sweeps.is_empty() {
+                egui::CollapsingHeader::new("Sweeps").show(ui, |ui| self.sweeps_ui(ui, &config));
+            }
+            if !config.scripts.is_empty() {
+                egui::CollapsingHeader::new("Scripts").show(ui, |ui| self.scripts_ui(ui, &config));
+            }
+            egui::CollapsingHeader::new("Controller bridge").show(ui, |ui| {
+                if controller::ui(ui, &mut self.state.controller, self.controller.as_mut()) {
+                    if let Some(bridge) = &self.controller {
+                        bridge.update(&self.state.controller);
+                    }
+                    self.state.save();
+                }
+            });
+            if !self.plugins.is_empty() {
+                let values = self.placeholder_values();
+                egui::CollapsingHeader::new("Plugins")
+                    .default_open(true)
+                    .show(ui, |ui| self.plugins.ui(ui, &values));
+            }
+            egui::CollapsingHeader::new("Run history").show(ui, |ui| {
+                history::recent_runs_ui(ui, &self.history, 10);
+                ui.separator();
+                self.export_panel.show(ui, &self.history);
+            });
+            egui::CollapsingHeader::new("Logging").show(ui, |ui| self.logging_ui(ui));
+        });
+        self.toasts.show(ctx);
+        self.hud(ctx);
+    }
+}
+
+/// The "Re-record" button of a recording session, explaining what happens to
+/// the episode being replaced.
+fn rerecord_button(ui: &mut egui::Ui, session: &session::Session) -> egui::Response {
+    let hover = if session.settings.cleanup.is_some() {
+        "Run the cleanup command, then record this episode again with the same task"
+    } else {
+        "Delete this episode from the dataset if it was saved, then record it again with the same \
+         task and index"
+    };
+    ui.button("Re-record last episode").on_hover_text(hover)
+}
+
+/// Command-line arguments for the application.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Path to the configuration file (TOML, YAML or JSON).
+    #[arg(short, long, default_value = "config.toml")]
+    config: PathBuf,
+    /// Load and validate the config, print the resolved commands and exit
+    /// without starting the GUI. Exits with status 1 if there are errors.
+    #[arg(long)]
+    check_config: bool,
+    /// List the config files opened recently, most recent first, and exit.
+    #[arg(long)]
+    recent: bool,
+    /// Also write the log as JSON lines to this file, e.g. for a log shipper.
+    /// `RUST_LOG` sets the level, `info` by default.
+    #[arg(long, value_name = "FILE")]
+    log_json: Option<PathBuf>,
+    /// Run a command-line action instead of starting the GUI.
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+}
+
+/// Actions that can be run from the command line without the GUI.
+#[derive(Subcommand, Debug)]
+enum CliCommand {
+    /// Export the run history to CSV or JSON.
+    ExportHistory {
+        /// Output format.
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// Only include runs started on or after this date (YYYY-MM-DD).
+        #[arg(long)]
+        since: Option<chrono::NaiveDate>,
+        /// Only include runs started on or before this date (YYYY-MM-DD).
+        #[arg(long)]
+        until: Option<chrono::NaiveDate>,
+        /// Only include runs of this process type.
+        #[arg(long = "type", value_enum)]
+        process_type: Option<ProcessType>,
+        /// File to write to. Prints to stdout if omitted.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Export the config (with its includes) and GUI state as a profile
+    /// archive, to set up another station the same way.
+    ExportProfile {
+        /// Archive to write. In the app's profiles folder if omitted.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Generate the parameters of a command's form from the `--help` of its
+    /// program, printed as TOML to paste into the config. Each flag becomes a
+    /// parameter of the documented type, defaulting to its value in the command.
+    ImportParams {
+        /// The process type whose command to document.
+        #[arg(value_enum)]
+        process_type: ProcessType,
+        /// The variant of the command, counted from 0.
+        #[arg(long, default_value_t = 0)]
+        variant: usize,
+        /// The flags to turn into parameters, e.g. `dataset.num_episodes`; every
+        /// flag the command sets to a plain value if omitted.
+        #[arg(long = "flag")]
+        flags: Vec<String>,
+    },
+    /// Import a profile archive: unpack its config files into the app's
+    /// profiles folder and take over its GUI state.
+    ImportProfile {
+        /// The archive written by `export-profile` or the GUI.
+        archive: PathBuf,
+    },
+}
+
+/// Implements `--check-config`: prints every problem with the config and the
+/// fully resolved commands, and returns the process exit code.
+fn check_config(config_path: &std::path::Path) -> i32 {
+    println!("Checking '{}'", config_path.display());
+    // Only reads the config, so unlike startup it leaves no audit entries.
+    let (config, diagnostics) = match config::load_unaudited(config_path) {
+        Ok(loaded) => loaded,
+        Err(diagnostics) => {
+            for d in &diagnostics {
+                println!("{}", d);
+            }
+            return 1;
+        }
+    };
+    for d in &diagnostics {
+        println!("{}", d);
+    }
+
+    for (process_type, _, variant) in config.commands.all_variants() {
+        let defaults = variant.params.iter().map(|p| (p.name.clone(), p.default_value())).collect();
+        let resolved = command::resolve(&config, variant, &variant.command, &defaults);
+        if variant.name.is_empty() {
+            println!("\n[{:?}]", process_type);
+        } else {
+            println!("\n[{:?}: {}]", process_type, variant.name);
+        }
+        println!("{}", paths::expand(resolved.trim()));
+        for missing in command::missing_programs(&config, variant, &variant.command) {
+            println!("(command not found: {})", missing);
+        }
+        if let Some(Err(e)) = variant.realtime.as_ref().map(|r| r.check()) {
+            println!("({})", e.lines().next().unwrap_or_default());
+        }
+        let mut unfilled = placeholders::names(&resolved);
+        unfilled.extend(placeholders::helpers(&resolved).into_iter().map(|(name, _)| name));
+        if !unfilled.is_empty() {
+            println!("(filled at launch: {})", unfilled.iter().map(|n| format!("{{{}}}", n)).collect::<Vec<_>>().join(", "));
+        }
+    }
+
+    let errors = diagnostics.iter().filter(|d| d.severity == config::Severity::Error).count();
+    println!("\n{} error(s), {} warning(s)", errors, diagnostics.len() - errors);
+    if errors > 0 { 1 } else { 0 }
+}
+
+/// Runs a CLI action with the config at `config_path` and returns the
+/// process exit code.
+fn run_cli(command: CliCommand, config_path: &std::path::Path) -> i32 {
+    let result = match command {
+        CliCommand::ExportHistory { format, since, until, process_type, output } => {
+            let history = History::load(History::default_path());
+            let filter = HistoryFilter { since, until, process_type };
+            let result = match output {
+                Some(path) => history
+                    .export_to_file(&filter, format, &path)
+                    .map(|n| eprintln!("Exported {} run(s) to '{}'.", n, path.display())),
+                None => history.export(&filter, format).map(|out| print!("{}", out)),
+            };
+            result
+        }
+        CliCommand::ExportProfile { output } => {
+            let path = output.unwrap_or_else(profile::default_path);
+            profile::export(config_path, &AppState::load(), &path)
+                .map(|()| eprintln!("Exported the profile to '{}'.", path.display()))
+        }
+        CliCommand::ImportParams { process_type, variant, flags } => {
+            // Only reads the config, like --check-config.
+            match config::load_unaudited(config_path) {
+                Ok((config, _)) => {
+                    introspect::generate(&config, process_type, variant, &flags).map(|toml| print!("{}", toml))
+                }
+                Err(diagnostics) => Err(diagnostics.iter().map(|d| d.to_string()).collect::<Vec<_>>().join("\n")),
+            }
+        }
+        CliCommand::ImportProfile { archive } => {
+            let mut state = AppState::load();
+            profile::import(&archive, &mut state).map(|config| {
+                state.remember_config(&config);
+                state.save();
+                eprintln!("Imported the profile; start the app with --config '{}'.", config.display());
+            })
+        }
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+fn main() -> Result<(), eframe::Error> {
+    // Parse command-line arguments.
+    let args = Args::parse();
+
+    // Initialize logging and tracing. This allows debug messages to be printed to the console.
+    let telemetry = telemetry::init(args.log_json.as_deref());
+    let log_control = telemetry.control();
+    // Logs which sandbox, if any, commands have to get out of.
+    sandbox::current();
+
+    if args.check_config {
+        std::process::exit(check_config(&args.config));
+    }
+    if args.recent {
+        for path in AppState::load().recent_configs {
+            let missing = if path.exists() { "" } else { " (missing)" };
+            println!("{}{}", path.display(), missing);
+        }
+        return Ok(());
+    }
+    if let Some(command) = args.command {
+        std::process::exit(run_cli(command, &args.config));
+    }
 
     let options = eframe::NativeOptions::default();
     eframe::run_native(
@@ -295,7 +3681,7 @@ fn main() -> Result<(), eframe::Error> {
             // Apply the new style
             cc.egui_ctx.set_style(style);
 
-            Box::new(MyApp::new(args.config))
+            Box::new(MyApp::new(args.config, log_control))
         }),
     )
 }
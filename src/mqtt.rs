@@ -0,0 +1,53 @@
+use tracing::{debug, error};
+use serde::Deserialize;
+use std::process::Command;
+
+/// The `[mqtt]` config table: where status events are published.
+///
+/// Messages are sent with `mosquitto_pub`, which must be installed. It reads
+/// credentials and TLS settings from `~/.config/mosquitto_pub`, so they do not
+/// need to be in this config.
+#[derive(Deserialize, Clone, Debug)]
+pub struct MqttConfig {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Prepended to every topic, e.g. `lab/teleop` gives `lab/teleop/process`.
+    #[serde(default = "default_topic_prefix")]
+    pub topic_prefix: String,
+}
+
+fn default_port() -> u16 {
+    1883
+}
+
+fn default_topic_prefix() -> String {
+    "teleop-record-replay".to_string()
+}
+
+/// Publishes `payload` to `<topic_prefix>/<topic>` in the background.
+/// `retain` keeps it on the broker for clients that subscribe later. Failures
+/// are only logged: a missing broker must never get in the way of recording.
+pub fn publish(config: &MqttConfig, topic: &str, payload: &str, retain: bool) {
+    let topic = format!("{}/{}", config.topic_prefix.trim_end_matches('/'), topic);
+    debug!("MQTT {}: {}", topic, payload);
+    let mut command = Command::new("mosquitto_pub");
+    command
+        .args(["-h", &config.host])
+        .args(["-p", &config.port.to_string()])
+        .args(["-q", "1"])
+        .args(["-t", &topic])
+        .args(["-m", payload]);
+    if retain {
+        command.arg("-r");
+    }
+    match command.spawn() {
+        Ok(mut child) => {
+            std::thread::spawn(move || match child.wait() {
+                Ok(status) if !status.success() => error!("mosquitto_pub failed to publish to '{}': {}", topic, status),
+                _ => {}
+            });
+        }
+        Err(e) => error!("Failed to run mosquitto_pub (is it installed?): {}", e),
+    }
+}
@@ -0,0 +1,178 @@
+use chrono::{DateTime, Local};
+use tracing::error;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::command::shell_quote;
+use crate::ProcessType;
+
+/// Where the output of a run started at `started_at` is captured.
+pub fn log_path(process_type: ProcessType, started_at: DateTime<Local>) -> PathBuf {
+    crate::paths::data_dir()
+        .join("runs")
+        .join(format!("{}-{}.log", started_at.format("%Y%m%d-%H%M%S"), process_type.key()))
+}
+
+/// Where the exit status of the run logged to `log` is written, see [`capture`].
+fn status_path(log: &Path) -> PathBuf {
+    log.with_extension("status")
+}
+
+/// Wraps `command` so its output still shows in the terminal but is also
+/// appended to `log`. The exit status is that of `command`, not of `tee`, and
+/// is also written next to the log: the terminal the command runs in exits
+/// with its own status, whatever that of the command.
+pub fn capture(command: &str, log: &Path) -> String {
+    format!(
+        "set -o pipefail; ({}) 2>&1 | tee -a {}; status=$?; echo $status > {}; exit $status",
+        command,
+        shell_quote(&log.display().to_string()),
+        shell_quote(&status_path(log).display().to_string())
+    )
+}
+
+/// The exit status of the command [`capture`]d to `log`, once it has ended;
+/// `None` if it did not get to write it, e.g. because its terminal was closed.
+/// The status file is removed.
+pub fn exit_status(log: &Path) -> Option<i32> {
+    let path = status_path(log);
+    let status = std::fs::read_to_string(&path).ok();
+    let _ = std::fs::remove_file(&path);
+    status?.trim().parse().ok()
+}
+
+/// Removes ANSI escape sequences (colors, cursor movement), which LeRobot
+/// uses e.g. to highlight URLs.
+pub fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        // CSI sequences end at the first character in `@`..=`~`.
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    out
+}
+
+/// The last `count` lines of the log file at `path`, without ANSI escapes;
+/// only its end is read, however long the run.
+pub fn tail(path: &Path, count: usize) -> Result<Vec<String>, String> {
+    const WINDOW: u64 = 64 * 1024;
+    let mut file = File::open(path).map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
+    let mut contents = Vec::new();
+    let start = file
+        .seek(SeekFrom::End(0))
+        .and_then(|end| file.seek(SeekFrom::Start(end.saturating_sub(WINDOW))))
+        .and_then(|start| file.read_to_end(&mut contents).map(|_| start))
+        .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    // Reading from the middle of the file starts in the middle of a line.
+    let lines: Vec<String> = contents
+        .split(|b| matches!(b, b'\n' | b'\r'))
+        .skip(usize::from(start > 0))
+        .filter(|line| !line.is_empty())
+        .map(|line| strip_ansi(&String::from_utf8_lossy(line)))
+        .collect();
+    Ok(lines[lines.len().saturating_sub(count)..].to_vec())
+}
+
+/// Follows a log file as it is written, returning complete new lines.
+pub struct OutputTail {
+    path: PathBuf,
+    /// How far the file has been read.
+    offset: u64,
+    /// The start of a line whose end has not been written yet.
+    partial: Vec<u8>,
+}
+
+impl OutputTail {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, offset: 0, partial: Vec::new() }
+    }
+
+    /// Reads what was appended since the last call and returns the lines
+    /// completed by it, without ANSI escapes. Progress bars redrawn with `\r`
+    /// count as separate lines.
+    pub fn poll(&mut self) -> Vec<String> {
+        let Ok(mut file) = File::open(&self.path) else { return Vec::new() };
+        let before = self.partial.len();
+        if let Err(e) = file.seek(SeekFrom::Start(self.offset)).and_then(|_| file.read_to_end(&mut self.partial)) {
+            error!("Failed to read '{}': {}", self.path.display(), e);
+            return Vec::new();
+        }
+        self.offset += (self.partial.len() - before) as u64;
+        let Some(end) = self.partial.iter().rposition(|b| matches!(b, b'\n' | b'\r')) else { return Vec::new() };
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        complete
+            .split(|b| matches!(b, b'\n' | b'\r'))
+            .filter(|line| !line.is_empty())
+            .map(|line| strip_ansi(&String::from_utf8_lossy(line)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+
+    #[test]
+    fn capture_writes_the_exit_status_of_the_command() {
+        let dir = std::env::temp_dir().join(format!("teleop-output-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("run.log");
+        let script = capture("echo failing; exit 3", &log);
+        let status = Command::new("bash").arg("-c").arg(script).stdout(Stdio::null()).status().unwrap();
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(exit_status(&log), Some(3));
+        // The status file is gone once read.
+        assert_eq!(exit_status(&log), None);
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "failing\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn strip_ansi_removes_colors_and_cursor_movement() {
+        let colored = "\x1b[1;34mwandb\x1b[0m: View run at \x1b[4mhttps://wandb.ai/x\x1b[0m";
+        assert_eq!(strip_ansi(colored), "wandb: View run at https://wandb.ai/x");
+        assert_eq!(strip_ansi("50%\x1b[2K\x1b[1Gdone"), "50%done");
+        assert_eq!(strip_ansi("épisode ✓"), "épisode ✓");
+        // A sequence cut off at the end of the text is dropped.
+        assert_eq!(strip_ansi("text\x1b[3"), "text");
+        assert_eq!(strip_ansi("text\x1b"), "text");
+    }
+
+    #[test]
+    fn output_tail_returns_complete_lines_as_they_are_written() {
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("teleop-tail-test-{}.log", std::process::id()));
+        let mut file = File::create(&path).unwrap();
+        let mut tail = OutputTail::new(path.clone());
+        assert!(tail.poll().is_empty());
+
+        write!(file, "first\nsec").unwrap();
+        assert_eq!(tail.poll(), ["first"]);
+        write!(file, "ond\n\n\x1b[32mthird\x1b[0m\n").unwrap();
+        assert_eq!(tail.poll(), ["second", "third"]);
+        // Progress bars redraw their line with `\r`.
+        write!(file, " 10%\r 50%\r100%\n").unwrap();
+        assert_eq!(tail.poll(), [" 10%", " 50%", "100%"]);
+        // A character split across two writes stays whole.
+        file.write_all(&"é\n".as_bytes()[..1]).unwrap();
+        assert!(tail.poll().is_empty());
+        file.write_all(&"é\n".as_bytes()[1..]).unwrap();
+        assert_eq!(tail.poll(), ["é"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,142 @@
+use eframe::egui;
+
+use crate::ProcessType;
+
+/// Everything that can be triggered from the command palette.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteAction {
+    Launch(ProcessType),
+    Stop,
+    StartSession,
+    /// Run the sweep with this name.
+    StartSweep(String),
+    /// Run the script with this name.
+    RunScript(String),
+    /// Select the command variant with this name for the process type.
+    SelectVariant(ProcessType, String),
+    SelectOperator(String),
+    /// Load the recent config file at this path instead.
+    SwitchConfig(std::path::PathBuf),
+    /// Make the recent dataset with this repo id the active dataset.
+    SelectDataset(String),
+    /// Open the folder of the recent dataset with this repo id.
+    OpenDatasetFolder(String),
+    OpenWorkingDirectory,
+    OpenTerminal,
+    OpenLogs,
+    /// Write a diagnostics bundle for a bug report.
+    ExportDiagnostics,
+    /// Run an action of a plugin, by plugin and action index.
+    PluginAction(usize, usize),
+    Secrets,
+    /// Start TensorBoard on the output of the training run.
+    TensorBoard,
+}
+
+/// Scores how well `query` matches `label` as a case-insensitive subsequence,
+/// favouring consecutive characters and matches at word starts. Returns `None`
+/// if not every character of the query appears in order.
+fn fuzzy_score(query: &str, label: &str) -> Option<i32> {
+    let label: Vec<char> = label.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (pos..label.len()).find(|&i| label[i] == q)?;
+        score += 1;
+        if previous == Some(found.wrapping_sub(1)) {
+            score += 5;
+        }
+        if found == 0 || !label[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        pos = found + 1;
+    }
+    // Prefer shorter labels among equally good matches.
+    Some(score * 100 - label.len() as i32)
+}
+
+/// A searchable list of actions, opened with Ctrl+K.
+#[derive(Default)]
+pub struct CommandPalette {
+    open: bool,
+    query: String,
+    selected: usize,
+}
+
+impl CommandPalette {
+    /// Toggles the palette on Ctrl+K (Cmd+K on macOS), shows it if open and
+    /// returns the action the user picked this frame.
+    pub fn show(&mut self, ctx: &egui::Context, actions: &[(String, PaletteAction)]) -> Option<PaletteAction> {
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::K)) {
+            self.open = !self.open;
+            self.query.clear();
+            self.selected = 0;
+        }
+        if !self.open {
+            return None;
+        }
+
+        let mut matches: Vec<(i32, &(String, PaletteAction))> = actions
+            .iter()
+            .filter_map(|a| fuzzy_score(&self.query, &a.0).map(|score| (score, a)))
+            .collect();
+        matches.sort_by_key(|m| std::cmp::Reverse(m.0));
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down && self.selected + 1 < matches.len() {
+            self.selected += 1;
+        }
+
+        let mut chosen = None;
+        egui::Window::new("Command palette")
+            .title_bar(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 40.0))
+            .resizable(false)
+            .show(ctx, |ui| {
+                let input = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("Type a command...")
+                        .desired_width(360.0),
+                );
+                input.request_focus();
+                if input.changed() {
+                    self.selected = 0;
+                }
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for (i, (_, (label, action))) in matches.iter().enumerate() {
+                        let response = ui.selectable_label(i == self.selected, label);
+                        if i == self.selected && (up || down) {
+                            response.scroll_to_me(None);
+                        }
+                        if response.clicked() {
+                            chosen = Some(action.clone());
+                        }
+                    }
+                    if matches.is_empty() {
+                        ui.weak("No matching commands");
+                    }
+                });
+            });
+
+        if enter {
+            chosen = matches.get(self.selected).map(|(_, (_, action))| action.clone());
+        }
+        if chosen.is_some() || escape {
+            self.open = false;
+        }
+        chosen
+    }
+}
@@ -0,0 +1,162 @@
+use eframe::egui;
+use serde::Deserialize;
+
+/// The kind of value a command parameter takes, which decides its widget.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamType {
+    #[default]
+    String,
+    Int,
+    Float,
+    Bool,
+}
+
+impl ParamType {
+    /// The name of the type in the config.
+    pub fn key(self) -> &'static str {
+        match self {
+            ParamType::String => "string",
+            ParamType::Int => "int",
+            ParamType::Float => "float",
+            ParamType::Bool => "bool",
+        }
+    }
+}
+
+/// A value the operator fills in through a generated form widget, e.g.
+/// `{ name = "num_episodes", type = "int", default = 10, min = 1 }`. The value
+/// is substituted for the `{name}` placeholder in the command, quoted for the
+/// shell, so it is one argument whatever the operator types.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Param {
+    pub name: String,
+    /// Shown next to the widget instead of the name.
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default, rename = "type")]
+    pub kind: ParamType,
+    #[serde(default)]
+    pub default: serde_json::Value,
+    /// Bounds for `int` and `float` parameters.
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// For `string` parameters: the values offered in a dropdown.
+    #[serde(default)]
+    pub choices: Vec<String>,
+}
+
+impl Param {
+    /// The label shown in the form.
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.name)
+    }
+
+    /// The default value as a string, as it would be substituted.
+    pub fn default_value(&self) -> String {
+        match &self.default {
+            serde_json::Value::Null => match self.kind {
+                ParamType::String => self.choices.first().cloned().unwrap_or_default(),
+                ParamType::Int | ParamType::Float => self.min.unwrap_or(0.0).to_string(),
+                ParamType::Bool => "false".to_string(),
+            },
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+
+    /// Checks `value` against the type, bounds and choices of the parameter.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        let number = match self.kind {
+            ParamType::String => {
+                if !self.choices.is_empty() && !self.choices.iter().any(|c| c == value) {
+                    return Err(format!("{}: '{}' is not one of {}", self.label(), value, self.choices.join(", ")));
+                }
+                return Ok(());
+            }
+            ParamType::Bool => {
+                return match value {
+                    "true" | "false" => Ok(()),
+                    _ => Err(format!("{}: expected true or false, got '{}'", self.label(), value)),
+                };
+            }
+            ParamType::Int => value
+                .parse::<i64>()
+                .map(|n| n as f64)
+                .map_err(|_| format!("{}: expected a whole number, got '{}'", self.label(), value))?,
+            ParamType::Float => value
+                .parse::<f64>()
+                .map_err(|_| format!("{}: expected a number, got '{}'", self.label(), value))?,
+        };
+        if let Some(min) = self.min.filter(|min| number < *min) {
+            return Err(format!("{}: must be at least {}", self.label(), min));
+        }
+        if let Some(max) = self.max.filter(|max| number > *max) {
+            return Err(format!("{}: must be at most {}", self.label(), max));
+        }
+        Ok(())
+    }
+
+    /// Shows the widget for this parameter, editing `value` in place. Returns
+    /// whether the value changed and should be saved.
+    pub fn ui(&self, ui: &mut egui::Ui, value: &mut String) -> bool {
+        let range = self.min.unwrap_or(f64::NEG_INFINITY)..=self.max.unwrap_or(f64::INFINITY);
+        match self.kind {
+            ParamType::Int => {
+                let mut number: i64 = value.parse().unwrap_or_default();
+                let changed = ui.add(egui::DragValue::new(&mut number).clamp_range(range)).changed();
+                if changed {
+                    *value = number.to_string();
+                }
+                changed
+            }
+            ParamType::Float => {
+                let mut number: f64 = value.parse().unwrap_or_default();
+                let changed = ui.add(egui::DragValue::new(&mut number).clamp_range(range).speed(0.1)).changed();
+                if changed {
+                    *value = number.to_string();
+                }
+                changed
+            }
+            ParamType::Bool => {
+                let mut checked = value == "true";
+                let changed = ui.checkbox(&mut checked, "").changed();
+                if changed {
+                    *value = checked.to_string();
+                }
+                changed
+            }
+            ParamType::String if !self.choices.is_empty() => {
+                let mut changed = false;
+                egui::ComboBox::from_id_source(("param", &self.name))
+                    .selected_text(value.as_str())
+                    .show_ui(ui, |ui| {
+                        for choice in &self.choices {
+                            if ui.selectable_label(value == choice, choice).clicked() {
+                                *value = choice.clone();
+                                changed = true;
+                            }
+                        }
+                    });
+                changed
+            }
+            ParamType::String => ui.text_edit_singleline(value).changed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn string_values_cannot_leave_their_argument() {
+        let param: Param = toml::from_str("name = \"task\"\nchoices = [\"it's done\"]\n").unwrap();
+        let values = HashMap::from([(param.name.clone(), param.default_value())]);
+        let command = crate::placeholders::substitute("--dataset.single_task={task} --push", &values);
+        assert_eq!(command, r"--dataset.single_task='it'\''s done' --push");
+    }
+}
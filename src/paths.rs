@@ -0,0 +1,101 @@
+use std::env;
+use std::path::PathBuf;
+
+/// Name of the directory our persistent files live in.
+const APP_DIR_NAME: &str = "teleop-record-replay";
+
+/// Returns the directory where the application keeps its persistent data
+/// (run history, logs, ...). Follows the XDG base directory spec and falls back
+/// to `~/.local/share`, or the current directory if `HOME` is not set either.
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+        return PathBuf::from(dir).join(APP_DIR_NAME);
+    }
+    match env::var_os("HOME") {
+        Some(home) => PathBuf::from(home).join(".local/share").join(APP_DIR_NAME),
+        None => PathBuf::from(".").join(APP_DIR_NAME),
+    }
+}
+
+/// Expands a leading `~` and `$VAR` / `${VAR}` references the way the shell
+/// would, so paths from the config can be checked before anything is spawned.
+/// Unset variables expand to an empty string, as in bash.
+pub fn expand(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    let mut rest = path;
+    if rest == "~" || rest.starts_with("~/") {
+        out.push_str(&env::var("HOME").unwrap_or_default());
+        rest = &rest[1..];
+    }
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let (name, consumed) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let len = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..len], len)
+        };
+        if name.is_empty() {
+            // A lone `$` is kept literally.
+            out.push('$');
+            rest = after;
+        } else {
+            out.push_str(&env::var(name).unwrap_or_default());
+            rest = &after[consumed..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Opens `path` in the system file manager.
+pub fn open_in_file_manager(path: &std::path::Path) -> Result<(), String> {
+    if !path.exists() {
+        return Err(format!("'{}' does not exist", path.display()));
+    }
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+    let mut child = std::process::Command::new(opener)
+        .arg(path)
+        .spawn()
+        .map_err(|e| format!("Failed to run '{}': {}", opener, e))?;
+    // Reap the opener in the background so it does not linger as a zombie.
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_home_and_variables() {
+        let home = env::var("HOME").unwrap_or_default();
+        env::set_var("PATHS_TEST_DIR", "/data dir");
+        assert_eq!(expand("~"), home);
+        assert_eq!(expand("~/x"), format!("{}/x", home));
+        assert_eq!(expand("$PATHS_TEST_DIR/a"), "/data dir/a");
+        assert_eq!(expand("${PATHS_TEST_DIR}b"), "/data dirb");
+        assert_eq!(expand("a/$PATHS_TEST_UNSET/b"), "a//b");
+    }
+
+    #[test]
+    fn keeps_what_the_shell_would_not_expand() {
+        assert_eq!(expand("a~/b"), "a~/b");
+        assert_eq!(expand("~user/b"), "~user/b");
+        assert_eq!(expand("cost: 5$"), "cost: 5$");
+        assert_eq!(expand("$ x"), "$ x");
+        assert_eq!(expand("${unclosed"), "${unclosed");
+    }
+}
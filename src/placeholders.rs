@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+use crate::command::shell_quote;
+
+/// Placeholders the app always knows how to fill.
+pub const BUILTIN: &[&str] = &["operator", "repo_id", "dataset_path", "checkpoint", "host", "fps"];
+
+/// A `{name}` placeholder found in a command, with its byte range.
+struct Placeholder<'a> {
+    name: &'a str,
+    start: usize,
+    end: usize,
+    /// The helper command of a `{name:$(command)}` placeholder, whose output
+    /// is the value.
+    helper: Option<&'a str>,
+}
+
+/// The length of `$(...)` at the start of `text`, up to the matching `)`.
+fn substitution_len(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 1 => return Some(i + 1),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Finds every `{name}` and `{name:$(command)}` placeholder in `command`.
+/// Shell parameter expansions like `${HOME}` are skipped so they keep working
+/// as before.
+fn scan(command: &str) -> Vec<Placeholder<'_>> {
+    let bytes = command.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'{' && (i == 0 || bytes[i - 1] != b'$') {
+            let rest = &command[i + 1..];
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            if len > 0 && rest[len..].starts_with('}') {
+                found.push(Placeholder {
+                    name: &rest[..len],
+                    start: i,
+                    end: i + len + 2,
+                    helper: None,
+                });
+                i += len + 2;
+                continue;
+            }
+            let substitution = rest[len..].strip_prefix(":$(").map(|_| &rest[len + 1..]);
+            if let Some(helper_len) = substitution.filter(|_| len > 0).and_then(substitution_len) {
+                if rest[len + 1 + helper_len..].starts_with('}') {
+                    let end = i + 1 + len + 1 + helper_len + 1;
+                    found.push(Placeholder {
+                        name: &rest[..len],
+                        start: i,
+                        end,
+                        helper: Some(&rest[len + 3..len + helper_len]),
+                    });
+                    i = end;
+                    continue;
+                }
+            }
+        }
+        i += 1;
+    }
+    found
+}
+
+/// Returns the names of all placeholders used in `command` that need a value,
+/// without duplicates: those filled by a helper command are left out.
+pub fn names(command: &str) -> Vec<String> {
+    let scanned = scan(command);
+    let filled_by_helper: Vec<&str> = scanned.iter().filter(|p| p.helper.is_some()).map(|p| p.name).collect();
+    let mut names: Vec<String> = Vec::new();
+    for p in scanned.iter().filter(|p| !filled_by_helper.contains(&p.name)) {
+        if !names.iter().any(|n| n == p.name) {
+            names.push(p.name.to_string());
+        }
+    }
+    names
+}
+
+/// The `{name:$(command)}` placeholders in `command`, as `(name, command)`.
+pub fn helpers(command: &str) -> Vec<(String, String)> {
+    scan(command).into_iter().filter_map(|p| Some((p.name.to_string(), p.helper?.to_string()))).collect()
+}
+
+/// Runs the helper command of each `{name:$(command)}` placeholder in
+/// `command` with bash in `dir`, and adds its output to `values` as the
+/// value of `name`, e.g. `{date:$(date +%Y%m%d)}`. Placeholders in the helper
+/// command itself are filled from `values` first.
+pub fn evaluate(command: &str, dir: Option<&Path>, values: &mut HashMap<String, String>) -> Result<(), String> {
+    for (name, helper) in helpers(command) {
+        if values.contains_key(&name) {
+            continue;
+        }
+        let helper = substitute(&helper, values);
+        if let Some(unfilled) = names(&helper).first() {
+            return Err(format!("The helper command of {{{}}} uses {{{}}}, which has no value.", name, unfilled));
+        }
+        let mut shell = Command::new("bash");
+        shell.arg("-c").arg(&helper);
+        if let Some(dir) = dir {
+            shell.current_dir(dir);
+        }
+        let output = crate::sandbox::on_host(shell)
+            .output()
+            .map_err(|e| format!("Failed to run bash for {{{}}}: {}", name, e))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            let reason = if stderr.is_empty() { output.status.to_string() } else { stderr };
+            return Err(format!("The helper command of {{{}}}, '{}', failed: {}", name, helper, reason));
+        }
+        let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        values.insert(name, value);
+    }
+    Ok(())
+}
+
+/// Replaces each placeholder that has a value in `values` with the value,
+/// quoted for the shell so values typed by the operator (`O'Brien`, `$(...)`)
+/// cannot break out of the command. A placeholder already written in quotes,
+/// like `'{task}'`, is replaced along with them. Unknown placeholders are left
+/// untouched.
+pub fn substitute(command: &str, values: &HashMap<String, String>) -> String {
+    replace(command, values, shell_quote)
+}
+
+/// Like [`substitute`], for text that is not run by a shell, e.g. a dataset
+/// name: the values are inserted as they are.
+pub fn fill(text: &str, values: &HashMap<String, String>) -> String {
+    replace(text, values, str::to_string)
+}
+
+fn replace(command: &str, values: &HashMap<String, String>, format: impl Fn(&str) -> String) -> String {
+    let mut out = String::with_capacity(command.len());
+    let mut last = 0;
+    for p in scan(command) {
+        if let Some(value) = values.get(p.name) {
+            let (start, end) = if format(value) == *value { (p.start, p.end) } else { quoted_span(command, &p) };
+            out.push_str(&command[last..start]);
+            out.push_str(&format(value));
+            last = end;
+        }
+    }
+    out.push_str(&command[last..]);
+    out
+}
+
+/// The byte range of `p` including the quotes around it, if it is the only
+/// thing in them.
+fn quoted_span(command: &str, p: &Placeholder) -> (usize, usize) {
+    let before = command[..p.start].chars().next_back();
+    match before {
+        Some(q @ ('\'' | '"')) if command[p.end..].starts_with(q) => (p.start - 1, p.end + 1),
+        _ => (p.start, p.end),
+    }
+}
+
+/// Reverses [`substitute`] for the placeholders used in `template`, so an
+/// edited, filled-in command can be saved as a template again. A value is only
+/// turned back into `{name}` where it follows the same text as in the template
+/// (e.g. `--num_episodes=`), or forms a whole word if the placeholder does, so
+/// a short value like `10` does not replace unrelated numbers.
+pub fn unsubstitute(command: &str, template: &str, values: &HashMap<String, String>) -> String {
+    let mut command = command.to_string();
+    for p in scan(template) {
+        let Some(value) = values.get(p.name).filter(|v| !v.is_empty()) else { continue };
+        // The value as [`substitute`] filled it in.
+        let quoted = shell_quote(value);
+        let (start, end) = if quoted == *value { (p.start, p.end) } else { quoted_span(template, &p) };
+        let word_start = template[..start].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let prefix = &template[word_start..start];
+        let placeholder = format!("{}{}", prefix, &template[start..end]);
+        if !prefix.is_empty() || quoted != *value {
+            // Quotes set the value apart as well as a prefix does.
+            command = command.replace(&format!("{}{}", prefix, quoted), &placeholder);
+        } else {
+            command = command
+                .split(' ')
+                .map(|word| if word == quoted { placeholder.as_str() } else { word })
+                .collect::<Vec<_>>()
+                .join(" ");
+        }
+    }
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    /// The arguments bash splits `command` into.
+    fn arguments(command: &str) -> Vec<String> {
+        let output = Command::new("bash").args(["-c", &format!("printf '%s\\n' {}", command)]).output().unwrap();
+        String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn values_are_quoted_for_the_shell() {
+        let values = values(&[("operator", "O'Brien $(echo pwned)"), ("fps", "30")]);
+        let command = substitute("--operator={operator} --fps {fps}", &values);
+        assert_eq!(command, r"--operator='O'\''Brien $(echo pwned)' --fps 30");
+        assert_eq!(arguments(&command), ["--operator=O'Brien $(echo pwned)", "--fps", "30"]);
+    }
+
+    #[test]
+    fn quotes_around_a_placeholder_are_replaced_with_it() {
+        let task = values(&[("task", "pick it'; rm -rf ~")]);
+        for template in ["--task='{task}'", "--task=\"{task}\""] {
+            assert_eq!(arguments(&substitute(template, &task)), ["--task=pick it'; rm -rf ~"]);
+        }
+        assert_eq!(substitute("--task='{task}'", &values(&[("task", "cube")])), "--task='cube'");
+    }
+
+    #[test]
+    fn filled_text_is_not_quoted() {
+        assert_eq!(fill("{user}_{date}", &values(&[("user", "a b"), ("date", "x")])), "a b_x");
+    }
+
+    #[test]
+    fn helpers_may_contain_braces_and_nested_parentheses() {
+        let command = "rec --root={day:$(date +%F | tr -d '{}')} --n={n:$(echo $(( (1 + 2) * 3 )))} --x={x} ${HOME}";
+        assert_eq!(
+            helpers(command),
+            [
+                ("day".to_string(), "date +%F | tr -d '{}'".to_string()),
+                ("n".to_string(), "echo $(( (1 + 2) * 3 ))".to_string()),
+            ]
+        );
+        assert_eq!(names(command), ["x"]);
+        let mut values = values(&[("x", "1")]);
+        evaluate("{n:$(echo $(( (1 + 2) * 3 )))}", None, &mut values).unwrap();
+        assert_eq!(values["n"], "9");
+    }
+
+    #[test]
+    fn unterminated_helpers_are_not_placeholders() {
+        for command in ["{n:$(echo 1}", "{n:$(echo 1)", "{n:$(echo (1)} x", "{:$(echo 1)}"] {
+            assert!(helpers(command).is_empty(), "{}", command);
+            assert!(names(command).is_empty(), "{}", command);
+            assert_eq!(substitute(command, &values(&[("n", "2")])), command);
+        }
+    }
+
+    #[test]
+    fn unsubstitute_reverses_substitute() {
+        let template = "rec --dataset.repo_id={repo_id} --task='{task}' --episodes {n} --fps=10 --note=\"{note}\"";
+        let values = values(&[("repo_id", "me/data"), ("task", "pick it's"), ("n", "10"), ("note", "plain")]);
+        let command = substitute(template, &values);
+        let filled = r#"rec --dataset.repo_id=me/data --task='pick it'\''s' --episodes 10 --fps=10 --note="plain""#;
+        assert_eq!(command, filled);
+        assert_eq!(unsubstitute(&command, template, &values), template);
+        let edited = command.replace("--fps=10", "--fps=30");
+        assert_eq!(unsubstitute(&edited, template, &values), template.replace("--fps=10", "--fps=30"));
+    }
+}
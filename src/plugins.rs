@@ -0,0 +1,332 @@
+use eframe::egui;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info};
+
+use crate::history::RunRecord;
+use crate::{paths, placeholders, sandbox};
+
+/// The `[plugins]` config table.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct PluginsConfig {
+    /// Directories each holding a plugin: a `plugin.toml` manifest and the
+    /// programs it runs.
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+/// A plugin's `plugin.toml`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Manifest {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Buttons, also offered in the command palette.
+    #[serde(default)]
+    pub actions: Vec<ActionManifest>,
+    /// Values shown in the plugin's panel and refreshed periodically.
+    #[serde(default)]
+    pub widgets: Vec<WidgetManifest>,
+    /// Read metrics from the output of finished runs.
+    #[serde(default)]
+    pub parsers: Vec<ParserManifest>,
+}
+
+/// A button running `command` and showing what it prints.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ActionManifest {
+    pub label: String,
+    pub command: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// A value shown as `label: <last line printed by command>`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct WidgetManifest {
+    pub label: String,
+    pub command: String,
+    /// How often `command` is run, in seconds.
+    #[serde(default = "default_interval")]
+    pub interval_s: f64,
+}
+
+fn default_interval() -> f64 {
+    5.0
+}
+
+/// A program run after each run (of the listed `process` types, or all)
+/// that prints a JSON object of named numbers added to the run's metrics.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ParserManifest {
+    pub command: String,
+    #[serde(default)]
+    pub process: Vec<String>,
+}
+
+/// Reads the manifest of the plugin in `dir`.
+pub fn load_manifest(dir: &Path) -> Result<Manifest, String> {
+    let path = dir.join("plugin.toml");
+    let text = fs::read_to_string(&path).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    toml::from_str(&text).map_err(|e| format!("Invalid plugin manifest '{}': {}", path.display(), e))
+}
+
+/// A loaded plugin.
+#[derive(Clone)]
+pub struct Plugin {
+    pub dir: PathBuf,
+    pub manifest: Manifest,
+}
+
+impl Plugin {
+    /// Runs `command` with bash in the plugin's directory, with the known
+    /// placeholders filled in, and returns what it printed.
+    fn run(&self, command: &str, values: &HashMap<String, String>, env: &[(&str, String)]) -> Result<String, String> {
+        let command = placeholders::substitute(command, values);
+        debug!("Plugin '{}' runs '{}'", self.manifest.name, command);
+        let mut shell = Command::new("bash");
+        shell
+            .arg("-c")
+            .arg(&command)
+            .current_dir(&self.dir)
+            .env("TELEOP_PLUGIN_DIR", &self.dir)
+            .envs(env.iter().map(|(k, v)| (k, v)));
+        let output = sandbox::on_host(shell)
+            .output()
+            .map_err(|e| format!("{}: failed to run bash: {}", self.manifest.name, e))?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().rev().find(|l| !l.trim().is_empty()).map(str::to_string);
+            Err(format!("{}: {}", self.manifest.name, reason.unwrap_or_else(|| output.status.to_string())))
+        }
+    }
+}
+
+/// Runs `command` of `plugin` in the background.
+fn spawn(
+    plugin: &Plugin,
+    command: &str,
+    values: &HashMap<String, String>,
+    env: Vec<(&'static str, String)>,
+) -> Receiver<Result<String, String>> {
+    let (sender, receiver) = mpsc::channel();
+    let plugin = plugin.clone();
+    let command = command.to_string();
+    let values = values.clone();
+    std::thread::spawn(move || {
+        let _ = sender.send(plugin.run(&command, &values, &env));
+    });
+    receiver
+}
+
+/// The last output of an action or widget, and the run producing the next.
+#[derive(Default)]
+struct Output {
+    last: Option<Result<String, String>>,
+    started: Option<Instant>,
+    running: Option<Receiver<Result<String, String>>>,
+}
+
+impl Output {
+    /// Picks up the result of a finished run.
+    fn poll(&mut self) {
+        let Some(receiver) = &self.running else { return };
+        if let Ok(result) = receiver.try_recv() {
+            if let Err(e) = &result {
+                error!("{}", e);
+            }
+            self.last = Some(result);
+            self.running = None;
+        }
+    }
+}
+
+/// The plugins of the config and what their programs last printed.
+#[derive(Default)]
+pub struct Plugins {
+    plugins: Vec<Plugin>,
+    /// By plugin and action index.
+    actions: HashMap<(usize, usize), Output>,
+    /// By plugin and widget index.
+    widgets: HashMap<(usize, usize), Output>,
+    /// Parsers running on finished runs, by the index of the run.
+    parsing: Vec<(usize, Receiver<Result<String, String>>)>,
+}
+
+impl Plugins {
+    /// Loads the plugins listed in `config`. Plugins that cannot be loaded are
+    /// left out; `config::validate` reports why.
+    pub fn load(config: &PluginsConfig) -> Self {
+        let plugins = config
+            .paths
+            .iter()
+            .map(|p| PathBuf::from(paths::expand(p)))
+            .filter_map(|dir| match load_manifest(&dir) {
+                Ok(manifest) => {
+                    info!("Loaded plugin '{}' from '{}'", manifest.name, dir.display());
+                    Some(Plugin { dir, manifest })
+                }
+                Err(e) => {
+                    error!("{}", e);
+                    None
+                }
+            })
+            .collect();
+        Self { plugins, ..Default::default() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Every action, as `(plugin index, action index, "<plugin>: <label>")`.
+    pub fn actions(&self) -> Vec<(usize, usize, String)> {
+        self.plugins
+            .iter()
+            .enumerate()
+            .flat_map(|(p, plugin)| {
+                plugin
+                    .manifest
+                    .actions
+                    .iter()
+                    .enumerate()
+                    .map(move |(a, action)| (p, a, format!("{}: {}", plugin.manifest.name, action.label)))
+            })
+            .collect()
+    }
+
+    /// Starts action `action` of plugin `plugin`, unless it is already running.
+    pub fn run_action(&mut self, plugin: usize, action: usize, values: &HashMap<String, String>) {
+        let Some((p, manifest)) = self.plugins.get(plugin).and_then(|p| Some((p, p.manifest.actions.get(action)?)))
+        else {
+            return;
+        };
+        let output = self.actions.entry((plugin, action)).or_default();
+        if output.running.is_none() {
+            info!("Running '{}' of plugin '{}'", manifest.label, p.manifest.name);
+            output.started = Some(Instant::now());
+            output.running = Some(spawn(p, &manifest.command, values, Vec::new()));
+        }
+    }
+
+    /// Runs the parsers that apply to the finished run at `index`.
+    pub fn parse(&mut self, index: usize, run: &RunRecord, values: &HashMap<String, String>) {
+        let Some(log_file) = &run.log_file else { return };
+        let env = vec![
+            ("TELEOP_LOG_FILE", log_file.display().to_string()),
+            ("TELEOP_PROCESS", run.process_type.key().to_string()),
+            ("TELEOP_EXIT_CODE", run.exit_code.map(|c| c.to_string()).unwrap_or_default()),
+        ];
+        for plugin in &self.plugins {
+            for parser in &plugin.manifest.parsers {
+                if parser.process.is_empty() || parser.process.iter().any(|p| p == run.process_type.key()) {
+                    self.parsing.push((index, spawn(plugin, &parser.command, values, env.clone())));
+                }
+            }
+        }
+    }
+
+    /// The metrics read by the parsers finished since the last call, by the
+    /// index of their run.
+    pub fn poll_parsed(&mut self) -> Vec<(usize, BTreeMap<String, f64>)> {
+        let mut parsed = Vec::new();
+        self.parsing.retain(|(index, receiver)| match receiver.try_recv() {
+            Ok(result) => {
+                let metrics = result.and_then(|output| {
+                    serde_json::from_str::<BTreeMap<String, f64>>(&output)
+                        .map_err(|e| format!("A plugin parser printed no object of numbers: {}", e))
+                });
+                match metrics {
+                    Ok(metrics) => parsed.push((*index, metrics)),
+                    Err(e) => error!("{}", e),
+                }
+                false
+            }
+            Err(mpsc::TryRecvError::Empty) => true,
+            Err(mpsc::TryRecvError::Disconnected) => false,
+        });
+        parsed
+    }
+
+    /// Whether anything is running that the GUI waits for.
+    pub fn busy(&self) -> bool {
+        !self.parsing.is_empty() || self.actions.values().chain(self.widgets.values()).any(|o| o.running.is_some())
+    }
+
+    /// The panel of each plugin: its widgets, refreshed when due, and its
+    /// actions with what they printed last.
+    pub fn ui(&mut self, ui: &mut egui::Ui, values: &HashMap<String, String>) {
+        let mut clicked = None;
+        let mut next_refresh = Duration::MAX;
+        for (p, plugin) in self.plugins.iter().enumerate() {
+            let heading = ui.strong(&plugin.manifest.name);
+            if let Some(description) = &plugin.manifest.description {
+                heading.on_hover_text(description);
+            }
+            for (w, widget) in plugin.manifest.widgets.iter().enumerate() {
+                let output = self.widgets.entry((p, w)).or_default();
+                output.poll();
+                let interval = Duration::from_secs_f64(widget.interval_s.max(0.5));
+                let due = output.started.map_or(Duration::ZERO, |s| interval.saturating_sub(s.elapsed()));
+                if due.is_zero() && output.running.is_none() {
+                    output.started = Some(Instant::now());
+                    output.running = Some(spawn(plugin, &widget.command, values, Vec::new()));
+                }
+                next_refresh = next_refresh.min(if due.is_zero() { interval } else { due });
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", widget.label));
+                    match &output.last {
+                        Some(Ok(value)) => ui.monospace(value.lines().last().unwrap_or_default()),
+                        Some(Err(e)) => ui.colored_label(egui::Color32::RED, e),
+                        None => ui.spinner(),
+                    };
+                });
+            }
+            ui.horizontal_wrapped(|ui| {
+                for (a, action) in plugin.manifest.actions.iter().enumerate() {
+                    let running = self.actions.get(&(p, a)).is_some_and(|o| o.running.is_some());
+                    let button = ui.add_enabled(!running, egui::Button::new(&action.label));
+                    let button = match &action.description {
+                        Some(description) => button.on_hover_text(description),
+                        None => button,
+                    };
+                    if button.clicked() {
+                        clicked = Some((p, a));
+                    }
+                    if running {
+                        ui.spinner();
+                    }
+                }
+            });
+            for (a, action) in plugin.manifest.actions.iter().enumerate() {
+                let Some(output) = self.actions.get_mut(&(p, a)) else { continue };
+                output.poll();
+                match &output.last {
+                    Some(Ok(text)) if !text.is_empty() => {
+                        egui::CollapsingHeader::new(format!("{} output", action.label))
+                            .id_source((p, a))
+                            .show(ui, |ui| ui.monospace(text));
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(egui::Color32::RED, e);
+                    }
+                    _ => {}
+                }
+            }
+            ui.separator();
+        }
+        if let Some((p, a)) = clicked {
+            self.run_action(p, a, values);
+        }
+        if next_refresh < Duration::MAX {
+            ui.ctx().request_repaint_after(next_refresh);
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use eframe::egui;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::OnceLock;
+use tracing::{debug, info};
+
+use crate::{command, sandbox};
+
+/// The size every camera is scaled (and padded) to for the preview.
+const WIDTH: usize = 320;
+const HEIGHT: usize = 240;
+/// Frames per second shown of each camera.
+const PREVIEW_FPS: u32 = 10;
+
+/// A camera of LeRobot's `--robot.cameras`, e.g.
+/// `{top: {type: opencv, index_or_path: 0, width: 640, height: 480, fps: 30}}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Camera {
+    pub name: String,
+    /// The V4L2 device, e.g. `/dev/video0`; `None` for cameras that are not
+    /// opened through OpenCV, such as RealSense ones.
+    pub device: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fps: Option<u32>,
+}
+
+/// The cameras in `command`, in the order they are listed.
+pub fn cameras(command: &str) -> Vec<Camera> {
+    static PATTERNS: OnceLock<[Regex; 2]> = OnceLock::new();
+    let [entry, field] = PATTERNS.get_or_init(|| {
+        [Regex::new(r"([\w.-]+)\s*:\s*\{([^{}]*)\}").unwrap(), Regex::new(r"(\w+)\s*:\s*([^,\s]+)").unwrap()]
+    });
+    let Some(value) = command::flag_value(command, "--robot.cameras") else { return Vec::new() };
+    entry
+        .captures_iter(&value)
+        .map(|camera| {
+            let field = |name: &str| {
+                let value = field.captures_iter(&camera[2]).find(|f| &f[1] == name)?;
+                Some(value[2].trim_matches(['"', '\'']).to_string())
+            };
+            let opencv = field("type").is_none_or(|t| t == "opencv");
+            let device = field("index_or_path").filter(|_| opencv).map(|d| match d.parse::<u32>() {
+                Ok(index) => format!("/dev/video{}", index),
+                Err(_) => d,
+            });
+            let number = |name: &str| field(name).and_then(|n| n.parse::<f64>().ok()).map(|n| n as u32);
+            Camera {
+                name: camera[1].to_string(),
+                device,
+                width: number("width"),
+                height: number("height"),
+                fps: number("fps"),
+            }
+        })
+        .collect()
+}
+
+/// How the camera tiles are arranged.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    #[default]
+    Grid,
+    Row,
+    Column,
+}
+
+impl Layout {
+    const ALL: [Layout; 3] = [Layout::Grid, Layout::Row, Layout::Column];
+
+    fn label(self) -> &'static str {
+        match self {
+            Layout::Grid => "Grid",
+            Layout::Row => "One row",
+            Layout::Column => "One column",
+        }
+    }
+
+    fn columns(self, cameras: usize) -> usize {
+        match self {
+            Layout::Grid => (cameras as f64).sqrt().ceil().max(1.0) as usize,
+            Layout::Row => cameras.max(1),
+            Layout::Column => 1,
+        }
+    }
+}
+
+/// The frames of one camera, read from ffmpeg in the background.
+struct Stream {
+    camera: Camera,
+    child: Option<Child>,
+    frames: Option<Receiver<Result<egui::ColorImage, String>>>,
+    texture: Option<egui::TextureHandle>,
+    error: Option<String>,
+}
+
+impl Stream {
+    fn start(camera: Camera, ctx: &egui::Context) -> Self {
+        let mut stream = Self { camera, child: None, frames: None, texture: None, error: None };
+        let Some(device) = stream.camera.device.clone() else {
+            stream.error = Some("Only OpenCV cameras can be previewed.".to_string());
+            return stream;
+        };
+        let mut ffmpeg = Command::new("ffmpeg");
+        ffmpeg.args(["-hide_banner", "-loglevel", "error", "-f", "v4l2"]);
+        if let (Some(width), Some(height)) = (stream.camera.width, stream.camera.height) {
+            ffmpeg.arg("-video_size").arg(format!("{}x{}", width, height));
+        }
+        if let Some(fps) = stream.camera.fps {
+            ffmpeg.arg("-framerate").arg(fps.to_string());
+        }
+        let filter = format!(
+            "fps={fps},scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2",
+            fps = PREVIEW_FPS,
+            w = WIDTH,
+            h = HEIGHT
+        );
+        ffmpeg.arg("-i").arg(&device).args(["-vf", &filter, "-f", "rawvideo", "-pix_fmt", "rgb24", "-"]);
+        let child = sandbox::on_host(ffmpeg).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                stream.error = Some(format!("Failed to run ffmpeg: {}", e));
+                return stream;
+            }
+        };
+        // Frames not shown yet are dropped rather than queued.
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let (Some(mut stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+            stream.error = Some("No output from ffmpeg".to_string());
+            return stream;
+        };
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let mut buffer = vec![0; WIDTH * HEIGHT * 3];
+            while stdout.read_exact(&mut buffer).is_ok() {
+                let frame = egui::ColorImage::from_rgb([WIDTH, HEIGHT], &buffer);
+                match sender.try_send(Ok(frame)) {
+                    Ok(()) => ctx.request_repaint(),
+                    Err(mpsc::TrySendError::Full(_)) => {}
+                    Err(mpsc::TrySendError::Disconnected(_)) => return,
+                }
+            }
+            // The last line ffmpeg printed tells why the camera stopped.
+            let reason = BufReader::new(stderr).lines().map_while(Result::ok).last();
+            let _ = sender.send(Err(reason.unwrap_or_else(|| format!("Cannot read '{}'", device))));
+            ctx.request_repaint();
+        });
+        info!("Previewing camera '{}'", stream.camera.name);
+        stream.child = Some(child);
+        stream.frames = Some(receiver);
+        stream
+    }
+
+    /// Shows the latest frame of the camera, or why there is none.
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        if let Some(frames) = &self.frames {
+            for frame in frames.try_iter() {
+                match frame {
+                    Ok(frame) => match &mut self.texture {
+                        Some(texture) => texture.set(frame, egui::TextureOptions::LINEAR),
+                        None => {
+                            let id = format!("camera_preview_{}", self.camera.name);
+                            self.texture = Some(ui.ctx().load_texture(id, frame, egui::TextureOptions::LINEAR));
+                        }
+                    },
+                    Err(e) => {
+                        debug!("Preview of camera '{}' stopped: {}", self.camera.name, e);
+                        self.error = Some(e);
+                    }
+                }
+            }
+        }
+        ui.vertical(|ui| {
+            let resolution = match (self.camera.width, self.camera.height) {
+                (Some(width), Some(height)) => format!(" ({}x{})", width, height),
+                _ => String::new(),
+            };
+            ui.label(format!("{}{}", self.camera.name, resolution));
+            let size = egui::vec2(WIDTH as f32, HEIGHT as f32);
+            match (&self.error, &self.texture) {
+                (Some(e), _) => {
+                    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+                    ui.painter().rect_filled(rect, 0.0, egui::Color32::from_gray(20));
+                    let message = egui::RichText::new(e).color(egui::Color32::RED);
+                    ui.put(rect.shrink(8.0), egui::Label::new(message).wrap(true));
+                }
+                (None, Some(texture)) => {
+                    ui.image((texture.id(), size));
+                }
+                (None, None) => {
+                    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+                    ui.painter().rect_filled(rect, 0.0, egui::Color32::from_gray(20));
+                    ui.put(rect, egui::Spinner::new());
+                }
+            }
+        });
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        // The camera must be free again before Record opens it.
+        if let Some(child) = &mut self.child {
+            let _ = sandbox::kill(child);
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Live previews of several cameras at once, to check their framing before
+/// recording. Cameras can only be opened once, so the preview is stopped
+/// before anything is launched.
+pub struct Preview {
+    streams: Vec<Stream>,
+}
+
+impl Preview {
+    pub fn start(cameras: Vec<Camera>, ctx: &egui::Context) -> Self {
+        Self { streams: cameras.into_iter().map(|c| Stream::start(c, ctx)).collect() }
+    }
+
+    /// Whether it shows `cameras`, e.g. after the config changed.
+    pub fn shows(&self, cameras: &[Camera]) -> bool {
+        self.streams.len() == cameras.len() && self.streams.iter().zip(cameras).all(|(s, c)| s.camera == *c)
+    }
+
+    /// The camera tiles, arranged as `layout` says; returns where they are.
+    pub fn ui(&mut self, ui: &mut egui::Ui, layout: Layout) -> egui::Rect {
+        let columns = layout.columns(self.streams.len());
+        egui::Grid::new("camera_preview_grid")
+            .num_columns(columns)
+            .show(ui, |ui| {
+                for (index, stream) in self.streams.iter_mut().enumerate() {
+                    stream.ui(ui);
+                    if (index + 1) % columns == 0 {
+                        ui.end_row();
+                    }
+                }
+            })
+            .response
+            .rect
+    }
+}
+
+/// The layout picker; returns whether the layout changed.
+pub fn layout_ui(ui: &mut egui::Ui, layout: &mut Layout) -> bool {
+    let mut changed = false;
+    egui::ComboBox::from_id_source("camera_preview_layout").selected_text(layout.label()).show_ui(ui, |ui| {
+        for option in Layout::ALL {
+            changed |= ui.selectable_value(layout, option, option.label()).changed();
+        }
+    });
+    changed
+}
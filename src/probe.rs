@@ -0,0 +1,104 @@
+use std::fs::OpenOptions;
+use std::process::Stdio;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+use crate::{command, sandbox};
+
+/// Flags naming the serial ports of LeRobot robots and teleoperators.
+const PORT_FLAGS: &[&str] = &["--robot.port", "--teleop.port"];
+
+/// How long a probe command may take before it counts as failed.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// The serial ports `command` passes to LeRobot.
+pub fn ports(command: &str) -> Vec<String> {
+    PORT_FLAGS.iter().filter_map(|flag| command::flag_value(command, flag)).filter(|p| !p.is_empty()).collect()
+}
+
+/// Opens `port` the way LeRobot would, to see that it is there and usable.
+fn open_port(port: &str) -> Result<(), String> {
+    match OpenOptions::new().read(true).write(true).open(port) {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            Err(format!("{}: permission denied; is this user in the dialout group?", port))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err(format!("{}: not found; is the cable plugged in?", port))
+        }
+        Err(e) => Err(format!("{}: {}", port, e)),
+    }
+}
+
+/// Runs `shell_command`, killing it after [`PROBE_TIMEOUT`]; its last line
+/// of output is the outcome.
+fn run_probe(shell_command: &str) -> Result<String, String> {
+    let mut child = sandbox::bash(shell_command)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run the probe: {}", e))?;
+    let started = Instant::now();
+    while child.try_wait().map_err(|e| e.to_string())?.is_none() {
+        if started.elapsed() > PROBE_TIMEOUT {
+            let _ = sandbox::kill(&mut child);
+            let _ = child.wait();
+            return Err(format!("No answer within {} s", PROBE_TIMEOUT.as_secs()));
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    let last_line = |bytes: &[u8]| {
+        let text = String::from_utf8_lossy(bytes);
+        text.lines().rev().map(str::trim).find(|l| !l.is_empty()).map(str::to_string)
+    };
+    if output.status.success() {
+        Ok(last_line(&output.stdout).unwrap_or_else(|| "Connected".to_string()))
+    } else {
+        let reason = last_line(&output.stderr).or_else(|| last_line(&output.stdout));
+        Err(reason.unwrap_or_else(|| output.status.to_string()))
+    }
+}
+
+/// A connection test running in the background, and its outcome once known.
+pub struct ConnectionTest {
+    running: Option<Receiver<Result<String, String>>>,
+    pub outcome: Option<Result<String, String>>,
+}
+
+impl ConnectionTest {
+    /// Runs `probe` (a full shell command) if given, otherwise opens `ports`.
+    pub fn start(probe: Option<String>, ports: Vec<String>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let result = match probe {
+                Some(probe) => run_probe(&probe),
+                None => ports.iter().try_for_each(|p| open_port(p)).map(|()| format!("{} opened", ports.join(", "))),
+            };
+            let _ = sender.send(result);
+        });
+        Self { running: Some(receiver), outcome: None }
+    }
+
+    /// Whether the test is still running; picks up its outcome once done.
+    pub fn running(&mut self) -> bool {
+        let Some(receiver) = &self.running else { return false };
+        match receiver.try_recv() {
+            Ok(outcome) => {
+                match &outcome {
+                    Ok(message) => info!("Connection test passed: {}", message),
+                    Err(e) => error!("Connection test failed: {}", e),
+                }
+                self.outcome = Some(outcome);
+                self.running = None;
+                false
+            }
+            Err(mpsc::TryRecvError::Empty) => true,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.running = None;
+                false
+            }
+        }
+    }
+}
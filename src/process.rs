@@ -0,0 +1,52 @@
+use std::fs;
+use std::process::Command;
+
+/// The parent pid of `pid`, read from `/proc/<pid>/stat`.
+fn parent(pid: u32) -> Option<u32> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name is in parentheses and may contain spaces, so parse
+    // from the last `)`: "<state> <ppid> ...".
+    let rest = &stat[stat.rfind(')')? + 1..];
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Every process below `pid` in the process tree (not including `pid`).
+pub fn descendants(pid: u32) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir("/proc") else { return Vec::new() };
+    let all: Vec<(u32, u32)> = entries
+        .filter_map(|e| e.ok()?.file_name().to_str()?.parse().ok())
+        .filter_map(|p| Some((p, parent(p)?)))
+        .collect();
+    let mut found = vec![pid];
+    let mut i = 0;
+    while i < found.len() {
+        let current = found[i];
+        found.extend(all.iter().filter(|(_, ppid)| *ppid == current).map(|(p, _)| *p));
+        i += 1;
+    }
+    found.remove(0);
+    found
+}
+
+/// Sends `signal` (e.g. `STOP`) to the processes launched in the terminal
+/// `terminal_pid`. The terminal itself is left alone so it keeps redrawing.
+pub fn signal_children(terminal_pid: u32, signal: &str) -> Result<(), String> {
+    if crate::sandbox::current() == Some(crate::sandbox::Sandbox::Flatpak) {
+        return Err("The processes started on the host cannot be seen from the Flatpak sandbox.".to_string());
+    }
+    let pids = descendants(terminal_pid);
+    if pids.is_empty() {
+        return Err("No processes found in the terminal.".to_string());
+    }
+    let status = Command::new("kill")
+        .arg("-s")
+        .arg(signal)
+        .args(pids.iter().map(u32::to_string))
+        .status()
+        .map_err(|e| format!("Failed to run kill: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Failed to send SIG{} to {:?}", signal, pids))
+    }
+}
@@ -0,0 +1,261 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tracing::info;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::config;
+use crate::paths;
+use crate::state::AppState;
+
+/// The manifest of a profile archive.
+const MANIFEST: &str = "profile.json";
+/// The GUI state in a profile archive.
+const STATE: &str = "state.json";
+/// The folder of a profile archive holding the config files.
+const CONFIG_DIR: &str = "config";
+
+/// What a profile archive holds besides the config files and GUI state.
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    /// The version of the app that wrote the profile.
+    version: String,
+    created: chrono::DateTime<chrono::Local>,
+    /// The name in the archive of the config file that was loaded, e.g.
+    /// `config/config.toml`; the others are its includes.
+    config: String,
+}
+
+fn to_json(value: &impl Serialize) -> Result<Vec<u8>, String> {
+    serde_json::to_vec_pretty(value).map_err(|e| format!("Failed to serialize the profile: {}", e))
+}
+
+/// Writes the setup of this station to the zip archive at `path`: the config
+/// file at `config_path` with everything it includes, and the GUI state
+/// (operator, selected variants, parameters, controller bridge, ...). Secrets
+/// stay in the OS keyring, and the recent datasets and configs, which only
+/// make sense on this machine, are left out.
+pub fn export(config_path: &Path, state: &AppState, path: &Path) -> Result<(), String> {
+    let files = config::files(config_path)?;
+    // Includes are relative to the including file, so they are stored
+    // relative to the folder of the loaded config to keep working.
+    let base = files[0].parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut entries = Vec::new();
+    for file in &files {
+        let relative = file.strip_prefix(&base).map_err(|_| {
+            format!(
+                "'{}' is outside the folder of '{}'; move it there to export a profile.",
+                file.display(),
+                config_path.display()
+            )
+        })?;
+        let contents = fs::read(file).map_err(|e| format!("Failed to read '{}': {}", file.display(), e))?;
+        entries.push((format!("{}/{}", CONFIG_DIR, relative.to_string_lossy()), contents));
+    }
+    let manifest = Manifest {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        created: chrono::Local::now(),
+        config: entries[0].0.clone(),
+    };
+    let portable = AppState { recent_datasets: Vec::new(), recent_configs: Vec::new(), ..state.clone() };
+    entries.push((MANIFEST.to_string(), to_json(&manifest)?));
+    entries.push((STATE.to_string(), to_json(&portable)?));
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+    }
+    let file = File::create(path).map_err(|e| format!("Failed to create '{}': {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    for (name, contents) in entries {
+        zip.start_file(name, SimpleFileOptions::default())
+            .and_then(|_| Ok(zip.write_all(&contents)?))
+            .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+    }
+    zip.finish().map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+    info!("Exported the profile of '{}' to '{}'", config_path.display(), path.display());
+    Ok(())
+}
+
+fn read_json<T: DeserializeOwned>(zip: &mut ZipArchive<File>, name: &str, archive: &Path) -> Result<T, String> {
+    let mut entry = zip
+        .by_name(name)
+        .map_err(|_| format!("'{}' is not a profile: it has no {}.", archive.display(), name))?;
+    let mut contents = String::new();
+    entry
+        .read_to_string(&mut contents)
+        .map_err(|e| format!("Failed to read '{}': {}", archive.display(), e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Invalid {} in '{}': {}", name, archive.display(), e))
+}
+
+/// Writes the config files of `zip` to `dir`.
+fn unpack(zip: &mut ZipArchive<File>, dir: &Path, archive: &Path) -> Result<(), String> {
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index).map_err(|e| format!("Failed to read '{}': {}", archive.display(), e))?;
+        // Names like `../../.bashrc` must not write outside the folder.
+        let Some(name) = entry.enclosed_name() else {
+            return Err(format!("'{}' contains a file outside its folders.", archive.display()));
+        };
+        let Ok(relative) = name.strip_prefix(CONFIG_DIR) else { continue };
+        if entry.is_dir() {
+            continue;
+        }
+        let target = dir.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+        }
+        let mut contents = Vec::new();
+        entry
+            .read_to_end(&mut contents)
+            .map_err(|e| format!("Failed to read '{}': {}", archive.display(), e))?;
+        fs::write(&target, contents).map_err(|e| format!("Failed to write '{}': {}", target.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Unpacks the config files of the profile archive at `archive` into a folder
+/// of their own under [`default_dir`] and takes over its GUI state, keeping the
+/// recent datasets and configs of this machine. Returns the config file to load.
+pub fn import(archive: &Path, state: &mut AppState) -> Result<PathBuf, String> {
+    import_into(archive, &default_dir(), state)
+}
+
+/// [`import`]s the profile `archive` into a folder of its own under `profiles`.
+fn import_into(archive: &Path, profiles: &Path, state: &mut AppState) -> Result<PathBuf, String> {
+    let file = File::open(archive).map_err(|e| format!("Failed to open '{}': {}", archive.display(), e))?;
+    let mut zip =
+        ZipArchive::new(file).map_err(|e| format!("'{}' is not a zip archive: {}", archive.display(), e))?;
+    let manifest: Manifest = read_json(&mut zip, MANIFEST, archive)?;
+    let imported: AppState = read_json(&mut zip, STATE, archive)?;
+    let config = Path::new(&manifest.config)
+        .strip_prefix(CONFIG_DIR)
+        .map_err(|_| format!("Invalid {} in '{}': no config file", MANIFEST, archive.display()))?
+        .to_path_buf();
+
+    let name = archive.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let dir = profiles.join(name);
+    if dir.exists() {
+        return Err(format!("'{}' already exists; remove it to import the profile again.", dir.display()));
+    }
+    if let Err(e) = unpack(&mut zip, &dir, archive) {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+    info!(
+        "Imported the profile '{}' (written by version {} on {}) into '{}'",
+        archive.display(),
+        manifest.version,
+        manifest.created.format("%Y-%m-%d"),
+        dir.display()
+    );
+    *state = AppState {
+        recent_datasets: std::mem::take(&mut state.recent_datasets),
+        recent_configs: std::mem::take(&mut state.recent_configs),
+        ..imported
+    };
+    Ok(dir.join(config))
+}
+
+/// Whether `path` looks like a profile archive, e.g. one dropped onto the window.
+pub fn is_archive(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+}
+
+/// A new archive in [`default_dir`], named after the current time.
+pub fn default_path() -> PathBuf {
+    default_dir().join(format!("profile-{}.zip", chrono::Local::now().format("%Y%m%d-%H%M%S")))
+}
+
+/// Where profiles are exported to and imported into.
+pub fn default_dir() -> PathBuf {
+    paths::data_dir().join("profiles")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::RecentDataset;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("teleop-profile-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entry_names(archive: &Path) -> Vec<String> {
+        let zip = ZipArchive::new(File::open(archive).unwrap()).unwrap();
+        let mut names: Vec<String> = zip.file_names().map(|name| name.unwrap().into_owned()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn profiles_survive_export_and_import() {
+        let dir = temp_dir("round-trip");
+        let station = dir.join("station");
+        fs::create_dir_all(station.join("robots")).unwrap();
+        fs::write(station.join("config.toml"), "include = [\"robots/so100.toml\"]\n").unwrap();
+        let robot = "[commands]\nrecord = \"record\"\n";
+        fs::write(station.join("robots/so100.toml"), robot).unwrap();
+        fs::write(station.join("hf_token.txt"), "hf_not_included").unwrap();
+        let recent = RecentDataset { repo_id: "me/data".to_string(), root: None, last_used: chrono::Local::now() };
+        let state = AppState {
+            operator: "ana".to_string(),
+            recent_datasets: vec![recent.clone()],
+            recent_configs: vec![station.join("config.toml")],
+            ..Default::default()
+        };
+
+        let archive = dir.join("lab.zip");
+        export(&station.join("config.toml"), &state, &archive).unwrap();
+        assert_eq!(
+            entry_names(&archive),
+            ["config/config.toml", "config/robots/so100.toml", "profile.json", "state.json"]
+        );
+        let mut zip = ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+        let exported: AppState = read_json(&mut zip, STATE, &archive).unwrap();
+        assert!(exported.recent_datasets.is_empty() && exported.recent_configs.is_empty());
+
+        let mut other = AppState { recent_datasets: vec![recent], ..Default::default() };
+        let config = import_into(&archive, &dir.join("profiles"), &mut other).unwrap();
+        assert_eq!(config, dir.join("profiles/lab/config.toml"));
+        assert_eq!(fs::read_to_string(dir.join("profiles/lab/robots/so100.toml")).unwrap(), robot);
+        assert_eq!(other.operator, "ana");
+        assert_eq!(other.recent_datasets.len(), 1);
+        assert!(other.recent_configs.is_empty());
+        assert!(import_into(&archive, &dir.join("profiles"), &mut other).unwrap_err().contains("already exists"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn entries_outside_the_archive_folders_are_rejected() {
+        let dir = temp_dir("escape");
+        let archive = dir.join("evil.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        let manifest = Manifest {
+            version: "0".to_string(),
+            created: chrono::Local::now(),
+            config: "config/config.toml".to_string(),
+        };
+        let entries = [
+            (MANIFEST, to_json(&manifest).unwrap()),
+            (STATE, to_json(&AppState::default()).unwrap()),
+            ("config/config.toml", Vec::new()),
+            ("config/../../escaped.txt", b"gotcha".to_vec()),
+        ];
+        for (name, contents) in entries {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(&contents).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let error = import_into(&archive, &dir.join("profiles"), &mut AppState::default()).unwrap_err();
+        assert!(error.contains("outside its folders"), "{}", error);
+        assert!(!dir.join("escaped.txt").exists());
+        assert!(!dir.join("profiles/evil").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,94 @@
+use serde::Deserialize;
+
+use crate::{command, sandbox};
+
+/// How a command is scheduled.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Policy {
+    /// `SCHED_FIFO`: runs until it blocks, ahead of every normal process.
+    #[default]
+    Fifo,
+    /// `SCHED_RR`: like `Fifo`, but shares the CPU with processes of the same priority.
+    Rr,
+    /// Normal scheduling with a lower niceness, i.e. a bigger share of the CPU.
+    Nice,
+}
+
+/// The `realtime` option of a command: launch it with elevated scheduling, to
+/// reduce the jitter of its control loop on a busy workstation. Needs the
+/// privileges to do so, e.g. `rtprio` and `nice` in
+/// `/etc/security/limits.conf` or `CAP_SYS_NICE`.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct RealtimeConfig {
+    #[serde(default)]
+    pub policy: Policy,
+    /// The priority, 1 to 99, for `fifo` and `rr`; the niceness, -20 to 19,
+    /// for `nice`.
+    #[serde(default)]
+    pub priority: Option<i32>,
+    /// Raise the locked-memory limit so the program can lock its memory with
+    /// `mlockall` and is not paged out. Memory locks do not survive `exec`, so
+    /// the program has to lock itself.
+    #[serde(default)]
+    pub lock_memory: bool,
+}
+
+impl RealtimeConfig {
+    pub fn priority(&self) -> i32 {
+        match (self.priority, self.policy) {
+            (Some(priority), _) => priority,
+            (None, Policy::Nice) => -10,
+            (None, _) => 50,
+        }
+    }
+
+    /// The range `priority` must be in.
+    pub fn range(&self) -> std::ops::RangeInclusive<i32> {
+        match self.policy {
+            Policy::Nice => -20..=19,
+            _ => 1..=99,
+        }
+    }
+
+    /// The program and arguments that run a command with this scheduling.
+    fn launcher(&self) -> String {
+        match self.policy {
+            Policy::Fifo => format!("chrt --fifo {}", self.priority()),
+            Policy::Rr => format!("chrt --rr {}", self.priority()),
+            Policy::Nice => format!("nice -n {}", self.priority()),
+        }
+    }
+
+    /// `shell_command`, run with this scheduling. Everything it starts
+    /// inherits the scheduling.
+    pub fn wrap(&self, shell_command: &str) -> String {
+        let limit = if self.lock_memory { "ulimit -l unlimited && " } else { "" };
+        format!("{}{} bash -c {}", limit, self.launcher(), command::shell_quote(shell_command))
+    }
+
+    /// Whether this scheduling can be had, tried on a command that does
+    /// nothing; the error tells how to get the privileges. `nice` only warns
+    /// when it cannot set the niceness, so a warning counts as a failure.
+    pub fn check(&self) -> Result<(), String> {
+        let output = sandbox::bash(&format!("{} >/dev/null", self.wrap("true")))
+            .output()
+            .map_err(|e| format!("Failed to run bash: {}", e))?;
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if output.status.success() && stderr.is_empty() {
+            return Ok(());
+        }
+        let how = match self.policy {
+            Policy::Nice => "a `nice` limit",
+            _ => "an `rtprio` limit",
+        };
+        Err(format!(
+            "This command is configured to run with elevated scheduling, which failed: {}\n\
+             Give your user {}{} in /etc/security/limits.conf (then log in again), \
+             or remove `realtime` from the command.",
+            stderr,
+            how,
+            if self.lock_memory { " and an unlimited `memlock` limit" } else { "" }
+        ))
+    }
+}
@@ -0,0 +1,113 @@
+use eframe::egui;
+use tracing::{debug, error, info};
+use serde::Deserialize;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Stdio};
+use std::sync::mpsc::{self, Receiver};
+
+use crate::command::shell_quote;
+use crate::sandbox;
+
+/// The `[ros]` config table: publishing status to and taking emergency stops
+/// from a ROS 2 system.
+///
+/// This goes through the `ros2` command line tools rather than linking against
+/// ROS, so the app builds and runs on machines without a ROS installation.
+#[derive(Deserialize, Clone, Debug)]
+pub struct RosConfig {
+    /// Run before every `ros2` command, e.g. `source /opt/ros/humble/setup.bash`.
+    #[serde(default)]
+    pub setup: Option<String>,
+    /// `std_msgs/msg/String` topic the JSON status events are published on.
+    #[serde(default = "default_status_topic")]
+    pub status_topic: String,
+    /// `std_msgs/msg/Bool` topic; `true` on it stops the running process.
+    #[serde(default)]
+    pub estop_topic: Option<String>,
+}
+
+fn default_status_topic() -> String {
+    "/teleop_record_replay/status".to_string()
+}
+
+/// `command` with the setup of `config` run first.
+fn with_setup(config: &RosConfig, command: &str) -> String {
+    match config.setup.as_deref().filter(|s| !s.trim().is_empty()) {
+        Some(setup) => format!("{} && {}", setup, command),
+        None => command.to_string(),
+    }
+}
+
+/// Publishes `payload` once on the status topic in the background. Failures
+/// are only logged, as for MQTT.
+pub fn publish(config: &RosConfig, payload: &str) {
+    // The message is given as YAML; a single-quoted YAML string escapes `'` as `''`.
+    let message = format!("{{data: '{}'}}", payload.replace('\'', "''"));
+    let command = format!(
+        "ros2 topic pub --once {} std_msgs/msg/String {}",
+        shell_quote(&config.status_topic),
+        shell_quote(&message)
+    );
+    debug!("ROS {}: {}", config.status_topic, payload);
+    match sandbox::bash(&with_setup(config, &command)).stdout(Stdio::null()).spawn() {
+        Ok(mut child) => {
+            std::thread::spawn(move || match child.wait() {
+                Ok(status) if !status.success() => error!("ros2 topic pub failed: {}", status),
+                _ => {}
+            });
+        }
+        Err(e) => error!("Failed to run ros2: {}", e),
+    }
+}
+
+/// Listens to the e-stop topic with `ros2 topic echo` for as long as it lives.
+pub struct EstopListener {
+    pub topic: String,
+    child: Child,
+    /// Receives a message for every `true` on the topic.
+    stops: Receiver<()>,
+}
+
+impl EstopListener {
+    /// Starts listening on `topic`; `ctx` is woken up when a stop arrives so
+    /// it is handled even while the GUI is idle.
+    pub fn start(config: &RosConfig, topic: &str, ctx: egui::Context) -> Result<Self, String> {
+        let command = format!("exec ros2 topic echo {} std_msgs/msg/Bool", shell_quote(topic));
+        let mut child = sandbox::bash(&with_setup(config, &command))
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run ros2: {}", e))?;
+        let stdout = child.stdout.take().ok_or_else(|| "No output from ros2".to_string())?;
+        let (sender, stops) = mpsc::channel();
+        std::thread::spawn(move || {
+            // `ros2 topic echo` prints each message as YAML: `data: true` then `---`.
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if line.trim() == "data: true" {
+                    if sender.send(()).is_err() {
+                        break;
+                    }
+                    ctx.request_repaint();
+                }
+            }
+        });
+        info!("Listening for emergency stops on ROS topic '{}'", topic);
+        Ok(Self { topic: topic.to_string(), child, stops })
+    }
+
+    /// Whether a stop arrived since the last call.
+    pub fn triggered(&self) -> bool {
+        self.stops.try_iter().count() > 0
+    }
+
+    /// Whether `ros2 topic echo` is still running.
+    pub fn running(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+}
+
+impl Drop for EstopListener {
+    fn drop(&mut self) {
+        let _ = sandbox::kill(&mut self.child);
+        let _ = self.child.wait();
+    }
+}
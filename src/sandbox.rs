@@ -0,0 +1,79 @@
+use std::path::Path;
+use std::process::Command;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+/// The app sandbox the app runs in, if any.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sandbox {
+    Flatpak,
+    Snap,
+}
+
+/// Detects the sandbox once: Flatpak puts `/.flatpak-info` in every sandbox,
+/// snapd sets `$SNAP`.
+pub fn current() -> Option<Sandbox> {
+    static SANDBOX: OnceLock<Option<Sandbox>> = OnceLock::new();
+    *SANDBOX.get_or_init(|| {
+        let sandbox = if Path::new("/.flatpak-info").exists() {
+            Some(Sandbox::Flatpak)
+        } else if std::env::var_os("SNAP").is_some() {
+            Some(Sandbox::Snap)
+        } else {
+            None
+        };
+        match sandbox {
+            Some(Sandbox::Flatpak) => info!("Running in a Flatpak sandbox; commands run on the host"),
+            Some(Sandbox::Snap) => warn!(
+                "Running as a snap; commands run inside its confinement and may not reach conda, \
+                 terminals or serial devices unless the snap uses classic confinement"
+            ),
+            None => {}
+        }
+        sandbox
+    })
+}
+
+/// `command` as it must be spawned to run on the host: unchanged, or in a
+/// Flatpak sandbox through `flatpak-spawn --host`, which does not pass on the
+/// environment or working directory by itself (so they are passed as its
+/// arguments, visible inside the sandbox only). Call it before setting up
+/// stdin/stdout/stderr, which cannot be carried over.
+pub fn on_host(command: Command) -> Command {
+    if current() != Some(Sandbox::Flatpak) {
+        return command;
+    }
+    let mut host = Command::new("flatpak-spawn");
+    host.arg("--host");
+    if let Some(dir) = command.get_current_dir() {
+        host.arg(format!("--directory={}", dir.display()));
+    }
+    for (key, value) in command.get_envs() {
+        if let Some(value) = value {
+            host.arg(format!("--env={}={}", key.to_string_lossy(), value.to_string_lossy()));
+        }
+    }
+    host.arg(command.get_program()).args(command.get_args());
+    host
+}
+
+/// `bash -c script`, run on the host.
+pub fn bash(script: &str) -> Command {
+    let mut command = Command::new("bash");
+    command.arg("-c").arg(script);
+    on_host(command)
+}
+
+/// Stops the host process started through `child`. In a Flatpak sandbox the
+/// child is `flatpak-spawn`, which passes SIGTERM on but cannot pass SIGKILL.
+pub fn kill(child: &mut std::process::Child) -> std::io::Result<()> {
+    if current() != Some(Sandbox::Flatpak) {
+        return child.kill();
+    }
+    let status = Command::new("kill").arg("-TERM").arg(child.id().to_string()).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!("kill exited with {}", status)))
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use crate::dataset;
+
+/// How often the dataset's metadata is read while Record runs.
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Episodes that landed on disk since the last look.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Saved {
+    /// Index of the first of them.
+    pub first: u64,
+    pub count: u64,
+    pub frames: u64,
+    /// Size of their data and videos; `None` if the dataset does not keep
+    /// files per episode.
+    pub bytes: Option<u64>,
+}
+
+impl Saved {
+    /// E.g. `Episode 12 saved: 450 frames, 38.2 MB`.
+    pub fn describe(&self) -> String {
+        let episodes = match self.count {
+            1 => format!("Episode {} saved", self.first),
+            n => format!("Episodes {}-{} saved", self.first, self.first + n - 1),
+        };
+        match self.bytes {
+            Some(bytes) => format!("{}: {} frames, {:.1} MB", episodes, self.frames, bytes as f64 / 1e6),
+            None => format!("{}: {} frames", episodes, self.frames),
+        }
+    }
+}
+
+/// Notices the episodes Record saves to its dataset, from the totals LeRobot
+/// updates in `meta/info.json` once an episode is completely written.
+pub struct SaveWatch {
+    dir: PathBuf,
+    /// Episodes and frames at the last look.
+    last: (u64, u64),
+    checked_at: Instant,
+}
+
+impl SaveWatch {
+    pub fn new(dir: PathBuf) -> Self {
+        let last = dataset::read_info(&dir).map(|i| (i.total_episodes, i.total_frames)).unwrap_or_default();
+        Self { dir, last, checked_at: Instant::now() }
+    }
+
+    /// The episodes saved since the last call, looking at most every few seconds.
+    pub fn poll(&mut self) -> Option<Saved> {
+        if self.checked_at.elapsed() < CHECK_INTERVAL {
+            return None;
+        }
+        self.check()
+    }
+
+    /// The episodes saved since the last call, e.g. once Record exited.
+    pub fn check(&mut self) -> Option<Saved> {
+        self.checked_at = Instant::now();
+        let info = dataset::read_info(&self.dir).ok()?;
+        let (episodes, frames) = (info.total_episodes, info.total_frames);
+        let (last_episodes, last_frames) = std::mem::replace(&mut self.last, (episodes, frames));
+        // Fewer episodes than before means one was deleted, not saved.
+        if episodes <= last_episodes {
+            return None;
+        }
+        let files: Vec<PathBuf> = (last_episodes..episodes).flat_map(|i| info.episode_files(i)).collect();
+        let bytes = (!files.is_empty())
+            .then(|| files.iter().filter_map(|f| std::fs::metadata(self.dir.join(f)).ok()).map(|m| m.len()).sum());
+        let saved = Saved {
+            first: last_episodes,
+            count: episodes - last_episodes,
+            frames: frames.saturating_sub(last_frames),
+            bytes,
+        };
+        info!("{} to '{}'", saved.describe(), self.dir.display());
+        Some(saved)
+    }
+}
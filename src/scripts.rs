@@ -0,0 +1,387 @@
+use regex::Regex;
+use rhai::{Dynamic, Engine, EvalAltResult, Map};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::history::RunRecord;
+use crate::{output, paths, ProcessType};
+
+/// A `[[scripts]]` config entry: a Rhai workflow script.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ScriptConfig {
+    pub name: String,
+    pub path: String,
+}
+
+impl ScriptConfig {
+    pub fn path(&self) -> PathBuf {
+        PathBuf::from(paths::expand(&self.path))
+    }
+}
+
+/// How a run launched by a script ended.
+#[derive(Clone, Debug)]
+pub struct RunResult {
+    pub exit_code: Option<i32>,
+    pub stopped: bool,
+    pub fault: Option<String>,
+    /// The captured output, without ANSI escapes.
+    pub output: String,
+    pub metrics: BTreeMap<String, f64>,
+}
+
+/// What a script needs from the app.
+pub enum Request {
+    /// Launch `process_type` with `values` (flags for keys starting with
+    /// `--`, placeholders otherwise) and report how the run ended.
+    Run {
+        process_type: ProcessType,
+        values: Vec<(String, String)>,
+        reply: Sender<Result<RunResult, String>>,
+    },
+    /// Launch `process_type` like [`Request::Run`], but reply with the line
+    /// matching the `ready_pattern` of its command once it prints one, and
+    /// leave it running.
+    Start {
+        process_type: ProcessType,
+        values: Vec<(String, String)>,
+        reply: Sender<Result<String, String>>,
+    },
+    /// Report how the run started last ended, once it has.
+    Wait { reply: Sender<Result<RunResult, String>> },
+    /// Ask the operator `question`; `None` if they cancelled.
+    Prompt { question: String, confirm: bool, reply: Sender<Option<String>> },
+}
+
+impl RunResult {
+    /// How `run` ended, with its output read back from its log.
+    pub fn of(run: &RunRecord) -> Self {
+        let output = run.log_file.as_ref().and_then(|f| std::fs::read_to_string(f).ok()).unwrap_or_default();
+        Self {
+            exit_code: run.exit_code,
+            stopped: run.stopped,
+            fault: run.fault.clone(),
+            output: output::strip_ansi(&output),
+            metrics: run.metrics.clone(),
+        }
+    }
+
+    /// Whether the run exited cleanly, without being stopped or faulting.
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0) && !self.stopped && self.fault.is_none()
+    }
+}
+
+/// Where the outcome of a launch requested by a script goes.
+pub enum Reply {
+    /// The result of the run once it ended, for [`Request::Run`].
+    Ended(Sender<Result<RunResult, String>>),
+    /// The ready line of the process, for [`Request::Start`].
+    Ready(Regex, Sender<Result<String, String>>),
+}
+
+impl Reply {
+    /// Tells the script the process was not launched.
+    pub fn fail(self, message: String) {
+        match self {
+            Reply::Ended(reply) => drop(reply.send(Err(message))),
+            Reply::Ready(_, reply) => drop(reply.send(Err(message))),
+        }
+    }
+}
+
+/// A question of a script waiting for the operator.
+pub struct Prompt {
+    pub question: String,
+    /// Whether it is a yes/no question.
+    pub confirm: bool,
+    /// What the operator typed so far.
+    pub answer: String,
+    reply: Sender<Option<String>>,
+}
+
+impl Prompt {
+    pub fn new(question: String, confirm: bool, reply: Sender<Option<String>>) -> Self {
+        Self { question, confirm, answer: String::new(), reply }
+    }
+
+    /// Hands `answer` to the script, `None` for cancel or no.
+    pub fn answer(self, answer: Option<String>) {
+        let _ = self.reply.send(answer);
+    }
+}
+
+/// A script running in the background.
+pub struct RunningScript {
+    pub name: String,
+    /// What the script printed.
+    pub log: Vec<String>,
+    requests: Receiver<Request>,
+    messages: Receiver<String>,
+    done: Receiver<Result<(), String>>,
+    /// Set to make the script stop at the next statement.
+    cancelled: Arc<AtomicBool>,
+    /// How the script ended, once it did.
+    pub outcome: Option<Result<(), String>>,
+    /// The history index of the run the script waits for, and where its result goes.
+    pub run: Option<(usize, Sender<Result<RunResult, String>>)>,
+    /// The ready line the script waits for, and where it goes.
+    pub ready: Option<(Regex, Sender<Result<String, String>>)>,
+    /// The history index of the run the script launched last.
+    pub launched: Option<usize>,
+    pub prompt: Option<Prompt>,
+    /// A run request put off until nothing else runs.
+    queued: Option<Request>,
+}
+
+impl RunningScript {
+    /// Compiles and starts the script of `config`.
+    pub fn start(config: &ScriptConfig) -> Result<Self, String> {
+        let source = std::fs::read_to_string(config.path())
+            .map_err(|e| format!("Failed to read script '{}': {}", config.path().display(), e))?;
+        let (request_sender, requests) = mpsc::channel();
+        let (message_sender, messages) = mpsc::channel();
+        let (done_sender, done) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let engine_cancelled = cancelled.clone();
+        let name = config.name.clone();
+        info!("Starting script '{}'", name);
+        std::thread::spawn(move || {
+            let engine = engine(request_sender, message_sender, engine_cancelled);
+            let result = engine.run(&source).map_err(|e| match *e {
+                EvalAltResult::ErrorTerminated(..) => "Stopped".to_string(),
+                e => e.to_string(),
+            });
+            let _ = done_sender.send(result);
+        });
+        Ok(Self {
+            name,
+            log: Vec::new(),
+            requests,
+            messages,
+            done,
+            cancelled,
+            outcome: None,
+            run: None,
+            ready: None,
+            launched: None,
+            prompt: None,
+            queued: None,
+        })
+    }
+
+    /// Makes the script stop; a run it launched carries on.
+    pub fn stop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        // Dropping the replies wakes up a script waiting for them.
+        self.run = None;
+        self.ready = None;
+        self.prompt = None;
+        self.queued = None;
+    }
+
+    /// The next request of the script, if it made one and is not waiting
+    /// for an earlier one.
+    pub fn next_request(&mut self) -> Option<Request> {
+        if self.run.is_some() || self.ready.is_some() || self.prompt.is_some() {
+            return None;
+        }
+        self.queued.take().or_else(|| self.requests.try_recv().ok())
+    }
+
+    /// Puts `request` off until the next call of [`Self::next_request`].
+    pub fn defer(&mut self, request: Request) {
+        self.queued = Some(request);
+    }
+
+    /// Collects what the script printed and whether it ended.
+    pub fn poll(&mut self) {
+        self.log.extend(self.messages.try_iter());
+        if self.outcome.is_none() {
+            if let Ok(outcome) = self.done.try_recv() {
+                match &outcome {
+                    Ok(()) => info!("Script '{}' finished", self.name),
+                    Err(e) => error!("Script '{}' failed: {}", self.name, e),
+                }
+                self.outcome = Some(outcome);
+            }
+        }
+    }
+
+    /// Hands the ready line to the script if one of `lines` of the running
+    /// process is it.
+    pub fn feed(&mut self, lines: &[String]) {
+        let Some((pattern, _)) = &self.ready else { return };
+        if let Some(line) = lines.iter().find(|line| pattern.is_match(line)) {
+            info!("Ready: {}", line);
+            let (_, reply) = self.ready.take().unwrap();
+            let _ = reply.send(Ok(line.clone()));
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.outcome.is_some()
+    }
+}
+
+/// Checks that the script of `config` can be read and compiles.
+pub fn check(config: &ScriptConfig) -> Result<(), String> {
+    let source = std::fs::read_to_string(config.path())
+        .map_err(|e| format!("failed to read '{}': {}", config.path().display(), e))?;
+    Engine::new().compile(source).map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn script_error(message: String) -> Box<EvalAltResult> {
+    message.into()
+}
+
+/// The engine scripts run in, with the functions they can call:
+///
+/// - `run(process)`, `run(process, #{"--flag": value, placeholder: value})`:
+///   launch a process and wait for it to end; returns `#{success, exit_code,
+///   stopped, fault, output, metrics}`.
+/// - `start(process)`, `start(process, values)`: launch a process and wait
+///   until it prints a line matching the `ready_pattern` of its command;
+///   returns that line. `wait()` then returns the result of the run as `run`
+///   does, once it ends.
+/// - `prompt(question)`: the operator's answer, or `()` if they cancelled.
+/// - `confirm(question)`: whether the operator answered yes.
+/// - `find(text, pattern)`: the first match of a regex (its first group, if
+///   it has one), or `()`; `find_number` parses it as a number.
+/// - `sleep(seconds)`; `print(...)` shows a line in the GUI.
+fn engine(requests: Sender<Request>, messages: Sender<String>, cancelled: Arc<AtomicBool>) -> Engine {
+    let mut engine = Engine::new();
+    engine.on_progress(move |_| cancelled.load(Ordering::Relaxed).then_some(Dynamic::UNIT));
+    let print_messages = messages.clone();
+    engine.on_print(move |text| {
+        let _ = print_messages.send(text.to_string());
+    });
+    engine.on_debug(move |text, _, _| {
+        let _ = messages.send(text.to_string());
+    });
+
+    fn process_type(process: &str) -> Result<ProcessType, Box<EvalAltResult>> {
+        ProcessType::from_key(process).ok_or_else(|| script_error(format!("Unknown process '{}'", process)))
+    }
+    fn launch_values(values: Map) -> Vec<(String, String)> {
+        values.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+    fn result_map(result: RunResult) -> Map {
+        let mut map = Map::new();
+        map.insert("success".into(), result.success().into());
+        map.insert("exit_code".into(), result.exit_code.map_or(Dynamic::UNIT, |c| (c as i64).into()));
+        map.insert("stopped".into(), result.stopped.into());
+        map.insert("fault".into(), result.fault.map_or(Dynamic::UNIT, Dynamic::from));
+        map.insert("output".into(), result.output.into());
+        let metrics: Map = result.metrics.into_iter().map(|(k, v)| (k.into(), v.into())).collect();
+        map.insert("metrics".into(), metrics.into());
+        map
+    }
+
+    let run_requests = requests.clone();
+    let run = move |process: &str, values: Map| -> Result<Map, Box<EvalAltResult>> {
+        let (reply, result) = mpsc::channel();
+        run_requests
+            .send(Request::Run { process_type: process_type(process)?, values: launch_values(values), reply })
+            .map_err(|_| script_error("The app went away".to_string()))?;
+        let result = result.recv().map_err(|_| script_error("Stopped".to_string()))?.map_err(script_error)?;
+        Ok(result_map(result))
+    };
+    let run_plain = run.clone();
+    engine.register_fn("run", move |process: &str| run_plain(process, Map::new()));
+    engine.register_fn("run", run);
+
+    let start_requests = requests.clone();
+    let start = move |process: &str, values: Map| -> Result<String, Box<EvalAltResult>> {
+        let (reply, line) = mpsc::channel();
+        start_requests
+            .send(Request::Start { process_type: process_type(process)?, values: launch_values(values), reply })
+            .map_err(|_| script_error("The app went away".to_string()))?;
+        line.recv().map_err(|_| script_error("Stopped".to_string()))?.map_err(script_error)
+    };
+    let start_plain = start.clone();
+    engine.register_fn("start", move |process: &str| start_plain(process, Map::new()));
+    engine.register_fn("start", start);
+
+    let wait_requests = requests.clone();
+    engine.register_fn("wait", move || -> Result<Map, Box<EvalAltResult>> {
+        let (reply, result) = mpsc::channel();
+        wait_requests
+            .send(Request::Wait { reply })
+            .map_err(|_| script_error("The app went away".to_string()))?;
+        let result = result.recv().map_err(|_| script_error("Stopped".to_string()))?.map_err(script_error)?;
+        Ok(result_map(result))
+    });
+
+    let ask = move |question: &str, confirm: bool| -> Result<Option<String>, Box<EvalAltResult>> {
+        let (reply, answer) = mpsc::channel();
+        requests
+            .send(Request::Prompt { question: question.to_string(), confirm, reply })
+            .map_err(|_| script_error("The app went away".to_string()))?;
+        answer.recv().map_err(|_| script_error("Stopped".to_string()))
+    };
+    let ask_confirm = ask.clone();
+    engine.register_fn("prompt", move |question: &str| {
+        ask(question, false).map(|answer| answer.map_or(Dynamic::UNIT, Dynamic::from))
+    });
+    engine.register_fn("confirm", move |question: &str| ask_confirm(question, true).map(|answer| answer.is_some()));
+
+    fn find(text: &str, pattern: &str) -> Result<Option<String>, Box<EvalAltResult>> {
+        let re = regex::Regex::new(pattern).map_err(|e| script_error(format!("Invalid pattern: {}", e)))?;
+        Ok(re.captures(text).and_then(|c| c.get(1).or(c.get(0))).map(|m| m.as_str().to_string()))
+    }
+    engine.register_fn("find", |text: &str, pattern: &str| {
+        find(text, pattern).map(|m| m.map_or(Dynamic::UNIT, Dynamic::from))
+    });
+    engine.register_fn("find_number", |text: &str, pattern: &str| {
+        find(text, pattern).map(|m| m.and_then(|m| m.parse::<f64>().ok()).map_or(Dynamic::UNIT, Dynamic::from))
+    });
+    engine.register_fn("sleep", |seconds: f64| std::thread::sleep(Duration::from_secs_f64(seconds.max(0.0))));
+    engine.register_fn("sleep", |seconds: i64| std::thread::sleep(Duration::from_secs(seconds.max(0) as u64)));
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(exit_code: Option<i32>, stopped: bool) -> RunResult {
+        RunResult { exit_code, stopped, fault: None, output: String::new(), metrics: BTreeMap::new() }
+    }
+
+    #[test]
+    fn only_clean_exits_are_successes() {
+        assert!(result(Some(0), false).success());
+        assert!(!result(Some(1), false).success());
+        assert!(!result(None, true).success());
+        assert!(!RunResult { fault: Some("torque".to_string()), ..result(Some(0), false) }.success());
+    }
+
+    #[test]
+    fn start_returns_the_ready_line() {
+        let path = std::env::temp_dir().join(format!("teleop-script-test-{}.rhai", std::process::id()));
+        std::fs::write(&path, r#"print(start("teleoperation", #{"--fps": 30}));"#).unwrap();
+        let config = ScriptConfig { name: "test".to_string(), path: path.display().to_string() };
+        let mut script = RunningScript::start(&config).unwrap();
+        let request = script.requests.recv_timeout(Duration::from_secs(5)).unwrap();
+        let Request::Start { process_type, values, reply } = request else { panic!("expected a start request") };
+        assert_eq!(process_type, ProcessType::Teleoperation);
+        assert_eq!(values, vec![("--fps".to_string(), "30".to_string())]);
+        script.ready = Some((Regex::new(r"Teleop loop running").unwrap(), reply));
+        script.feed(&["Connecting".to_string()]);
+        assert!(script.ready.is_some());
+        script.feed(&["INFO Teleop loop running at 30 Hz".to_string()]);
+        assert!(script.ready.is_none());
+        let outcome = script.done.recv_timeout(Duration::from_secs(5)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(outcome, Ok(()));
+        script.poll();
+        assert_eq!(script.log, vec!["INFO Teleop loop running at 30 Hz".to_string()]);
+    }
+}
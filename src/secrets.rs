@@ -0,0 +1,144 @@
+use eframe::egui;
+use keyring::Entry;
+use std::collections::BTreeMap;
+
+/// Service name under which all our secrets are stored in the OS keyring.
+const SERVICE: &str = "teleop-record-replay";
+
+fn entry(name: &str) -> Result<Entry, String> {
+    Entry::new(SERVICE, name).map_err(|e| format!("Keyring error for secret '{}': {}", name, e))
+}
+
+/// Reads a secret from the OS keyring. Returns `Ok(None)` if it was never set.
+pub fn get(name: &str) -> Result<Option<String>, String> {
+    match entry(name)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read secret '{}': {}", name, e)),
+    }
+}
+
+/// Stores (or replaces) a secret in the OS keyring.
+pub fn set(name: &str, value: &str) -> Result<(), String> {
+    entry(name)?
+        .set_password(value)
+        .map_err(|e| format!("Failed to store secret '{}': {}", name, e))
+}
+
+/// Removes a secret from the OS keyring. Removing a missing secret is not an error.
+pub fn delete(name: &str) -> Result<(), String> {
+    match entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete secret '{}': {}", name, e)),
+    }
+}
+
+/// Resolves the `[secrets]` config table (environment variable -> secret name)
+/// into the environment variables to pass to launched processes.
+pub fn resolve_env(mapping: &BTreeMap<String, String>) -> Result<Vec<(String, String)>, String> {
+    mapping
+        .iter()
+        .map(|(var, name)| match get(name)? {
+            Some(value) => Ok((var.clone(), value)),
+            None => Err(format!(
+                "Secret '{}' (for ${}) is not set. Set it in the Secrets dialog.",
+                name, var
+            )),
+        })
+        .collect()
+}
+
+/// Outcome of an action in the secrets dialog that should be audited.
+pub enum SecretChange {
+    Updated(String),
+    Deleted(String),
+}
+
+/// Dialog to set, update and delete the secrets referenced by the config.
+#[derive(Default)]
+pub struct SecretsDialog {
+    pub open: bool,
+    name: String,
+    value: String,
+    /// Outcome of the last action, shown at the bottom of the dialog.
+    status: Option<Result<String, String>>,
+    /// Whether each known secret is set (`None` if the keyring failed). Cached
+    /// because every lookup is a round trip to the keyring daemon.
+    presence: Option<BTreeMap<String, Option<bool>>>,
+}
+
+impl SecretsDialog {
+    /// Shows the dialog if it is open. `known` are the secret names the config
+    /// refers to. Returns the change made this frame, if any.
+    pub fn show(&mut self, ctx: &egui::Context, known: &BTreeMap<String, String>) -> Option<SecretChange> {
+        let mut change = None;
+        let mut open = self.open;
+        if !open {
+            self.presence = None;
+            return None;
+        }
+        let presence = self.presence.get_or_insert_with(|| {
+            known
+                .values()
+                .map(|name| (name.clone(), get(name).ok().map(|v| v.is_some())))
+                .collect()
+        });
+        egui::Window::new("Secrets").open(&mut open).show(ctx, |ui| {
+            ui.label("Secrets are stored in the OS keyring and passed to commands as environment variables.");
+            egui::Grid::new("secrets_grid").num_columns(3).show(ui, |ui| {
+                for (var, name) in known {
+                    ui.label(format!("{} (${})", name, var));
+                    match presence.get(name).copied().flatten() {
+                        Some(true) => ui.label("set"),
+                        Some(false) => ui.colored_label(egui::Color32::YELLOW, "not set"),
+                        None => ui.colored_label(egui::Color32::RED, "keyring unavailable"),
+                    };
+                    if ui.small_button("Edit").clicked() {
+                        self.name = name.clone();
+                        self.value.clear();
+                    }
+                    ui.end_row();
+                }
+            });
+            ui.separator();
+            egui::Grid::new("secrets_edit_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut self.name);
+                ui.end_row();
+                ui.label("Value:");
+                ui.add(egui::TextEdit::singleline(&mut self.value).password(true));
+                ui.end_row();
+            });
+            ui.horizontal(|ui| {
+                let name = self.name.trim().to_string();
+                if ui.add_enabled(!name.is_empty(), egui::Button::new("Save")).clicked() {
+                    self.status = Some(set(&name, &self.value).map(|()| format!("Saved '{}'.", name)));
+                    if matches!(self.status, Some(Ok(_))) {
+                        self.value.clear();
+                        change = Some(SecretChange::Updated(name.clone()));
+                    }
+                }
+                if ui.add_enabled(!name.is_empty(), egui::Button::new("Delete")).clicked() {
+                    self.status = Some(delete(&name).map(|()| format!("Deleted '{}'.", name)));
+                    if matches!(self.status, Some(Ok(_))) {
+                        change = Some(SecretChange::Deleted(name.clone()));
+                    }
+                }
+            });
+            match &self.status {
+                Some(Ok(msg)) => {
+                    ui.label(msg);
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+                None => {}
+            }
+        });
+        self.open = open;
+        if change.is_some() {
+            self.presence = None;
+        }
+        change
+    }
+}
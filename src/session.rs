@@ -0,0 +1,550 @@
+use eframe::egui;
+use tracing::{error, info};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::process::{Child, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Instant;
+
+use crate::command;
+use crate::config::{Config, Variant};
+use crate::dataset::RecentDataset;
+use crate::sandbox;
+
+/// LeRobot flags the session sets so each Record launch is a single episode
+/// whose timing is controlled by the app.
+pub const NUM_EPISODES_FLAG: &str = "--dataset.num_episodes";
+pub const EPISODE_TIME_FLAG: &str = "--dataset.episode_time_s";
+pub const RESET_TIME_FLAG: &str = "--dataset.reset_time_s";
+/// The LeRobot flag holding the task description of the recorded episodes.
+pub const TASK_FLAG: &str = "--dataset.single_task";
+
+/// Settings of one phase of a recording session.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct PhaseConfig {
+    /// How long the phase lasts. Without it, the phase waits for the operator
+    /// (or, for recording, for LeRobot's own episode length).
+    #[serde(default)]
+    pub seconds: Option<f64>,
+    /// A shell command started in the background when the phase begins, e.g.
+    /// moving the robot to its home pose. The phase does not end before it does.
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+/// The `[session]` config table: how a structured recording session runs.
+#[derive(Deserialize, Clone, Debug)]
+pub struct SessionConfig {
+    /// Episodes to keep before the session is done.
+    #[serde(default = "default_episodes")]
+    pub episodes: u32,
+    /// Episodes to keep of each task, by its `--dataset.single_task`; each is
+    /// shown with a progress bar of its own.
+    #[serde(default)]
+    pub targets: BTreeMap<String, u32>,
+    /// Putting the scene back in place before an episode. Without `seconds`,
+    /// it lasts the `--dataset.reset_time_s` of the Record command, as it would
+    /// in `lerobot-record`, or waits for the operator if that is not set.
+    #[serde(default)]
+    pub reset: PhaseConfig,
+    /// A short countdown so the operator is ready when recording starts.
+    #[serde(default = "default_countdown")]
+    pub countdown: PhaseConfig,
+    /// Recording one episode with the Record command.
+    #[serde(default)]
+    pub recording: PhaseConfig,
+    /// Deciding whether to keep the episode.
+    #[serde(default)]
+    pub review: PhaseConfig,
+    /// Run before re-recording an episode instead of deleting the bad one
+    /// from the dataset, which is only supported for v2 datasets.
+    /// `{repo_id}`, `{dataset_path}` and `{episode_index}` are filled in.
+    #[serde(default)]
+    pub cleanup: Option<String>,
+    /// Run on every episode recorded successfully, with the same placeholders
+    /// as `cleanup`. It prints a JSON line like `{"passed": false, "messages":
+    /// ["gripper camera is black"]}`; without one its exit code counts. An
+    /// episode can only be kept once it passed.
+    #[serde(default)]
+    pub validate: Option<String>,
+    /// Plays a sound when the reset ends and the next episode is about to
+    /// start; empty for silence.
+    #[serde(default = "default_cue")]
+    pub cue: String,
+}
+
+fn default_cue() -> String {
+    "paplay /usr/share/sounds/freedesktop/stereo/bell.oga".to_string()
+}
+
+fn default_episodes() -> u32 {
+    10
+}
+
+fn default_countdown() -> PhaseConfig {
+    PhaseConfig { seconds: Some(3.0), command: None }
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            episodes: default_episodes(),
+            targets: BTreeMap::new(),
+            reset: PhaseConfig::default(),
+            countdown: default_countdown(),
+            recording: PhaseConfig::default(),
+            review: PhaseConfig::default(),
+            cleanup: None,
+            validate: None,
+            cue: default_cue(),
+        }
+    }
+}
+
+/// The outcome of the validation command on an episode.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Validation {
+    pub passed: bool,
+    #[serde(default)]
+    pub messages: Vec<String>,
+}
+
+impl Validation {
+    /// Reads the result from the `output` of the validation command: its last
+    /// JSON line or, lacking one, whether it `succeeded` and what it printed.
+    fn parse(succeeded: bool, output: &str) -> Self {
+        let json = output.lines().rev().map(str::trim).filter(|l| l.starts_with('{'));
+        if let Some(validation) = json.filter_map(|l| serde_json::from_str(l).ok()).next() {
+            return validation;
+        }
+        Self {
+            passed: succeeded,
+            messages: output.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect(),
+        }
+    }
+}
+
+/// The validation command of the last episode, while it runs.
+struct RunningValidation {
+    child: Child,
+    /// Receives everything the command printed once it closes its output.
+    output: Receiver<String>,
+}
+
+/// Where a recording session is. There is no idle phase: no session means idle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Reset,
+    Countdown,
+    Recording,
+    /// The episode just recorded is waiting for a decision; `success` is
+    /// whether the Record process exited cleanly.
+    Review { success: bool },
+    /// Running the cleanup command before re-recording an episode.
+    Cleanup,
+    Done,
+}
+
+impl Phase {
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Reset => "Reset the environment",
+            Phase::Countdown => "Get ready",
+            Phase::Recording => "Recording",
+            Phase::Review { .. } => "Review",
+            Phase::Cleanup => "Cleaning up",
+            Phase::Done => "Session complete",
+        }
+    }
+}
+
+/// A recording session in progress.
+pub struct Session {
+    pub settings: SessionConfig,
+    pub phase: Phase,
+    /// When the current phase began.
+    phase_started: Instant,
+    /// The background command of the current phase, if still running.
+    phase_command: Option<Child>,
+    /// Set by the operator to end a phase that has no fixed duration.
+    ready: bool,
+    /// Episodes kept so far.
+    pub kept: u32,
+    /// Episodes discarded so far.
+    pub discarded: u32,
+    /// Episodes kept so far of each task.
+    pub kept_by_task: BTreeMap<String, u32>,
+    /// Targets reached since the last call to [`Session::take_reached`].
+    reached: Vec<String>,
+    /// Index in the dataset of the episode recorded last, if known.
+    pub episode_index: Option<u64>,
+    /// The task the last episode was recorded with.
+    pub task: Option<String>,
+    /// Whether the last episode was discarded or failed and can be re-recorded.
+    pub can_rerecord: bool,
+    /// Whether the next recording re-records the last episode.
+    rerecord: bool,
+    /// The validation of the episode under review, while it runs.
+    running_validation: Option<RunningValidation>,
+    /// The outcome of the validation of the episode under review.
+    pub validation: Option<Validation>,
+    /// Problems found by the checks run when the session started.
+    pub warnings: Vec<String>,
+    /// The dataset `[dataset_naming]` named for the session, if set.
+    pub dataset: Option<RecentDataset>,
+    /// Tracing span covering the whole session.
+    span: tracing::Span,
+    /// Tracing span of the current phase, within `span`.
+    pub phase_span: tracing::Span,
+}
+
+impl Session {
+    /// A new session; start it by entering [`Phase::Reset`].
+    pub fn new(settings: SessionConfig) -> Self {
+        let span = tracing::info_span!(parent: None, "session", episodes = settings.episodes);
+        Self {
+            settings,
+            phase: Phase::Done,
+            phase_started: Instant::now(),
+            phase_command: None,
+            ready: false,
+            kept: 0,
+            discarded: 0,
+            kept_by_task: BTreeMap::new(),
+            reached: Vec::new(),
+            episode_index: None,
+            task: None,
+            can_rerecord: false,
+            rerecord: false,
+            running_validation: None,
+            validation: None,
+            warnings: Vec::new(),
+            dataset: None,
+            span,
+            phase_span: tracing::Span::none(),
+        }
+    }
+
+    fn phase_config(&self, phase: Phase) -> Option<&PhaseConfig> {
+        match phase {
+            Phase::Reset => Some(&self.settings.reset),
+            Phase::Countdown => Some(&self.settings.countdown),
+            Phase::Recording => Some(&self.settings.recording),
+            Phase::Review { .. } => Some(&self.settings.review),
+            Phase::Cleanup | Phase::Done => None,
+        }
+    }
+
+    /// The command run when entering `phase`.
+    fn phase_command(&self, phase: Phase) -> Option<&str> {
+        match phase {
+            Phase::Cleanup => self.settings.cleanup.as_deref(),
+            _ => self.phase_config(phase)?.command.as_deref(),
+        }
+    }
+
+    /// Switches to `phase` and starts its background command, if any, with
+    /// placeholders filled from `values`. The working directory and conda
+    /// environment are those of `variant`.
+    pub fn enter(
+        &mut self,
+        phase: Phase,
+        config: &Config,
+        variant: &Variant,
+        values: &HashMap<String, String>,
+    ) -> Result<(), String> {
+        self.phase_span = tracing::info_span!(parent: &self.span, "phase", phase = phase.name());
+        let _entered = self.phase_span.clone().entered();
+        info!("Recording session: {:?}", phase);
+        self.stop_phase_command();
+        self.stop_validation();
+        self.validation = None;
+        self.phase = phase;
+        self.phase_started = Instant::now();
+        self.ready = false;
+        if phase == Phase::Countdown {
+            self.play_cue();
+        }
+        if let (Phase::Review { success: true }, Some(validate)) = (phase, &self.settings.validate) {
+            self.start_validation(&command::resolve(config, variant, validate, values))?;
+        }
+        let Some(phase_command) = self.phase_command(phase) else { return Ok(()) };
+        let shell_command = command::resolve(config, variant, phase_command, values);
+        let child = sandbox::bash(&shell_command)
+            .spawn()
+            .map_err(|e| format!("Failed to run the {} command: {}", phase.name().to_lowercase(), e))?;
+        self.phase_command = Some(child);
+        Ok(())
+    }
+
+    /// Plays the cue in the background; a missing player is only logged.
+    fn play_cue(&self) {
+        if self.settings.cue.trim().is_empty() {
+            return;
+        }
+        match sandbox::bash(&self.settings.cue).stdout(Stdio::null()).stderr(Stdio::null()).spawn() {
+            Ok(mut child) => {
+                std::thread::spawn(move || match child.wait() {
+                    Ok(status) if !status.success() => error!("Recording session: the cue failed with {}", status),
+                    _ => {}
+                });
+            }
+            Err(e) => error!("Recording session: failed to play the cue: {}", e),
+        }
+    }
+
+    fn start_validation(&mut self, shell_command: &str) -> Result<(), String> {
+        let mut child = sandbox::bash(shell_command)
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run the validation command: {}", e))?;
+        let mut stdout = child.stdout.take().ok_or_else(|| "No output from the validation command".to_string())?;
+        let (sender, output) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut text = String::new();
+            let _ = stdout.read_to_string(&mut text);
+            let _ = sender.send(text);
+        });
+        self.running_validation = Some(RunningValidation { child, output });
+        Ok(())
+    }
+
+    /// Whether the episode under review is still being validated; picks up
+    /// the result once it is done.
+    pub fn validating(&mut self) -> bool {
+        let Some(running) = &mut self.running_validation else { return false };
+        let status = match running.child.try_wait() {
+            Ok(None) => return true,
+            Ok(Some(status)) => status,
+            Err(e) => {
+                error!("Recording session: validation command: {}", e);
+                self.running_validation = None;
+                return false;
+            }
+        };
+        let output = running.output.recv().unwrap_or_default();
+        let validation = Validation::parse(status.success(), &output);
+        info!("Recording session: episode validation {}", if validation.passed { "passed" } else { "failed" });
+        self.validation = Some(validation);
+        self.running_validation = None;
+        false
+    }
+
+    /// Whether the episode under review may be kept as far as its validation
+    /// is concerned: it passed, or there is no validation command.
+    pub fn validation_passed(&mut self) -> bool {
+        if self.settings.validate.is_none() {
+            return true;
+        }
+        !self.validating() && self.validation.as_ref().is_some_and(|v| v.passed)
+    }
+
+    fn stop_validation(&mut self) {
+        if let Some(mut running) = self.running_validation.take() {
+            let _ = sandbox::kill(&mut running.child);
+            let _ = running.child.wait();
+        }
+    }
+
+    /// Seconds left in the current phase, if it has a fixed duration.
+    pub fn remaining(&self) -> Option<f64> {
+        let seconds = self.phase_config(self.phase)?.seconds?;
+        Some((seconds - self.phase_started.elapsed().as_secs_f64()).max(0.0))
+    }
+
+    /// Whether the phase's background command is still running.
+    pub fn phase_command_running(&mut self) -> bool {
+        match &mut self.phase_command {
+            Some(child) => match child.try_wait() {
+                Ok(None) => true,
+                Ok(Some(status)) => {
+                    if !status.success() {
+                        error!("Recording session: {} command exited with {}", self.phase.name(), status);
+                    }
+                    self.phase_command = None;
+                    false
+                }
+                Err(_) => {
+                    self.phase_command = None;
+                    false
+                }
+            },
+            None => false,
+        }
+    }
+
+    /// Lets a phase without a fixed duration end, or ends a timed one early.
+    pub fn ready(&mut self) {
+        self.ready = true;
+    }
+
+    /// Returns the phase to move on to if the current one is complete. The
+    /// recording phase ends through [`Session::recording_finished`] instead.
+    pub fn poll(&mut self) -> Option<Phase> {
+        if self.phase_command_running() {
+            return None;
+        }
+        let timed_out = self.remaining().is_some_and(|r| r <= 0.0);
+        let validation_passed = self.validation_passed();
+        match self.phase {
+            Phase::Reset if timed_out || self.ready => Some(Phase::Countdown),
+            Phase::Countdown if timed_out || self.ready => Some(Phase::Recording),
+            // Episodes nobody objected to in time are kept, if they passed validation.
+            Phase::Review { success: true } if timed_out && validation_passed => Some(self.review(true)),
+            Phase::Cleanup => Some(Phase::Reset),
+            _ => None,
+        }
+    }
+
+    /// Called when the Record process of the recording phase exits with
+    /// `exit_code`; an episode that was `interrupted`, e.g. stopped or
+    /// faulted, failed whatever the exit code.
+    pub fn recording_finished(&mut self, exit_code: Option<i32>, interrupted: bool) -> Option<Phase> {
+        let success = exit_code == Some(0) && !interrupted;
+        (self.phase == Phase::Recording).then_some(Phase::Review { success })
+    }
+
+    /// Records the operator's decision on the last episode and returns the next phase.
+    pub fn review(&mut self, keep: bool) -> Phase {
+        if keep {
+            self.kept += 1;
+            if let Some(task) = &self.task {
+                let kept = self.kept_by_task.entry(task.clone()).or_default();
+                *kept += 1;
+                if self.settings.targets.get(task) == Some(kept) {
+                    self.reached.push(format!("Task '{}' done: {} episodes kept", task, kept));
+                }
+            }
+        } else {
+            self.discarded += 1;
+        }
+        self.can_rerecord = !keep;
+        if self.kept >= self.settings.episodes {
+            self.reached.push(format!("Session complete: {} episodes kept", self.kept));
+            Phase::Done
+        } else {
+            Phase::Reset
+        }
+    }
+
+    /// The targets reached since the last call, to notify the operator of.
+    pub fn take_reached(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.reached)
+    }
+
+    /// Shows how far the session and each task with a target are, with the
+    /// target of the session to change; returns whether it changed.
+    pub fn progress_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let bar = |ui: &mut egui::Ui, kept: u32, target: u32, text: String| {
+            let fraction = kept as f32 / target.max(1) as f32;
+            let bar = egui::ProgressBar::new(fraction.min(1.0)).text(text);
+            ui.add(if kept >= target { bar.fill(egui::Color32::DARK_GREEN) } else { bar });
+        };
+        let changed = ui
+            .horizontal(|ui| {
+                ui.label("Target");
+                let target = egui::DragValue::new(&mut self.settings.episodes).clamp_range(1..=u32::MAX);
+                let hover = "Episodes to keep before the session is done";
+                ui.add(target.suffix(" episodes")).on_hover_text(hover).changed()
+            })
+            .inner;
+        bar(ui, self.kept, self.settings.episodes, format!("{} / {} kept", self.kept, self.settings.episodes));
+        for (task, &target) in &self.settings.targets {
+            let kept = self.kept_by_task.get(task).copied().unwrap_or(0);
+            bar(ui, kept, target, format!("{}: {} / {}", task, kept, target));
+        }
+        changed
+    }
+
+    /// Re-records the last episode: discards it (unless that already
+    /// happened), then runs the cleanup command, if any, and resets. The next
+    /// recording uses the same task; without a cleanup command the app
+    /// deletes the episode so it also gets the same index.
+    pub fn rerecord(&mut self) -> Phase {
+        if matches!(self.phase, Phase::Review { .. }) {
+            self.discarded += 1;
+        }
+        self.can_rerecord = false;
+        self.rerecord = true;
+        if self.settings.cleanup.is_some() {
+            Phase::Cleanup
+        } else {
+            Phase::Reset
+        }
+    }
+
+    /// Called when the recording of an episode is launched with `command`,
+    /// into a dataset that had `episode_index` episodes before.
+    pub fn recording_started(&mut self, command: &str, episode_index: Option<u64>) {
+        self.task = command::flag_value(command, TASK_FLAG);
+        self.episode_index = episode_index;
+        self.rerecord = false;
+    }
+
+    /// Flags for the Record command of one episode.
+    pub fn record_flags(&self) -> Vec<(String, String)> {
+        let mut flags = vec![
+            (NUM_EPISODES_FLAG.to_string(), "1".to_string()),
+            // The session has its own reset phase.
+            (RESET_TIME_FLAG.to_string(), "0".to_string()),
+        ];
+        if let Some(seconds) = self.settings.recording.seconds {
+            flags.push((EPISODE_TIME_FLAG.to_string(), seconds.to_string()));
+        }
+        if let Some(task) = self.task.as_ref().filter(|_| self.rerecord) {
+            flags.push((TASK_FLAG.to_string(), task.clone()));
+        }
+        flags
+    }
+
+    /// Stops the background command of the current phase, if any.
+    pub fn stop_phase_command(&mut self) {
+        if let Some(mut child) = self.phase_command.take() {
+            let _ = sandbox::kill(&mut child);
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.stop_phase_command();
+        self.stop_validation();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+
+    #[test]
+    fn a_failed_recording_is_reviewed_as_failed() {
+        let dir = std::env::temp_dir().join(format!("teleop-session-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("record.log");
+        let script = crate::output::capture("exit 1", &log);
+        Command::new("bash").arg("-c").arg(script).stdout(Stdio::null()).status().unwrap();
+        let exit_code = crate::output::exit_status(&log);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let mut session = Session::new(SessionConfig::default());
+        session.phase = Phase::Recording;
+        assert_eq!(session.recording_finished(exit_code, false), Some(Phase::Review { success: false }));
+        assert_eq!(session.review(false), Phase::Reset);
+        assert!(session.can_rerecord);
+        assert_eq!((session.kept, session.discarded), (0, 1));
+    }
+
+    #[test]
+    fn a_recording_succeeds_only_if_it_exits_cleanly() {
+        let mut session = Session::new(SessionConfig::default());
+        session.phase = Phase::Recording;
+        assert_eq!(session.recording_finished(Some(0), false), Some(Phase::Review { success: true }));
+        assert_eq!(session.recording_finished(Some(0), true), Some(Phase::Review { success: false }));
+        assert_eq!(session.recording_finished(None, false), Some(Phase::Review { success: false }));
+        session.phase = Phase::Reset;
+        assert_eq!(session.recording_finished(Some(1), false), None);
+    }
+}
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver};
+use tracing::{info, warn};
+
+use crate::config::{Config, Variant};
+use crate::{command, sandbox};
+
+/// The code and packages a run was launched with, so every dataset can be
+/// traced back to what recorded it.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Snapshot {
+    /// The commit checked out in the working directory, if it is a git repository.
+    #[serde(default)]
+    pub git_commit: Option<String>,
+    /// Whether tracked files had uncommitted changes.
+    #[serde(default)]
+    pub git_dirty: bool,
+    /// The packages of the environment, as `conda list --export` or
+    /// `pip freeze` print them.
+    #[serde(default)]
+    pub packages: Vec<String>,
+}
+
+impl Snapshot {
+    /// The commit, with `-dirty` appended as `git describe --dirty` does.
+    pub fn describe(&self) -> Option<String> {
+        let commit = self.git_commit.as_ref()?;
+        Some(if self.git_dirty { format!("{}-dirty", commit) } else { commit.clone() })
+    }
+}
+
+/// The shell commands taking a snapshot, each run where the command runs.
+struct Scripts {
+    commit: String,
+    status: String,
+    packages: String,
+}
+
+/// The output of `shell_command` if it succeeds.
+fn run(shell_command: &str) -> Result<String, String> {
+    let output = sandbox::bash(shell_command).output().map_err(|e| format!("Failed to run bash: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn take(scripts: &Scripts) -> Snapshot {
+    let git_commit = run(&scripts.commit).ok().map(|o| o.trim().to_string()).filter(|c| !c.is_empty());
+    let git_dirty = git_commit.is_some() && run(&scripts.status).is_ok_and(|o| !o.trim().is_empty());
+    let packages = match run(&scripts.packages) {
+        Ok(output) => output.lines().filter(|l| !l.is_empty() && !l.starts_with('#')).map(str::to_string).collect(),
+        Err(e) => {
+            warn!("Failed to list the packages of the run's environment: {}", e);
+            Vec::new()
+        }
+    };
+    Snapshot { git_commit, git_dirty, packages }
+}
+
+/// Snapshots being taken in the background, by the index of their run.
+#[derive(Default)]
+pub struct Snapshots {
+    pending: Vec<(usize, Receiver<Snapshot>)>,
+}
+
+impl Snapshots {
+    /// Starts taking the snapshot of the run at `index`, launched from
+    /// `variant`: in its working directory and with its conda environment.
+    pub fn start(&mut self, index: usize, config: &Config, variant: &Variant) {
+        let in_environment = |script: &str| command::resolve(config, variant, script, &HashMap::new());
+        let packages = match variant.conda_env(&config.commands) {
+            Some(_) => "conda list --export",
+            None => "python -m pip freeze",
+        };
+        let scripts = Scripts {
+            commit: in_environment("git rev-parse HEAD"),
+            status: in_environment("git status --porcelain --untracked-files=no"),
+            packages: in_environment(packages),
+        };
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(take(&scripts));
+        });
+        self.pending.push((index, receiver));
+    }
+
+    /// Whether any snapshot is still being taken.
+    pub fn taking(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// The snapshots taken since the last call, by the index of their run.
+    pub fn poll(&mut self) -> Vec<(usize, Snapshot)> {
+        let mut taken = Vec::new();
+        self.pending.retain(|(index, receiver)| match receiver.try_recv() {
+            Ok(snapshot) => {
+                info!(
+                    "Run {} was launched from commit {} with {} package(s)",
+                    index,
+                    snapshot.describe().as_deref().unwrap_or("(none)"),
+                    snapshot.packages.len()
+                );
+                taken.push((*index, snapshot));
+                false
+            }
+            Err(mpsc::TryRecvError::Empty) => true,
+            Err(mpsc::TryRecvError::Disconnected) => false,
+        });
+        taken
+    }
+}
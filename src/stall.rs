@@ -0,0 +1,180 @@
+use eframe::egui;
+use regex::Regex;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{error, info, warn};
+
+use crate::sandbox;
+
+/// The `[stall_check]` config table: while Record runs, the dataset folder is
+/// watched for new data, to notice cameras that froze while the script itself
+/// carries on.
+#[derive(Deserialize, Clone, Debug)]
+pub struct StallCheckConfig {
+    /// Seconds without anything written to the dataset before the recording
+    /// counts as stalled.
+    #[serde(default = "default_seconds")]
+    pub seconds: f64,
+    /// Run once each time a recording stalls, e.g. to play a sound; none if unset.
+    #[serde(default)]
+    pub alert: Option<String>,
+}
+
+fn default_seconds() -> f64 {
+    20.0
+}
+
+/// How often the dataset folder is looked at.
+const SCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The number of files in a folder, their total size and the latest
+/// modification time, which change whenever anything is written.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct Fingerprint {
+    files: u64,
+    bytes: u64,
+    modified: Option<SystemTime>,
+}
+
+fn fingerprint(dir: &Path) -> Fingerprint {
+    let mut total = Fingerprint::default();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else { continue };
+            if metadata.is_dir() {
+                pending.push(entry.path());
+                continue;
+            }
+            total.files += 1;
+            total.bytes += metadata.len();
+            total.modified = total.modified.max(metadata.modified().ok());
+        }
+    }
+    total
+}
+
+/// Watches the dataset a Record run writes to for growth.
+pub struct StallWatch {
+    dir: PathBuf,
+    after: Duration,
+    alert: Option<String>,
+    last: Option<Fingerprint>,
+    /// When the dataset last grew, or the watch was (re)started.
+    grew_at: Instant,
+    scanned_at: Option<Instant>,
+    scanning: Option<Receiver<Fingerprint>>,
+    /// Set while LeRobot waits for the environment to be reset, when it
+    /// writes nothing.
+    resetting: bool,
+    /// Whether the current stall was alerted of already.
+    alerted: bool,
+}
+
+impl StallWatch {
+    pub fn new(config: &StallCheckConfig, dir: PathBuf) -> Self {
+        Self {
+            dir,
+            after: Duration::from_secs_f64(config.seconds),
+            alert: config.alert.clone().filter(|a| !a.trim().is_empty()),
+            last: None,
+            grew_at: Instant::now(),
+            scanned_at: None,
+            scanning: None,
+            resetting: false,
+            alerted: false,
+        }
+    }
+
+    /// Follows the phases `lerobot-record` announces in its output.
+    pub fn feed(&mut self, line: &str) {
+        static PATTERNS: OnceLock<[Regex; 2]> = OnceLock::new();
+        let [recording, reset] = PATTERNS.get_or_init(|| {
+            [Regex::new(r"Recording episode \d+").unwrap(), Regex::new(r"Reset the environment").unwrap()]
+        });
+        if recording.is_match(line) {
+            self.resetting = false;
+            self.alerted = false;
+            self.grew_at = Instant::now();
+        } else if reset.is_match(line) {
+            self.resetting = true;
+        }
+    }
+
+    /// Picks up the latest scan of the dataset and starts the next one when
+    /// due. A paused process writes nothing either, so it does not count.
+    pub fn poll(&mut self, paused: bool, ctx: &egui::Context) {
+        if paused || self.resetting {
+            self.grew_at = Instant::now();
+        }
+        if let Some(receiver) = &self.scanning {
+            match receiver.try_recv() {
+                Ok(fingerprint) => {
+                    if self.last.is_some_and(|l| l != fingerprint) {
+                        if self.alerted {
+                            info!("Recording into '{}' resumed", self.dir.display());
+                        }
+                        self.grew_at = Instant::now();
+                        self.alerted = false;
+                    }
+                    self.last = Some(fingerprint);
+                    self.scanning = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => self.scanning = None,
+            }
+        }
+        if self.scanning.is_none() && self.scanned_at.is_none_or(|at| at.elapsed() >= SCAN_INTERVAL) {
+            let (sender, receiver) = mpsc::channel();
+            let dir = self.dir.clone();
+            std::thread::spawn(move || {
+                let _ = sender.send(fingerprint(&dir));
+            });
+            self.scanning = Some(receiver);
+            self.scanned_at = Some(Instant::now());
+        }
+        if self.stalled().is_some() && !self.alerted {
+            self.alerted = true;
+            warn!(
+                "Nothing was written to '{}' for {:?}; the recording appears stalled",
+                self.dir.display(),
+                self.after
+            );
+            if let Some(alert) = &self.alert {
+                match sandbox::bash(alert).stdout(Stdio::null()).stderr(Stdio::null()).spawn() {
+                    Ok(mut child) => {
+                        std::thread::spawn(move || match child.wait() {
+                            Ok(status) if !status.success() => error!("The stall alert failed with {}", status),
+                            _ => {}
+                        });
+                    }
+                    Err(e) => error!("Failed to run the stall alert: {}", e),
+                }
+            }
+        }
+        ctx.request_repaint_after(SCAN_INTERVAL);
+    }
+
+    /// For how long nothing was written, once that is longer than allowed.
+    pub fn stalled(&self) -> Option<Duration> {
+        Some(self.grew_at.elapsed()).filter(|d| *d >= self.after)
+    }
+
+    pub fn ui(&self, ui: &mut egui::Ui) {
+        if let Some(stalled) = self.stalled() {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!(
+                    "Recording appears stalled: nothing was written to the dataset for {} s. Check the cameras.",
+                    stalled.as_secs()
+                ),
+            )
+            .on_hover_text(self.dir.display().to_string());
+        }
+    }
+}
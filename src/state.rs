@@ -0,0 +1,105 @@
+use tracing::error;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::controller::BridgeSettings;
+use crate::dataset::RecentDataset;
+use crate::preview::Layout;
+
+/// GUI state remembered between sessions (as opposed to the config, which the
+/// app only reads).
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct AppState {
+    /// The operator selected last time.
+    #[serde(default)]
+    pub operator: String,
+    /// Datasets used by recent runs, most recent first.
+    #[serde(default)]
+    pub recent_datasets: Vec<RecentDataset>,
+    /// The command variant selected for each process type, by config key and
+    /// variant name.
+    #[serde(default)]
+    pub variants: BTreeMap<String, String>,
+    /// Values entered in the parameter forms, by `<process type>.<param name>`.
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+    /// The checkpoint picked for `{checkpoint}`; the newest one if unset or gone.
+    #[serde(default)]
+    pub checkpoint: Option<String>,
+    /// The robot host picked for `{host}`.
+    #[serde(default)]
+    pub host: String,
+    /// The control and recording rate filled into `{fps}`, if entered.
+    #[serde(default)]
+    pub fps: Option<u32>,
+    /// The controller inputs forwarded to the teleop script.
+    #[serde(default)]
+    pub controller: BridgeSettings,
+    /// The namespace and name composed for `{repo_id}`.
+    #[serde(default)]
+    pub repo_namespace: String,
+    #[serde(default)]
+    pub repo_name: String,
+    /// How the camera preview tiles are arranged.
+    #[serde(default)]
+    pub preview_layout: Layout,
+    /// Episodes to keep in a recording session, if set in the GUI instead of
+    /// `[session] episodes`.
+    #[serde(default)]
+    pub session_episodes: Option<u32>,
+    /// Whether the recording overlay was switched off.
+    #[serde(default)]
+    pub hud_off: bool,
+    /// Config files loaded recently, most recent first.
+    #[serde(default)]
+    pub recent_configs: Vec<PathBuf>,
+}
+
+/// How many config files the config switcher remembers.
+const MAX_RECENT_CONFIGS: usize = 10;
+
+/// The rate filled into `{fps}` until another one is entered.
+const DEFAULT_FPS: u32 = 30;
+
+impl AppState {
+    pub fn fps(&self) -> u32 {
+        self.fps.unwrap_or(DEFAULT_FPS)
+    }
+
+    /// Moves (or adds) the config file at `path` to the front of the recent
+    /// configs, keeping the list short.
+    pub fn remember_config(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.recent_configs.retain(|p| *p != path);
+        self.recent_configs.insert(0, path);
+        self.recent_configs.truncate(MAX_RECENT_CONFIGS);
+    }
+
+    fn path() -> PathBuf {
+        crate::paths::data_dir().join("state.json")
+    }
+
+    /// Loads the saved state, falling back to defaults if there is none.
+    pub fn load() -> Self {
+        fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    /// Persists the state. Failures are logged only.
+    pub fn save(&self) {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let result = serde_json::to_string_pretty(self)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            error!("Failed to save app state '{}': {}", path.display(), e);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::ProcessType;
+
+/// A change worth telling the outside world about, e.g. a lab dashboard.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StatusEvent {
+    ProcessStarted { process_type: ProcessType, pid: u32 },
+    /// `exit_code` is `None` if the process was killed.
+    ProcessFinished { process_type: ProcessType, exit_code: Option<i32>, stopped: bool },
+    ProcessPaused { process_type: ProcessType },
+    ProcessResumed { process_type: ProcessType },
+    /// A recording session moved to a new phase.
+    SessionPhase {
+        phase: String,
+        /// Index in the dataset of the episode being (or last) recorded.
+        episode_index: Option<u64>,
+        kept: u32,
+        discarded: u32,
+        episodes: u32,
+    },
+    SessionEnded { kept: u32, discarded: u32 },
+}
+
+impl StatusEvent {
+    /// The last part of the topic the event is published under: `process`
+    /// for changes of the running process, `session` for episode events.
+    pub fn topic(&self) -> &'static str {
+        match self {
+            StatusEvent::ProcessStarted { .. }
+            | StatusEvent::ProcessFinished { .. }
+            | StatusEvent::ProcessPaused { .. }
+            | StatusEvent::ProcessResumed { .. } => "process",
+            StatusEvent::SessionPhase { .. } | StatusEvent::SessionEnded { .. } => "session",
+        }
+    }
+}
+
+/// The name of this machine, to tell launchers on different PCs apart.
+pub fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|h| h.trim().to_string())
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// A [`StatusEvent`] as published, with when and by whom it happened.
+#[derive(Serialize, Debug)]
+pub struct StatusMessage<'a> {
+    pub timestamp: DateTime<Local>,
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator: Option<&'a str>,
+    #[serde(flatten)]
+    pub event: &'a StatusEvent,
+}
@@ -0,0 +1,137 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::ProcessType;
+
+/// A `[[sweeps]]` config entry: a process launched once for every
+/// combination of the listed values, one run after the other.
+#[derive(Deserialize, Clone, Debug)]
+pub struct SweepConfig {
+    pub name: String,
+    /// The process type to launch, by config key, e.g. `"evaluate"`.
+    pub process: String,
+    /// Values to try. Keys starting with `--` set that flag in the command,
+    /// any other key fills the placeholder (or parameter) of that name.
+    #[serde(default)]
+    pub values: BTreeMap<String, Vec<String>>,
+}
+
+impl SweepConfig {
+    /// Every combination of the values, in order, each as `(key, value)` pairs.
+    pub fn grid(&self) -> Vec<Vec<(String, String)>> {
+        let mut grid = vec![Vec::new()];
+        for (key, values) in &self.values {
+            grid = grid
+                .into_iter()
+                .flat_map(|point: Vec<(String, String)>| {
+                    values.iter().map(move |value| {
+                        let mut point = point.clone();
+                        point.push((key.clone(), value.clone()));
+                        point
+                    })
+                })
+                .collect();
+        }
+        grid
+    }
+}
+
+/// Where a single run of a sweep is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunStatus {
+    Pending,
+    Running,
+    /// The process exited with this code; `None` if it was stopped or its
+    /// exit code is unknown.
+    Finished(Option<i32>),
+    /// The process could not be launched.
+    Failed(String),
+    /// Aborted before it started.
+    Skipped,
+}
+
+impl RunStatus {
+    pub fn describe(&self) -> String {
+        match self {
+            RunStatus::Pending => "pending".to_string(),
+            RunStatus::Running => "running".to_string(),
+            RunStatus::Finished(Some(0)) => "ok".to_string(),
+            RunStatus::Finished(Some(code)) => format!("exit code {}", code),
+            RunStatus::Finished(None) => "stopped".to_string(),
+            RunStatus::Failed(e) => format!("failed: {}", e),
+            RunStatus::Skipped => "skipped".to_string(),
+        }
+    }
+}
+
+/// One combination of values of a sweep.
+pub struct SweepRun {
+    pub values: Vec<(String, String)>,
+    pub status: RunStatus,
+    /// Index of the run in the history, once launched.
+    pub history_index: Option<usize>,
+}
+
+/// A sweep in progress.
+pub struct Sweep {
+    pub name: String,
+    pub process_type: ProcessType,
+    pub runs: Vec<SweepRun>,
+    /// Whether this is an A/B comparison of two runs rather than a configured sweep.
+    pub comparison: bool,
+}
+
+impl Sweep {
+    pub fn new(config: &SweepConfig, process_type: ProcessType) -> Self {
+        Self::from_grid(config.name.clone(), process_type, config.grid())
+    }
+
+    /// Runs `process_type` once with each of `values`, in order.
+    pub fn from_grid(name: String, process_type: ProcessType, grid: Vec<Vec<(String, String)>>) -> Self {
+        Self {
+            name,
+            process_type,
+            runs: grid
+                .into_iter()
+                .map(|values| SweepRun { values, status: RunStatus::Pending, history_index: None })
+                .collect(),
+            comparison: false,
+        }
+    }
+
+    /// The run to launch next, if any is left.
+    pub fn next_pending(&mut self) -> Option<&mut SweepRun> {
+        self.runs.iter_mut().find(|r| r.status == RunStatus::Pending)
+    }
+
+    /// The run currently running, if any.
+    pub fn running(&mut self) -> Option<&mut SweepRun> {
+        self.runs.iter_mut().find(|r| r.status == RunStatus::Running)
+    }
+
+    /// Skips the runs that have not started yet; the running one carries on.
+    pub fn abort(&mut self) {
+        for run in &mut self.runs {
+            if run.status == RunStatus::Pending {
+                run.status = RunStatus::Skipped;
+            }
+        }
+    }
+
+    /// Whether every run has finished, failed or been skipped.
+    pub fn done(&self) -> bool {
+        self.runs.iter().all(|r| !matches!(r.status, RunStatus::Pending | RunStatus::Running))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_runs_are_not_reported_as_ok() {
+        assert_eq!(RunStatus::Finished(Some(0)).describe(), "ok");
+        assert_eq!(RunStatus::Finished(Some(1)).describe(), "exit code 1");
+        assert_eq!(RunStatus::Finished(None).describe(), "stopped");
+    }
+}
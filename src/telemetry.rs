@@ -0,0 +1,234 @@
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use serde::Deserialize;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Layer, Registry};
+
+use crate::paths;
+
+/// Name the app reports itself as to the tracing backend.
+const SERVICE_NAME: &str = "teleop-record-replay";
+
+/// The `[logging]` config table: the app's own log, as also set from the GUI.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct LoggingConfig {
+    /// Verbosity, `error` to `trace`, or `RUST_LOG`-style directives such as
+    /// `info,teleop_record_replay=debug`. `RUST_LOG` (or `error`) if unset.
+    #[serde(default)]
+    pub level: Option<String>,
+    /// A file the log is also appended to.
+    #[serde(default)]
+    pub file: Option<String>,
+}
+
+/// The levels offered in the GUI.
+pub const LEVELS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+
+/// Keeps the trace exporter alive; dropping it sends the spans not yet sent.
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+    control: LogControl,
+}
+
+impl Telemetry {
+    /// A handle changing the log level and file while the app runs.
+    pub fn control(&self) -> LogControl {
+        self.control.clone()
+    }
+}
+
+/// The file the log is also written to, if any.
+type LogFile = Arc<Mutex<Option<(PathBuf, File)>>>;
+
+/// Writes to the log file, or nowhere if there is none.
+struct LogFileWriter(LogFile);
+
+impl Write for LogFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some((_, file)) = self.0.lock().unwrap().as_mut() {
+            file.write_all(buf)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.0.lock().unwrap().as_mut() {
+            Some((_, file)) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Changes the level and destination of the console log at runtime.
+#[derive(Clone)]
+pub struct LogControl {
+    filter: reload::Handle<EnvFilter, Registry>,
+    file: LogFile,
+    /// The level the app was started with, used when none is configured.
+    initial_level: String,
+    /// The JSON log file given on the command line, if any.
+    json_file: Option<PathBuf>,
+}
+
+impl LogControl {
+    /// The current level (or directives).
+    pub fn level(&self) -> String {
+        self.filter.with_current(|f| f.to_string()).unwrap_or_default()
+    }
+
+    pub fn set_level(&self, level: &str) -> Result<(), String> {
+        let filter = EnvFilter::try_new(level).map_err(|e| format!("Invalid log level '{}': {}", level, e))?;
+        self.filter.reload(filter).map_err(|e| format!("Failed to change the log level: {}", e))?;
+        info!("Log level set to '{}'", level);
+        Ok(())
+    }
+
+    /// The file the log is also written to, if any.
+    pub fn file(&self) -> Option<PathBuf> {
+        self.file.lock().unwrap().as_ref().map(|(path, _)| path.clone())
+    }
+
+    /// Every file the log is written to.
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.file().into_iter().chain(self.json_file.clone()).collect()
+    }
+
+    /// Also appends the log to `path` from now on, or stops writing to a file.
+    pub fn set_file(&self, path: Option<&Path>) -> Result<(), String> {
+        let file = match path {
+            Some(path) => {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    std::fs::create_dir_all(parent)
+                        .map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+                }
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| format!("Failed to open log file '{}': {}", path.display(), e))?;
+                Some((path.to_path_buf(), file))
+            }
+            None => None,
+        };
+        *self.file.lock().unwrap() = file;
+        if let Some(path) = path {
+            info!("Logging to '{}'", path.display());
+        }
+        Ok(())
+    }
+
+    /// Applies the `[logging]` table, e.g. after the config was (re)loaded.
+    pub fn apply(&self, config: &LoggingConfig) -> Result<(), String> {
+        self.set_level(config.level.as_deref().unwrap_or(&self.initial_level))?;
+        let file = config.file.as_deref().map(|f| PathBuf::from(paths::expand(f)));
+        if file != self.file() {
+            self.set_file(file.as_deref())?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to send the remaining traces: {}", e);
+            }
+        }
+    }
+}
+
+/// The filter of the JSON log file and the exporter: `RUST_LOG`, or `info`.
+/// The console keeps `env_logger`'s default of errors only.
+fn filter(default: &str) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default))
+}
+
+/// Sets up logging: human-readable to stderr (and the file set through
+/// [`LogControl`], at the same level), as JSON lines appended to
+/// `json_file` if given, and spans exported over OTLP/HTTP if
+/// `OTEL_EXPORTER_OTLP_ENDPOINT` (or `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`) is set.
+/// `log` records, e.g. from eframe, go through the same outputs.
+pub fn init(json_file: Option<&Path>) -> Telemetry {
+    let mut problems = Vec::new();
+    let json = json_file.and_then(|path| match OpenOptions::new().create(true).append(true).open(path) {
+        Ok(file) => Some(
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .with_writer(Mutex::new(file))
+                .with_filter(filter("info")),
+        ),
+        Err(e) => {
+            problems.push(format!("Failed to open log file '{}': {}", path.display(), e));
+            None
+        }
+    });
+
+    let endpoint_set = ["OTEL_EXPORTER_OTLP_ENDPOINT", "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()));
+    let provider = endpoint_set
+        .then(|| opentelemetry_otlp::SpanExporter::builder().with_http().build())
+        .and_then(|exporter| exporter.map_err(|e| problems.push(format!("Failed to set up OTLP export: {}", e))).ok())
+        .map(|exporter| {
+            SdkTracerProvider::builder()
+                .with_batch_exporter(exporter)
+                .with_resource(Resource::builder().with_service_name(SERVICE_NAME).build())
+                .build()
+        });
+    let otel = provider.as_ref().map(|provider| {
+        // The exporter's own logging must not feed back into it.
+        let filter = ["opentelemetry=off", "opentelemetry_sdk=off", "reqwest=off", "hyper_util=off"]
+            .iter()
+            .fold(filter("info"), |filter, directive| filter.add_directive(directive.parse().unwrap()));
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME)).with_filter(filter)
+    });
+
+    let initial_level = filter("error").to_string();
+    let (console_filter, handle) = reload::Layer::new(filter("error"));
+    let file: LogFile = Arc::default();
+    let file_writer = file.clone();
+    let console = tracing_subscriber::fmt::layer()
+        .with_writer(io::stderr)
+        .and_then(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(move || LogFileWriter(file_writer.clone())),
+        )
+        .with_filter(console_filter);
+
+    tracing_subscriber::registry().with(console).with(json).with(otel).init();
+    for problem in problems {
+        error!("{}", problem);
+    }
+    let json_file = json_file.map(Path::to_path_buf);
+    Telemetry { provider, control: LogControl { filter: handle, file, initial_level, json_file } }
+}
+
+/// The W3C `traceparent` of `span`, passed to launched commands as
+/// `TRACEPARENT` so what they report can join the trace of their run.
+/// `None` unless spans are exported.
+pub fn traceparent(span: &tracing::Span) -> Option<String> {
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let context = span.context();
+    let span_context = context.span().span_context().clone();
+    span_context.is_valid().then(|| {
+        format!(
+            "00-{}-{}-{:02x}",
+            span_context.trace_id(),
+            span_context.span_id(),
+            span_context.trace_flags().to_u8()
+        )
+    })
+}
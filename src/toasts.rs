@@ -0,0 +1,38 @@
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+/// How long a toast stays on screen.
+const LIFETIME: Duration = Duration::from_secs(6);
+
+/// Short messages shown for a few seconds in the corner of the window, for
+/// things that went right and need no answer.
+#[derive(Default)]
+pub struct Toasts {
+    shown: Vec<(String, Instant)>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, text: String) {
+        self.shown.push((text, Instant::now()));
+    }
+
+    /// Shows the toasts that are not expired yet, newest at the bottom.
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.shown.retain(|(_, at)| at.elapsed() < LIFETIME);
+        if self.shown.is_empty() {
+            return;
+        }
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -12.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                for (text, _) in &self.shown {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(egui::RichText::new(text).color(egui::Color32::LIGHT_GREEN));
+                    });
+                }
+            });
+        ctx.request_repaint_after(Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,43 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// A link to an experiment tracker dashboard printed by a run.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TrackerLink {
+    /// What the link points to, e.g. "W&B run".
+    pub label: String,
+    pub url: String,
+}
+
+fn url_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r#"https?://[^\s'"<>()\[\]]+"#).unwrap())
+}
+
+/// Finds Weights & Biases and MLflow run, project and experiment URLs in a
+/// line of output, e.g. wandb's "View run at https://wandb.ai/..." or
+/// LeRobot's "Track this run --> https://wandb.ai/...".
+pub fn find(line: &str) -> Vec<TrackerLink> {
+    url_pattern()
+        .find_iter(line)
+        .filter_map(|m| {
+            let url = m.as_str().trim_end_matches(['.', ',', ';', ':']);
+            let kind = if url.contains("wandb.ai/") {
+                "W&B"
+            } else if url.contains("#/experiments/") {
+                "MLflow"
+            } else {
+                return None;
+            };
+            let what = if url.contains("/runs/") {
+                "run"
+            } else if kind == "MLflow" {
+                "experiment"
+            } else {
+                "project"
+            };
+            Some(TrackerLink { label: format!("{} {}", kind, what), url: url.to_string() })
+        })
+        .collect()
+}
@@ -0,0 +1,383 @@
+use eframe::egui;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+use tracing::{error, info};
+
+/// The `[updates]` config table: newer releases are looked for on startup if set.
+#[derive(Deserialize, Clone, Debug)]
+pub struct UpdatesConfig {
+    /// The GitHub repository releases are published in, `owner/name`.
+    #[serde(default = "default_repo")]
+    pub repo: String,
+    /// Name of the release asset holding the binary for this machine, with
+    /// `{os}` and `{arch}` filled in as in `linux` and `x86_64`. The release
+    /// must also publish its SHA-256 as the asset of the same name ending in
+    /// `.sha256`, as written by `sha256sum`.
+    #[serde(default = "default_asset")]
+    pub asset: String,
+}
+
+fn default_repo() -> String {
+    "wddler/teleop-record-replay".to_string()
+}
+
+fn default_asset() -> String {
+    "teleop-record-replay-{arch}-{os}".to_string()
+}
+
+impl UpdatesConfig {
+    fn asset_name(&self) -> String {
+        self.asset.replace("{os}", std::env::consts::OS).replace("{arch}", std::env::consts::ARCH)
+    }
+}
+
+/// A release newer than the running version.
+#[derive(Clone, Debug)]
+pub struct Release {
+    pub version: String,
+    /// The release page, with its notes.
+    pub url: String,
+    /// Where the binary for this machine is downloaded from, if there is one.
+    pub download: Option<String>,
+    /// Where the SHA-256 of the binary is downloaded from, if published.
+    pub checksum: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    html_url: String,
+    #[serde(default)]
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// `1.2.3` (or `v1.2.3`) as numbers; a pre-release suffix is ignored.
+/// Trailing zeros are dropped so `1.2` and `1.2.0` compare equal.
+fn parse_version(version: &str) -> Vec<u64> {
+    let mut parts: Vec<u64> = version
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+    while parts.last() == Some(&0) {
+        parts.pop();
+    }
+    parts
+}
+
+/// Whether `candidate` is a later version than `current`.
+fn is_newer(candidate: &str, current: &str) -> bool {
+    parse_version(candidate) > parse_version(current)
+}
+
+/// Asks GitHub for the latest release; `None` if it is not newer than this build.
+fn latest(config: &UpdatesConfig) -> Result<Option<Release>, String> {
+    let url = format!("https://api.github.com/repos/{}/releases/latest", config.repo);
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", "20", "-H", "Accept: application/vnd.github+json", &url])
+        .output()
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to check for updates: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let release: GithubRelease =
+        serde_json::from_slice(&output.stdout).map_err(|e| format!("Unexpected answer from GitHub: {}", e))?;
+    if !is_newer(&release.tag_name, env!("CARGO_PKG_VERSION")) {
+        return Ok(None);
+    }
+    let asset = config.asset_name();
+    let url_of = |name: &str| release.assets.iter().find(|a| a.name == name).map(|a| a.browser_download_url.clone());
+    Ok(Some(Release {
+        version: release.tag_name.trim_start_matches('v').to_string(),
+        download: url_of(&asset),
+        checksum: url_of(&format!("{}.sha256", asset)),
+        url: release.html_url,
+    }))
+}
+
+/// Downloads `release` and puts it in place of the running binary, which
+/// keeps running until the app is restarted. The download must match the
+/// published SHA-256 before it is run at all, and then run and report the
+/// new version before it replaces anything.
+fn install(release: &Release) -> Result<PathBuf, String> {
+    let url = release.download.as_deref().ok_or("The release has no binary for this machine.")?;
+    let checksum_url = release.checksum.as_deref().ok_or("The release publishes no SHA-256 of its binary.")?;
+    let expected = fetch(checksum_url).and_then(|text| parse_checksum(&text))?;
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to find the running binary: {}", e))?;
+    // Next to the binary, so it can be renamed over it.
+    let download = exe.with_extension("download");
+    let result = download_to(url, &download)
+        .and_then(|()| verify(&download, &expected))
+        .and_then(|()| make_executable(&download))
+        .and_then(|()| check_version(&download, &release.version));
+    let result = result.and_then(|()| {
+        fs::rename(&download, &exe).map_err(|e| format!("Failed to replace '{}': {}", exe.display(), e))
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&download);
+    }
+    result.map(|()| exe)
+}
+
+fn download_to(url: &str, path: &Path) -> Result<(), String> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", "600", "-o"])
+        .arg(path)
+        .arg(url)
+        .output()
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Failed to download '{}': {}", url, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+/// The contents of the small text file at `url`.
+fn fetch(url: &str) -> Result<String, String> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", "60", url])
+        .output()
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Failed to download '{}': {}", url, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The SHA-256 in a checksum file as written by `sha256sum`, `<hex>  <file>`,
+/// in lowercase.
+fn parse_checksum(text: &str) -> Result<String, String> {
+    let hash = text.split_whitespace().next().unwrap_or_default().to_lowercase();
+    if hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(hash)
+    } else {
+        Err("The published SHA-256 of the release is malformed.".to_string())
+    }
+}
+
+/// The SHA-256 of the file at `path`, in lowercase hex.
+fn sha256(path: &Path) -> Result<String, String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Checks that the file at `path` has the SHA-256 `expected`.
+fn verify(path: &Path, expected: &str) -> Result<(), String> {
+    let actual = sha256(path)?;
+    if actual != expected {
+        return Err(format!(
+            "The download has SHA-256 {} instead of the published {}; not installing it.",
+            actual, expected
+        ));
+    }
+    Ok(())
+}
+
+fn make_executable(path: &Path) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to make '{}' executable: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Checks that the binary at `path` runs and is `version`.
+fn check_version(path: &Path, version: &str) -> Result<(), String> {
+    let output = Command::new(path)
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("The downloaded binary does not run: {}", e))?;
+    let reported = String::from_utf8_lossy(&output.stdout);
+    match reported.split_whitespace().last() {
+        Some(v) if v == version => Ok(()),
+        _ => Err(format!("The downloaded binary reports '{}' instead of version {}", reported.trim(), version)),
+    }
+}
+
+/// What the updater is doing.
+#[derive(Default)]
+enum State {
+    #[default]
+    Idle,
+    Checking(Receiver<Result<Option<Release>, String>>),
+    Available(Release),
+    Installing(Release, Receiver<Result<PathBuf, String>>),
+    /// Installed; used after a restart.
+    Installed(String),
+    Failed(String),
+    Dismissed,
+}
+
+/// Looks for and installs newer releases in the background.
+#[derive(Default)]
+pub struct Updater {
+    state: State,
+    started: bool,
+}
+
+impl Updater {
+    /// Starts the check, once.
+    pub fn start(&mut self, config: &UpdatesConfig, ctx: &egui::Context) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        let (sender, receiver) = mpsc::channel();
+        let config = config.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = sender.send(latest(&config));
+            ctx.request_repaint();
+        });
+        self.state = State::Checking(receiver);
+    }
+
+    fn install(&mut self, release: Release, ctx: &egui::Context) {
+        info!("Installing version {}", release.version);
+        let (sender, receiver) = mpsc::channel();
+        let ctx = ctx.clone();
+        let to_install = release.clone();
+        std::thread::spawn(move || {
+            let _ = sender.send(install(&to_install));
+            ctx.request_repaint();
+        });
+        self.state = State::Installing(release, receiver);
+    }
+
+    /// Picks up finished work and shows the notice, if there is anything to say.
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        match &self.state {
+            State::Checking(receiver) => match receiver.try_recv() {
+                Ok(Ok(Some(release))) => {
+                    info!("Version {} is available", release.version);
+                    self.state = State::Available(release);
+                }
+                Ok(Ok(None)) => self.state = State::Idle,
+                // Being offline is no reason to bother the operator.
+                Ok(Err(e)) => {
+                    error!("{}", e);
+                    self.state = State::Idle;
+                }
+                Err(_) => {}
+            },
+            State::Installing(release, receiver) => match receiver.try_recv() {
+                Ok(Ok(exe)) => {
+                    info!("Installed version {} to '{}'", release.version, exe.display());
+                    self.state = State::Installed(release.version.clone());
+                }
+                Ok(Err(e)) => {
+                    error!("{}", e);
+                    self.state = State::Failed(e);
+                }
+                Err(_) => {}
+            },
+            _ => {}
+        }
+
+        let mut next = None;
+        match &self.state {
+            State::Available(release) => {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::LIGHT_BLUE, format!("Version {} is available.", release.version));
+                    if ui.link("Release notes").clicked() {
+                        ui.ctx().open_url(egui::OpenUrl::new_tab(&release.url));
+                    }
+                    if release.download.is_some() && ui.button("Update").clicked() {
+                        next = Some(Some(release.clone()));
+                    }
+                    if ui.button("Dismiss").clicked() {
+                        next = Some(None);
+                    }
+                });
+            }
+            State::Installing(release, _) => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(format!("Installing version {}...", release.version));
+                });
+            }
+            State::Installed(version) => {
+                let text = format!("Version {} is installed; restart to use it.", version);
+                ui.colored_label(egui::Color32::LIGHT_BLUE, text);
+            }
+            State::Failed(e) => {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::RED, format!("Update failed: {}", e));
+                    if ui.button("Dismiss").clicked() {
+                        next = Some(None);
+                    }
+                });
+            }
+            _ => {}
+        }
+        match next {
+            Some(Some(release)) => self.install(release, ui.ctx()),
+            Some(None) => self.state = State::Dismissed,
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn versions_are_compared_as_numbers() {
+        assert_eq!(parse_version("v1.10.2"), [1, 10, 2]);
+        assert_eq!(parse_version("2.0.1-rc.1+build5"), [2, 0, 1]);
+        assert_eq!(parse_version("1.x.3"), [1, 0, 3]);
+        assert!(parse_version("").is_empty());
+        assert!(is_newer("1.10.0", "1.9.9"));
+        assert!(is_newer("2", "1.99"));
+        assert!(!is_newer("1.2.3", "1.2.3"));
+        // A pre-release of the running version is not an update.
+        assert!(!is_newer("1.2.3-rc1", "1.2.3"));
+    }
+
+    #[test]
+    fn versions_differing_in_trailing_zeros_are_equal() {
+        assert!(!is_newer("v1.2.0", "1.2"));
+        assert!(!is_newer("1.2", "1.2.0"));
+        assert!(is_newer("1.2.1", "1.2"));
+    }
+
+    #[test]
+    fn checksums_are_read_as_sha256sum_writes_them() {
+        let line = format!("{}  teleop-record-replay-x86_64-linux\n", ABC.to_uppercase());
+        assert_eq!(parse_checksum(&line).as_deref(), Ok(ABC));
+        assert!(parse_checksum("").is_err());
+        assert!(parse_checksum("not-a-hash  file").is_err());
+    }
+
+    #[test]
+    fn downloads_must_match_the_published_checksum() {
+        let path = std::env::temp_dir().join(format!("teleop-update-test-{}", std::process::id()));
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(verify(&path, ABC), Ok(()));
+        assert!(verify(&path, &"0".repeat(64)).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
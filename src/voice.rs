@@ -0,0 +1,119 @@
+use eframe::egui;
+use serde::Deserialize;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use tracing::{debug, info};
+
+use crate::sandbox;
+
+/// The `[voice]` config table: hotwords starting and stopping recordings, for
+/// operators whose hands are on the leader arms.
+///
+/// Recognition is left to an on-device keyword spotter run as `command`, such
+/// as PocketSphinx or Vosk, so no audio leaves the machine and the app does
+/// not link against a speech library.
+#[derive(Deserialize, Clone, Debug)]
+pub struct VoiceConfig {
+    /// Listens to the microphone and prints a line for each phrase it hears.
+    pub command: String,
+    /// Phrases that start recording: launch Record, or end the reset or
+    /// countdown of a recording session.
+    #[serde(default = "default_start")]
+    pub start: Vec<String>,
+    /// Phrases that stop: end the episode being recorded as Esc would, or
+    /// stop any other process.
+    #[serde(default = "default_stop")]
+    pub stop: Vec<String>,
+}
+
+fn default_start() -> Vec<String> {
+    vec!["start recording".to_string()]
+}
+
+fn default_stop() -> Vec<String> {
+    vec!["stop".to_string()]
+}
+
+/// What a heard phrase asks for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VoiceCommand {
+    Start,
+    Stop,
+}
+
+impl VoiceConfig {
+    /// The command `line` of the keyword spotter's output asks for, if any.
+    /// Matching ignores case and surrounding text, so JSON output works too;
+    /// stop phrases win if both match.
+    fn command_in(&self, line: &str) -> Option<VoiceCommand> {
+        let line = line.to_lowercase();
+        let heard =
+            |phrases: &[String]| phrases.iter().any(|p| !p.trim().is_empty() && line.contains(&p.to_lowercase()));
+        if heard(&self.stop) {
+            Some(VoiceCommand::Stop)
+        } else if heard(&self.start) {
+            Some(VoiceCommand::Start)
+        } else {
+            None
+        }
+    }
+}
+
+/// Runs the keyword spotter for as long as it lives.
+pub struct VoiceListener {
+    child: Child,
+    commands: Receiver<VoiceCommand>,
+    /// The command heard last, shown so the operator knows it was understood.
+    pub last: Option<VoiceCommand>,
+}
+
+impl VoiceListener {
+    /// Starts the keyword spotter; `ctx` is woken up when a command is heard
+    /// so it is acted on even while the GUI is idle.
+    pub fn start(config: &VoiceConfig, ctx: egui::Context) -> Result<Self, String> {
+        let mut child = sandbox::bash(&config.command)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to run the keyword spotter: {}", e))?;
+        let stdout = child.stdout.take().ok_or_else(|| "No output from the keyword spotter".to_string())?;
+        let (sender, commands) = mpsc::channel();
+        let config = config.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                debug!("Keyword spotter: {}", line);
+                let Some(command) = config.command_in(&line) else { continue };
+                if sender.send(command).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            }
+        });
+        info!("Listening for voice commands");
+        Ok(Self { child, commands, last: None })
+    }
+
+    /// The command heard since the last call, if any.
+    pub fn heard(&mut self) -> Option<VoiceCommand> {
+        let command = self.commands.try_iter().last();
+        if command.is_some() {
+            self.last = command;
+        }
+        command
+    }
+
+    /// Whether the keyword spotter is still running.
+    pub fn running(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+}
+
+impl Drop for VoiceListener {
+    fn drop(&mut self) {
+        // The spotter may be a pipeline; it must not keep the microphone.
+        let _ = crate::process::signal_children(self.child.id(), "TERM");
+        let _ = sandbox::kill(&mut self.child);
+        let _ = self.child.wait();
+    }
+}
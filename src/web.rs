@@ -0,0 +1,191 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{debug, error, info};
+
+use crate::history::{History, RunRecord};
+use crate::{output, status};
+
+/// The `[web]` config table: a read-only status page, to check on a
+/// data-collection session from another room. It has no authentication, so
+/// it should only be reachable from the lab network.
+#[derive(Deserialize, Clone, Debug)]
+pub struct WebConfig {
+    /// Where the page is served, e.g. `0.0.0.0:8787` for every interface.
+    #[serde(default = "default_address")]
+    pub address: String,
+}
+
+fn default_address() -> String {
+    "0.0.0.0:8787".to_string()
+}
+
+/// Lines of output shown of the current (or last) run.
+const TAIL_LINES: usize = 40;
+/// Runs listed in the history.
+const RECENT_RUNS: usize = 20;
+/// How often the page reloads itself, in seconds.
+const REFRESH_SECONDS: u32 = 5;
+
+/// What the page shows, also served as JSON at `/api/status`.
+#[derive(Serialize)]
+struct Status {
+    host: String,
+    /// The run still going, i.e. the last one if it has not finished.
+    running: Option<RunRecord>,
+    /// The last lines of output of the running run, or else of the last one.
+    log: Vec<String>,
+    /// The latest runs, newest first.
+    recent: Vec<RunRecord>,
+}
+
+impl Status {
+    /// Read from the run history and the output logs on disk, which the GUI
+    /// keeps up to date, so serving never waits for the GUI.
+    fn load(history_path: PathBuf) -> Self {
+        let history = History::load(history_path);
+        let last = history.runs().last();
+        let log = match last.and_then(|r| r.log_file.as_ref()) {
+            Some(log_file) => output::tail(log_file, TAIL_LINES).unwrap_or_else(|e| vec![e]),
+            None => Vec::new(),
+        };
+        Self {
+            host: status::hostname(),
+            running: last.filter(|r| r.finished_at.is_none()).cloned(),
+            log,
+            recent: history.runs().iter().rev().take(RECENT_RUNS).cloned().collect(),
+        }
+    }
+}
+
+/// The web server, answering in the background for as long as the app runs.
+pub struct Server {
+    pub address: SocketAddr,
+}
+
+impl Server {
+    pub fn start(config: &WebConfig, history_path: PathBuf) -> Result<Self, String> {
+        let listener = TcpListener::bind(&config.address)
+            .map_err(|e| format!("Failed to serve the status page on '{}': {}", config.address, e))?;
+        let address = listener.local_addr().map_err(|e| e.to_string())?;
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let history_path = history_path.clone();
+                        std::thread::spawn(move || {
+                            if let Err(e) = respond(stream, history_path) {
+                                debug!("Status page request failed: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => error!("Failed to accept a status page connection: {}", e),
+                }
+            }
+        });
+        info!("Serving the status page on http://{}/", address);
+        Ok(Self { address })
+    }
+}
+
+/// Answers one request: `/` is the page, `/api/status` the same as JSON.
+fn respond(mut stream: TcpStream, history_path: PathBuf) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // The headers are of no interest, but are read so the client is not cut off.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let mut parts = request.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let path = target.split('?').next().unwrap_or_default();
+    let (code, content_type, body) = match (method, path) {
+        ("GET" | "HEAD", "/") => ("200 OK", "text/html; charset=utf-8", page(&Status::load(history_path))),
+        ("GET" | "HEAD", "/api/status") => {
+            let json = serde_json::to_string_pretty(&Status::load(history_path)).unwrap_or_default();
+            ("200 OK", "application/json", json)
+        }
+        ("GET" | "HEAD", _) => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "The status page is read-only\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        code,
+        content_type,
+        body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// The status page, reloading itself every few seconds.
+fn page(status: &Status) -> String {
+    let current = match &status.running {
+        Some(run) => format!(
+            "<p class=\"running\">Running <b>{:?}</b> since {}{}</p><p><code>{}</code></p>",
+            run.process_type,
+            run.started_at.format("%H:%M:%S"),
+            run.operator.as_ref().map(|o| format!(" (operator {})", escape(o))).unwrap_or_default(),
+            escape(&run.command)
+        ),
+        None => "<p>Idle</p>".to_string(),
+    };
+    let rows: String = status
+        .recent
+        .iter()
+        .map(|run| {
+            let duration = run.finished_at.map(|f| {
+                let seconds = (f - run.started_at).num_seconds().max(0);
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            });
+            format!(
+                "<tr><td>{}</td><td>{:?}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                run.started_at.format("%Y-%m-%d %H:%M"),
+                run.process_type,
+                escape(run.operator.as_deref().unwrap_or_default()),
+                duration.unwrap_or_default(),
+                escape(&run.outcome())
+            )
+        })
+        .collect();
+    format!(
+        r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><meta http-equiv="refresh" content="{refresh}">
+<title>{host}: teleop-record-replay</title>
+<style>
+body {{ font-family: sans-serif; margin: 1em 2em; }}
+.running {{ color: #c00; }}
+pre {{ background: #111; color: #ddd; padding: 0.5em; overflow-x: auto; }}
+td, th {{ padding: 0.2em 1em 0.2em 0; text-align: left; }}
+</style></head><body>
+<h1>{host}</h1>
+{current}
+<h2>Output</h2>
+<pre>{log}</pre>
+<h2>Recent runs</h2>
+<table><tr><th>Started</th><th>Process</th><th>Operator</th><th>Duration</th><th>Outcome</th></tr>
+{rows}</table>
+<p><small>Updated {now}</small></p>
+</body></html>
+"#,
+        refresh = REFRESH_SECONDS,
+        host = escape(&status.host),
+        current = current,
+        log = escape(&status.log.join("\n")),
+        rows = rows,
+        now = Local::now().format("%H:%M:%S")
+    )
+}